@@ -17,6 +17,13 @@ class Quaternion {
     }
 }
 
+class Ray {
+    constructor(origin, dir) {
+        this.origin = origin;
+        this.dir = dir;
+    }
+}
+
 function toEuler(quaternion) {
     const x = quaternion.x;
     const y = quaternion.y;
@@ -42,9 +49,213 @@ function toEuler(quaternion) {
     };
 }
 
+// User data crosses into Rust as a plain number, so only exact integers are accepted.
+function checkUserData(userData) {
+    if (!Number.isSafeInteger(userData) || userData < 0) {
+        throw new RangeError(`User data must be an integer from 0 to 2^53 - 1, got ${userData}`);
+    }
+    return userData;
+}
+
+// Accepts either a packed 16/16 bit value (as in rapier.js) or an object
+// carrying full 32-bit `memberships` and `filter` masks.
+function interactionGroups(groups) {
+    if (groups === undefined || groups === null) {
+        return [undefined, undefined];
+    }
+    if (typeof groups === "number") {
+        return [groups >>> 16, groups & 0xffff];
+    }
+    return [groups.memberships >>> 0, groups.filter >>> 0];
+}
+
+function shapeParams(shape) {
+    switch (shape.type) {
+        case "ball":
+            return [shape.radius];
+        case "cuboid":
+            return [shape.hx, shape.hy, shape.hz];
+        case "capsule":
+        case "cylinder":
+        case "cone":
+            return [shape.halfHeight, shape.radius];
+        case "roundCylinder":
+            return [shape.halfHeight, shape.radius, shape.borderRadius];
+        default:
+            throw new Error(`Unsupported query shape: ${shape.type}.`);
+    }
+}
+
+function colliderDescFromParams(shapeType, params) {
+    switch (shapeType) {
+        case 0:
+            return ColliderDesc.ball(params[0]);
+        case 1:
+            return ColliderDesc.cuboid(params[0], params[1], params[2]);
+        case 2:
+            return ColliderDesc.capsule(params[0], params[1]);
+        case 10:
+            return ColliderDesc.cylinder(params[0], params[1]);
+        case 11:
+            return ColliderDesc.cone(params[0], params[1]);
+        case 14:
+            return ColliderDesc.roundCylinder(params[0], params[1], params[2]);
+        default:
+            throw new Error(`Unsupported compound sub-shape: ${shapeType}.`);
+    }
+}
+
+function encodeCompoundShapes(shapes) {
+    const data = [];
+    for (const desc of shapes) {
+        const params = shapeParams(desc.shape);
+        const translation = desc.translation ?? new Vector3();
+        const rotation = desc.rotation ?? new Quaternion();
+        data.push(
+            desc.shape.shapeType,
+            params.length,
+            translation.x,
+            translation.y,
+            translation.z,
+            rotation.x,
+            rotation.y,
+            rotation.z,
+            rotation.w,
+            ...params,
+        );
+    }
+    return data;
+}
+
+function decodeCompoundShapes(data) {
+    const shapes = [];
+    let offset = 0;
+    while (offset + 9 <= data.length) {
+        const paramCount = data[offset + 1];
+        const params = data.slice(offset + 9, offset + 9 + paramCount);
+        const desc = colliderDescFromParams(data[offset], params)
+            .setTranslation(data[offset + 2], data[offset + 3], data[offset + 4])
+            .setRotation(
+                new Quaternion(
+                    data[offset + 5],
+                    data[offset + 6],
+                    data[offset + 7],
+                    data[offset + 8],
+                ),
+            );
+        shapes.push(desc);
+        offset += 9 + paramCount;
+    }
+    return shapes;
+}
+
+function decodeJointData(data) {
+    const anchor1 = new Vector3(data[3], data[4], data[5]);
+    const anchor2 = new Vector3(data[6], data[7], data[8]);
+    switch (data[0]) {
+        case 0:
+            return JointData.revolute(
+                anchor1,
+                anchor2,
+                new Vector3(data[9], data[10], data[11]),
+            );
+        case 1:
+            return JointData.fixed(
+                anchor1,
+                new Quaternion(data[9], data[10], data[11], data[12]),
+                anchor2,
+                new Quaternion(data[13], data[14], data[15], data[16]),
+            );
+        case 2:
+            return JointData.prismatic(
+                anchor1,
+                anchor2,
+                new Vector3(data[9], data[10], data[11]),
+            );
+        case 3:
+            return JointData.spherical(anchor1, anchor2);
+        case 4:
+            return JointData.rope(data[9], anchor1, anchor2);
+        case 5:
+            return JointData.spring(data[9], data[10], data[11], anchor1, anchor2);
+        default:
+            throw new Error(`Unsupported joint type code: ${data[0]}.`);
+    }
+}
+
+function readSnapshotHeader(snapshot) {
+    const header = nativeRapier.readSnapshotHeader(snapshot);
+    if (header.length === 0) {
+        return null;
+    }
+    return {
+        formatVersion: header[0],
+        precision: header[1],
+        compression: header[2],
+        rapierVersion: `${header[3]}.${header[4]}.${header[5]}`,
+        payloadLength: header[6],
+        rawLength: header[7],
+        crc32: header[8],
+        kind: header[9],
+    };
+}
+
+const SnapshotErrorCode = {
+    UNKNOWN_WORLD: -1,
+    TRUNCATED: 1,
+    BAD_MAGIC: 2,
+    UNSUPPORTED_FORMAT_VERSION: 3,
+    PRECISION_MISMATCH: 4,
+    RAPIER_VERSION_MISMATCH: 5,
+    LENGTH_MISMATCH: 6,
+    CHECKSUM_MISMATCH: 7,
+    PAYLOAD: 8,
+    UNKNOWN_COMPRESSION: 9,
+    DECOMPRESSION: 10,
+    RAW_LENGTH_TOO_LARGE: 11,
+    UNEXPECTED_KIND: 12,
+    SCENE_REQUIRED: 13,
+    SCENE_MISMATCH: 14,
+    BASE_REQUIRED: 15,
+    BASE_MISMATCH: 16,
+};
+
+const SnapshotCompression = {
+    NONE: 0,
+    LZ4: 1,
+    ZSTD: 2,
+};
+
+const SnapshotKind = {
+    WORLD: 0,
+    SCENE: 1,
+    DYNAMIC: 2,
+    DELTA: 3,
+};
+
+function checkSnapshotCompression(compression) {
+    if (!Object.values(SnapshotCompression).includes(compression)) {
+        throw new RangeError(`Unknown snapshot compression ${compression}`);
+    }
+}
+
+// `reference` is true when the scene or base passed along, not the snapshot itself, was
+// rejected.
+// `header` belongs to the rejected one and is null when it has no readable header.
+class SnapshotError extends Error {
+    constructor({ code, message, reference }, header) {
+        super(message);
+        this.name = "SnapshotError";
+        this.code = code;
+        this.reference = reference;
+        this.header = header;
+    }
+}
+
 class World {
     colliders = new Map();
     impulseJoints = new Map();
+    multibodyJoints = new Map();
 
     #rigidBodies = new Map();
     #timestep = 1 / 60;
@@ -52,13 +263,14 @@ class World {
     constructor(gravity) {
         const rigidBodies = this.#rigidBodies;
 
-        nativeRapier.initWorld(gravity.x, gravity.y, gravity.z);
+        const worldId = nativeRapier.initWorld(gravity.x, gravity.y, gravity.z);
+        this.id = worldId;
         this.integrationParameters = {
             /**
              * @param {number} value
              */
             set numSolverIterations(value) {
-                nativeRapier.setIntegrationParametersNumSolverIterations(value);
+                nativeRapier.setIntegrationParametersNumSolverIterations(worldId, value);
             },
             /**
              * @param {number} value
@@ -66,6 +278,7 @@ class World {
             set numAdditionalFrictionIterations(value) {
                 nativeRapier
                     .setIntegrationParametersNumAdditionalFrictionIterations(
+                        worldId,
                         value,
                     );
             },
@@ -74,6 +287,7 @@ class World {
              */
             set numInternalPgsIterations(value) {
                 nativeRapier.setIntegrationParametersNumInternalPgsIterations(
+                    worldId,
                     value,
                 );
             },
@@ -81,13 +295,13 @@ class World {
              * @param {number} value
              */
             set minIslandSize(value) {
-                nativeRapier.setIntegrationParametersMinIslandSize(value);
+                nativeRapier.setIntegrationParametersMinIslandSize(worldId, value);
             },
             /**
              * @param {number} value
              */
             set dt(value) {
-                nativeRapier.setIntegrationParametersDt(value);
+                nativeRapier.setIntegrationParametersDt(worldId, value);
             },
             switchToStandardPgsSolver() {
                 return nativeRapier.integrationParametersSwitchToStandardPgsSolver();
@@ -111,24 +325,18 @@ class World {
      */
     set timestep(value) {
         this.#timestep = value;
-        nativeRapier.setTimestep(value);
+        nativeRapier.setTimestep(this.id, value);
     }
 
     createRigidBody(rigidBodyDesc) {
-        let handle;
-        switch (rigidBodyDesc.bodyType) {
-            case "dynamic":
-                handle = nativeRapier.createDynamicBody(rigidBodyDesc);
-                break;
-            case "kinematic":
-                handle = nativeRapier.createKinematicBody(rigidBodyDesc);
-                break;
-            case "fixed":
-            default:
-                handle = nativeRapier.createFixedBody(rigidBodyDesc);
-                break;
+        const handle = nativeRapier.createBody(
+            this.id,
+            new Float64Array(rigidBodyDesc.encode()),
+        );
+        if (handle === null) {
+            throw new Error("Invalid rigid body descriptor");
         }
-        const body = new RigidBody(handle);
+        const body = new RigidBody(handle, this);
         this.#rigidBodies.set(handle, body);
         return body;
     }
@@ -143,6 +351,7 @@ class World {
         switch (colliderDesc.shape.type) {
             case "cuboid":
                 handle = nativeRapier.addBoxCollider(
+                    this.id,
                     body.handle,
                     colliderDesc.shape.hx,
                     colliderDesc.shape.hy,
@@ -158,6 +367,7 @@ class World {
                 break;
             case "cylinder":
                 handle = nativeRapier.addCylinderCollider(
+                    this.id,
                     body.handle,
                     colliderDesc.shape.halfHeight,
                     colliderDesc.shape.radius,
@@ -170,6 +380,99 @@ class World {
                     rotation?.z,
                 );
                 break;
+            case "ball":
+                handle = nativeRapier.addBallCollider(
+                    this.id,
+                    body.handle,
+                    colliderDesc.shape.radius,
+                    colliderDesc.sensor,
+                    translation?.x,
+                    translation?.y,
+                    translation?.z,
+                    rotation?.x,
+                    rotation?.y,
+                    rotation?.z,
+                );
+                break;
+            case "capsule":
+                handle = nativeRapier.addCapsuleCollider(
+                    this.id,
+                    body.handle,
+                    colliderDesc.shape.halfHeight,
+                    colliderDesc.shape.radius,
+                    colliderDesc.sensor,
+                    translation?.x,
+                    translation?.y,
+                    translation?.z,
+                    rotation?.x,
+                    rotation?.y,
+                    rotation?.z,
+                );
+                break;
+            case "cone":
+                handle = nativeRapier.addConeCollider(
+                    this.id,
+                    body.handle,
+                    colliderDesc.shape.halfHeight,
+                    colliderDesc.shape.radius,
+                    colliderDesc.sensor,
+                    translation?.x,
+                    translation?.y,
+                    translation?.z,
+                    rotation?.x,
+                    rotation?.y,
+                    rotation?.z,
+                );
+                break;
+            case "roundCylinder":
+                handle = nativeRapier.addRoundCylinderCollider(
+                    this.id,
+                    body.handle,
+                    colliderDesc.shape.halfHeight,
+                    colliderDesc.shape.radius,
+                    colliderDesc.shape.borderRadius,
+                    colliderDesc.sensor,
+                    translation?.x,
+                    translation?.y,
+                    translation?.z,
+                    rotation?.x,
+                    rotation?.y,
+                    rotation?.z,
+                );
+                break;
+            case "heightfield":
+                handle = nativeRapier.addHeightfieldCollider(
+                    this.id,
+                    body.handle,
+                    colliderDesc.shape.nrows,
+                    colliderDesc.shape.ncols,
+                    new Float64Array(colliderDesc.shape.heights),
+                    colliderDesc.shape.scale.x,
+                    colliderDesc.shape.scale.y,
+                    colliderDesc.shape.scale.z,
+                    colliderDesc.sensor,
+                    translation?.x,
+                    translation?.y,
+                    translation?.z,
+                    rotation?.x,
+                    rotation?.y,
+                    rotation?.z,
+                );
+                break;
+            case "compound":
+                handle = nativeRapier.addCompoundCollider(
+                    this.id,
+                    body.handle,
+                    new Float64Array(encodeCompoundShapes(colliderDesc.shape.shapes)),
+                    colliderDesc.sensor,
+                    translation?.x,
+                    translation?.y,
+                    translation?.z,
+                    rotation?.x,
+                    rotation?.y,
+                    rotation?.z,
+                );
+                break;
             case "trimesh":
                 const verticesTyped = new Float64Array(
                     colliderDesc.shape.vertices,
@@ -178,6 +481,7 @@ class World {
                     colliderDesc.shape.indices,
                 );
                 handle = nativeRapier.addTrimeshCollider(
+                    this.id,
                     body.handle,
                     verticesTyped,
                     indicesTyped,
@@ -196,6 +500,7 @@ class World {
                     colliderDesc.shape.vertices,
                 );
                 handle = nativeRapier.addConvexHullCollider(
+                    this.id,
                     body.handle,
                     convexVerticesTyped,
                     colliderDesc.sensor,
@@ -212,6 +517,9 @@ class World {
                     `Unsupported collider shape: ${colliderDesc.shape.type}.`,
                 );
         }
+        if (handle === null) {
+            throw new Error("Invalid collider descriptor");
+        }
         const collider = new Collider(handle, colliderDesc, this);
         body.colliders.push(collider);
         this.colliders.set(handle, collider);
@@ -223,6 +531,7 @@ class World {
         switch (jointData.type) {
             case "revolute":
                 handle = nativeRapier.createRevoluteJoint(
+                    this.id,
                     body1.handle,
                     body2.handle,
                     jointData.anchor1.x,
@@ -239,6 +548,7 @@ class World {
                 break;
             case "fixed":
                 handle = nativeRapier.createFixedJoint(
+                    this.id,
                     body1.handle,
                     body2.handle,
                     jointData.anchor1.x,
@@ -258,16 +568,256 @@ class World {
                     wakeUp,
                 );
                 break;
+            case "prismatic":
+                handle = nativeRapier.createPrismaticJoint(
+                    this.id,
+                    body1.handle,
+                    body2.handle,
+                    jointData.anchor1.x,
+                    jointData.anchor1.y,
+                    jointData.anchor1.z,
+                    jointData.anchor2.x,
+                    jointData.anchor2.y,
+                    jointData.anchor2.z,
+                    jointData.axis.x,
+                    jointData.axis.y,
+                    jointData.axis.z,
+                    wakeUp,
+                );
+                break;
+            case "spherical":
+                handle = nativeRapier.createSphericalJoint(
+                    this.id,
+                    body1.handle,
+                    body2.handle,
+                    jointData.anchor1.x,
+                    jointData.anchor1.y,
+                    jointData.anchor1.z,
+                    jointData.anchor2.x,
+                    jointData.anchor2.y,
+                    jointData.anchor2.z,
+                    wakeUp,
+                );
+                break;
+            case "rope":
+                handle = nativeRapier.createRopeJoint(
+                    this.id,
+                    body1.handle,
+                    body2.handle,
+                    jointData.anchor1.x,
+                    jointData.anchor1.y,
+                    jointData.anchor1.z,
+                    jointData.anchor2.x,
+                    jointData.anchor2.y,
+                    jointData.anchor2.z,
+                    jointData.length,
+                    wakeUp,
+                );
+                break;
+            case "spring":
+                handle = nativeRapier.createSpringJoint(
+                    this.id,
+                    body1.handle,
+                    body2.handle,
+                    jointData.anchor1.x,
+                    jointData.anchor1.y,
+                    jointData.anchor1.z,
+                    jointData.anchor2.x,
+                    jointData.anchor2.y,
+                    jointData.anchor2.z,
+                    jointData.restLength,
+                    jointData.stiffness,
+                    jointData.damping,
+                    wakeUp,
+                );
+                break;
             default:
                 throw new Error(`Unsupported joint type: ${jointData.type}`);
         }
-        const joint = new ImpulseJoint(handle, jointData, body1, body2);
+        if (handle === null) {
+            throw new Error("Invalid impulse joint descriptor");
+        }
+        const joint = new ImpulseJoint(handle, jointData, body1, body2, this);
         this.impulseJoints.set(handle, joint);
         return joint;
     }
 
+    /**
+     * Returns null when Rapier rejects the link, e.g. because it would close a loop.
+     */
+    createMultibodyJoint(jointData, body1, body2, wakeUp = true) {
+        let handle;
+        switch (jointData.type) {
+            case "revolute":
+                handle = nativeRapier.createMultibodyRevoluteJoint(
+                    this.id,
+                    body1.handle,
+                    body2.handle,
+                    jointData.anchor1.x,
+                    jointData.anchor1.y,
+                    jointData.anchor1.z,
+                    jointData.anchor2.x,
+                    jointData.anchor2.y,
+                    jointData.anchor2.z,
+                    jointData.axis.x,
+                    jointData.axis.y,
+                    jointData.axis.z,
+                    wakeUp,
+                );
+                break;
+            case "prismatic":
+                handle = nativeRapier.createMultibodyPrismaticJoint(
+                    this.id,
+                    body1.handle,
+                    body2.handle,
+                    jointData.anchor1.x,
+                    jointData.anchor1.y,
+                    jointData.anchor1.z,
+                    jointData.anchor2.x,
+                    jointData.anchor2.y,
+                    jointData.anchor2.z,
+                    jointData.axis.x,
+                    jointData.axis.y,
+                    jointData.axis.z,
+                    wakeUp,
+                );
+                break;
+            case "fixed":
+                handle = nativeRapier.createMultibodyFixedJoint(
+                    this.id,
+                    body1.handle,
+                    body2.handle,
+                    jointData.anchor1.x,
+                    jointData.anchor1.y,
+                    jointData.anchor1.z,
+                    jointData.anchor2.x,
+                    jointData.anchor2.y,
+                    jointData.anchor2.z,
+                    jointData.frame1.x,
+                    jointData.frame1.y,
+                    jointData.frame1.z,
+                    jointData.frame1.w,
+                    jointData.frame2.x,
+                    jointData.frame2.y,
+                    jointData.frame2.z,
+                    jointData.frame2.w,
+                    wakeUp,
+                );
+                break;
+            default:
+                throw new Error(`Unsupported multibody joint type: ${jointData.type}`);
+        }
+        if (handle === null) {
+            return null;
+        }
+        const joint = new MultibodyJoint(handle, jointData, body1, body2, this);
+        this.multibodyJoints.set(handle, joint);
+        return joint;
+    }
+
+    /**
+     * Also removes every impulse and multibody joint attached to `body`; Rapier cannot keep them.
+     * With `removeAttachedColliders` false, the body's colliders stay in the world without a parent.
+     */
+    removeRigidBody(body, removeAttachedColliders = true) {
+        nativeRapier.removeRigidBody(
+            this.id,
+            body.handle,
+            removeAttachedColliders,
+        );
+        this.#rigidBodies.delete(body.handle);
+        if (removeAttachedColliders) {
+            for (const collider of body.colliders) {
+                this.colliders.delete(collider.handle);
+            }
+        }
+        for (const [handle, joint] of this.impulseJoints) {
+            if (joint.body1() === body || joint.body2() === body) {
+                this.impulseJoints.delete(handle);
+            }
+        }
+        for (const [handle, joint] of this.multibodyJoints) {
+            if (joint.body1() === body || joint.body2() === body) {
+                this.multibodyJoints.delete(handle);
+            }
+        }
+    }
+
+    removeCollider(collider, wakeUp = true) {
+        nativeRapier.removeCollider(this.id, collider.handle, wakeUp);
+        this.colliders.delete(collider.handle);
+        for (const body of this.#rigidBodies.values()) {
+            const index = body.colliders.indexOf(collider);
+            if (index !== -1) {
+                body.colliders.splice(index, 1);
+            }
+        }
+    }
+
+    removeImpulseJoint(joint, wakeUp = true) {
+        nativeRapier.removeImpulseJoint(this.id, joint.handle, wakeUp);
+        this.impulseJoints.delete(joint.handle);
+    }
+
+    removeMultibodyJoint(joint, wakeUp = true) {
+        nativeRapier.removeMultibodyJoint(this.id, joint.handle, wakeUp);
+        this.multibodyJoints.delete(joint.handle);
+    }
+
     step(timestep = this.#timestep) {
-        nativeRapier.stepSimulation(timestep);
+        nativeRapier.stepSimulation(this.id, timestep);
+    }
+
+    bodyCount() {
+        return nativeRapier.getBodyCount(this.id);
+    }
+
+    bodyHandles() {
+        return nativeRapier.getBodyHandles(this.id);
+    }
+
+    bodyTranslationsArray() {
+        return nativeRapier.getBodyTranslations(this.id);
+    }
+
+    bodyRotationsArray() {
+        return nativeRapier.getBodyRotations(this.id);
+    }
+
+    /**
+     * Writes [x, y, z, qx, qy, qz, qw] for every body, in the order of `bodyHandles()`.
+     * Returns the number of bodies written, or -1 when the buffer is too small.
+     * @param {ArrayBuffer | Float32Array} buffer
+     */
+    writeBodyPoses(buffer) {
+        const view = buffer instanceof Float32Array ? buffer : new Float32Array(buffer);
+        return nativeRapier.writeBodyPoses(this.id, view);
+    }
+
+    /**
+     * Calls `onActive(body, x, y, z, qx, qy, qz, qw)` for every awake dynamic or kinematic body,
+     * then `onAsleep(body)` for bodies that fell asleep since the previous call.
+     */
+    drainActiveBodies(onActive, onAsleep) {
+        const data = nativeRapier.getActiveBodyPoses(this.id);
+        const activeEnd = 1 + data[0] * 8;
+        for (let i = 1; i < activeEnd; i += 8) {
+            onActive(
+                this.#rigidBodies.get(data[i]),
+                data[i + 1],
+                data[i + 2],
+                data[i + 3],
+                data[i + 4],
+                data[i + 5],
+                data[i + 6],
+                data[i + 7],
+            );
+        }
+        if (onAsleep) {
+            for (let i = activeEnd; i < data.length; i++) {
+                onAsleep(this.#rigidBodies.get(data[i]));
+            }
+        }
     }
 
     forEachCollider(callback) {
@@ -278,6 +828,7 @@ class World {
 
     intersectionPairsWith(collider, callback) {
         const collidersHandles = nativeRapier.intersectionPairsWith(
+            this.id,
             collider.handle,
         );
         for (const colliderHandle of collidersHandles) {
@@ -288,92 +839,441 @@ class World {
         }
     }
 
-    takeSnapshot() {
-        return new Uint8Array(nativeRapier.takeSnapshot());
+    castRay(ray, maxToi, solid, filterGroups, filterExcludeRigidBody) {
+        const hit = nativeRapier.castRay(
+            this.id,
+            ray.origin.x,
+            ray.origin.y,
+            ray.origin.z,
+            ray.dir.x,
+            ray.dir.y,
+            ray.dir.z,
+            maxToi,
+            solid,
+            ...interactionGroups(filterGroups),
+            filterExcludeRigidBody?.handle,
+        );
+        if (hit.length) {
+            return {
+                collider: this.colliders.get(hit[0]),
+                timeOfImpact: hit[1],
+            };
+        } else {
+            return null;
+        }
+    }
+
+    castRayAndGetNormal(
+        ray,
+        maxToi,
+        solid,
+        filterGroups,
+        filterExcludeRigidBody,
+    ) {
+        const hit = nativeRapier.castRayAndGetNormal(
+            this.id,
+            ray.origin.x,
+            ray.origin.y,
+            ray.origin.z,
+            ray.dir.x,
+            ray.dir.y,
+            ray.dir.z,
+            maxToi,
+            solid,
+            ...interactionGroups(filterGroups),
+            filterExcludeRigidBody?.handle,
+        );
+        if (hit.length) {
+            return {
+                collider: this.colliders.get(hit[0]),
+                timeOfImpact: hit[1],
+                normal: new Vector3(hit[2], hit[3], hit[4]),
+            };
+        } else {
+            return null;
+        }
+    }
+
+    castShape(
+        shapePos,
+        shapeRot,
+        shapeVel,
+        shape,
+        maxToi,
+        stopAtPenetration,
+        filterGroups,
+        filterExcludeRigidBody,
+    ) {
+        const hit = nativeRapier.castShape(
+            this.id,
+            shape.shapeType,
+            new Float64Array(shapeParams(shape)),
+            shapePos.x,
+            shapePos.y,
+            shapePos.z,
+            shapeRot.x,
+            shapeRot.y,
+            shapeRot.z,
+            shapeRot.w,
+            shapeVel.x,
+            shapeVel.y,
+            shapeVel.z,
+            maxToi,
+            stopAtPenetration,
+            ...interactionGroups(filterGroups),
+            filterExcludeRigidBody?.handle,
+        );
+        if (hit.length) {
+            return {
+                collider: this.colliders.get(hit[0]),
+                timeOfImpact: hit[1],
+                witness1: new Vector3(hit[2], hit[3], hit[4]),
+                witness2: new Vector3(hit[5], hit[6], hit[7]),
+                normal1: new Vector3(hit[8], hit[9], hit[10]),
+                normal2: new Vector3(hit[11], hit[12], hit[13]),
+            };
+        } else {
+            return null;
+        }
+    }
+
+    projectPoint(point, maxDist, solid, filterGroups) {
+        const projection = nativeRapier.projectPoint(
+            this.id,
+            point.x,
+            point.y,
+            point.z,
+            maxDist,
+            solid,
+            ...interactionGroups(filterGroups),
+        );
+        if (projection.length) {
+            return {
+                collider: this.colliders.get(projection[0]),
+                point: new Vector3(projection[1], projection[2], projection[3]),
+                isInside: projection[4] === 1,
+            };
+        } else {
+            return null;
+        }
+    }
+
+    intersectionsWithPoint(point, callback, filterGroups) {
+        const handles = nativeRapier.intersectionsWithPoint(
+            this.id,
+            point.x,
+            point.y,
+            point.z,
+            ...interactionGroups(filterGroups),
+        );
+        for (const handle of handles) {
+            callback(this.colliders.get(handle));
+        }
+    }
+
+    intersectionsWithShape(shapePos, shapeRot, shape, callback, filterGroups) {
+        const handles = nativeRapier.intersectionsWithShape(
+            this.id,
+            shape.shapeType,
+            new Float64Array(shapeParams(shape)),
+            shapePos.x,
+            shapePos.y,
+            shapePos.z,
+            shapeRot.x,
+            shapeRot.y,
+            shapeRot.z,
+            shapeRot.w,
+            ...interactionGroups(filterGroups),
+        );
+        for (const handle of handles) {
+            callback(this.colliders.get(handle));
+        }
+    }
+
+    findFreeSpawnPose(min, max, radius, halfHeight, filterGroups) {
+        const translation = nativeRapier.findFreeSpawnPose(
+            this.id,
+            min.x,
+            min.y,
+            min.z,
+            max.x,
+            max.y,
+            max.z,
+            radius,
+            halfHeight,
+            ...interactionGroups(filterGroups),
+        );
+        if (translation.length) {
+            return new Vector3(translation[0], translation[1], translation[2]);
+        } else {
+            return null;
+        }
+    }
+
+    bodiesUserData() {
+        const entries = nativeRapier.getBodiesUserData(this.id);
+        const userData = new Map();
+        for (let i = 0; i < entries.length; i += 2) {
+            userData.set(entries[i], entries[i + 1]);
+        }
+        return userData;
+    }
+
+    drainCollisionEvents(callback) {
+        const events = nativeRapier.drainCollisionEvents(this.id);
+        for (let i = 0; i < events.length; i += 5) {
+            const flags = events[i + 2];
+            callback(
+                events[i],
+                events[i + 1],
+                (flags & CollisionEventFlags.STARTED) !== 0,
+                flags,
+                events[i + 3],
+                events[i + 4],
+            );
+        }
+    }
+
+    drainContactForceEvents(callback) {
+        const events = nativeRapier.drainContactForceEvents(this.id);
+        for (let i = 0; i < events.length; i += 6) {
+            const maxForceDirection = new Vector3(
+                events[i + 3],
+                events[i + 4],
+                events[i + 5],
+            );
+            callback(events[i], events[i + 1], events[i + 2], maxForceDirection);
+        }
+    }
+
+    drainBrokenJoints(callback) {
+        const brokenJoints = nativeRapier.drainBrokenJoints(this.id);
+        for (let i = 0; i < brokenJoints.length; i += 5) {
+            const joint = this.impulseJoints.get(brokenJoints[i]);
+            this.impulseJoints.delete(brokenJoints[i]);
+            callback(joint, brokenJoints[i + 3], brokenJoints[i + 4]);
+        }
+    }
+
+    setCollidersContactIgnored(collider1, collider2, ignored = true) {
+        nativeRapier.setColliderPairIgnored(
+            this.id,
+            collider1.handle,
+            collider2.handle,
+            ignored,
+        );
+    }
+
+    free() {
+        nativeRapier.destroyWorld(this.id);
+    }
+
+    /**
+     * The compressed and raw payload sizes are reported by `World.readSnapshotHeader`.
+     * @param {number} compression One of `SnapshotCompression`.
+     */
+    takeSnapshot(compression = SnapshotCompression.NONE) {
+        checkSnapshotCompression(compression);
+        return new Uint8Array(nativeRapier.takeSnapshot(this.id, compression));
+    }
+
+    /**
+     * Captures the fixed bodies and the colliders that are parentless or attached to one.
+     * Later dynamic snapshots leave these out, so they must not change afterwards.
+     * @param {number} compression One of `SnapshotCompression`.
+     */
+    takeSceneSnapshot(compression = SnapshotCompression.NONE) {
+        checkSnapshotCompression(compression);
+        return new Uint8Array(nativeRapier.takeSceneSnapshot(this.id, compression));
+    }
+
+    /**
+     * Like `takeSnapshot`, but leaves out the scene last captured or restored.
+     * Returns an empty array when the world has no scene.
+     * @param {number} compression One of `SnapshotCompression`.
+     */
+    takeDynamicSnapshot(compression = SnapshotCompression.NONE) {
+        checkSnapshotCompression(compression);
+        return new Uint8Array(nativeRapier.takeDynamicSnapshot(this.id, compression));
+    }
+
+    /**
+     * Takes a snapshot and keeps the state of its bodies for `takeDeltaSnapshot` until released.
+     * @param {number} compression One of `SnapshotCompression`.
+     * @returns {{ id: number, snapshot: Uint8Array }}
+     */
+    takeBaseSnapshot(compression = SnapshotCompression.NONE) {
+        checkSnapshotCompression(compression);
+        const snapshot = new Uint8Array(nativeRapier.takeBaseSnapshot(this.id, compression));
+        return { id: readSnapshotHeader(snapshot)?.crc32 ?? null, snapshot };
+    }
+
+    releaseBaseSnapshot(baseId) {
+        return nativeRapier.releaseBaseSnapshot(this.id, baseId);
+    }
+
+    /**
+     * Encodes the bodies whose pose, velocity or sleep state changed since the base, and the
+     * bodies added and removed since. Returns an empty array for an unknown base.
+     * @param {number} compression One of `SnapshotCompression`.
+     */
+    takeDeltaSnapshot(baseId, compression = SnapshotCompression.NONE) {
+        checkSnapshotCompression(compression);
+        return new Uint8Array(nativeRapier.takeDeltaSnapshot(this.id, baseId, compression));
+    }
+
+    /**
+     * Restores `base` with the bodies of `delta` applied on top, bit for bit as they were when the
+     * delta was taken. Contacts and islands come from the base and catch up on the next step.
+     */
+    static applyDeltaSnapshot(base, delta) {
+        return World.restoreSnapshot(delta, base);
+    }
+
+    static readSnapshotHeader(snapshot) {
+        return readSnapshotHeader(snapshot);
+    }
+
+    /**
+     * Throws a `SnapshotError` saying why the snapshot was rejected.
+     * @param {Uint8Array} snapshot A whole world, dynamic or delta snapshot.
+     * @param {Uint8Array} [reference] The scene a dynamic snapshot or the base a delta snapshot
+     * was taken against.
+     */
+    static restoreSnapshot(snapshot, reference = null) {
+        const world = new World(new Vector3());
+        const error = nativeRapier.restoreSnapshot(world.id, snapshot, reference);
+        if (error !== null) {
+            world.free();
+            const rejected = error.reference ? reference : snapshot;
+            throw new SnapshotError(error, rejected && readSnapshotHeader(rejected));
+        }
+        world.#syncFromNative();
+        return world;
+    }
+
+    /**
+     * Exports the world as pretty-printed JSON; the schema is described in the README.
+     */
+    exportJson() {
+        const json = nativeRapier.exportWorldJson(this.id);
+        if (json === null) {
+            throw new Error("The world holds a shape that world JSON cannot describe");
+        }
+        return json;
     }
 
-    static restoreSnapshot(snapshot) {
+    /**
+     * Throws an `Error` saying why the JSON was rejected.
+     * @param {string} json A world in the schema described in the README.
+     */
+    static importJson(json) {
         const world = new World(new Vector3());
-        nativeRapier.restoreSnapshot(snapshot);
-        const bodies = nativeRapier.getWorldBodies();
+        const error = nativeRapier.importWorldJson(world.id, json);
+        if (error !== null) {
+            world.free();
+            throw new Error(`Invalid world JSON: ${error}`);
+        }
+        world.#syncFromNative();
+        return world;
+    }
+
+    #syncFromNative() {
+        const bodies = nativeRapier.getWorldBodies(this.id);
         for (const handle of bodies) {
-            const body = new RigidBody(handle);
-            world.#rigidBodies.set(handle, body);
+            const body = new RigidBody(handle, this);
+            this.#rigidBodies.set(handle, body);
         }
-        const colliders = nativeRapier.getWorldColliders();
+        const colliders = nativeRapier.getWorldColliders(this.id);
         for (const handle of colliders) {
             let colliderDesc;
-            const shapeType = nativeRapier.getColliderShapeType(handle);
+            const shapeType = nativeRapier.getColliderShapeType(this.id, handle);
             if (shapeType === 1) {
-                const extents = nativeRapier.getColliderHalfExtents(handle);
+                const extents = nativeRapier.getColliderHalfExtents(this.id, handle);
                 colliderDesc = ColliderDesc.cuboid(
                     extents[0],
                     extents[1],
                     extents[2],
                 );
             } else if (shapeType === 10) {
-                const radius = nativeRapier.getColliderRadius(handle);
-                const halfHeight = nativeRapier.getColliderHalfHeight(handle);
+                const radius = nativeRapier.getColliderRadius(this.id, handle);
+                const halfHeight = nativeRapier.getColliderHalfHeight(this.id, handle);
                 colliderDesc = ColliderDesc.cylinder(halfHeight, radius);
+            } else if (shapeType === 0) {
+                const radius = nativeRapier.getColliderRadius(this.id, handle);
+                colliderDesc = ColliderDesc.ball(radius);
+            } else if (shapeType === 2) {
+                const radius = nativeRapier.getColliderRadius(this.id, handle);
+                const halfHeight = nativeRapier.getColliderHalfHeight(this.id, handle);
+                colliderDesc = ColliderDesc.capsule(halfHeight, radius);
+            } else if (shapeType === 11) {
+                const radius = nativeRapier.getColliderRadius(this.id, handle);
+                const halfHeight = nativeRapier.getColliderHalfHeight(this.id, handle);
+                colliderDesc = ColliderDesc.cone(halfHeight, radius);
+            } else if (shapeType === 14) {
+                const radius = nativeRapier.getColliderRadius(this.id, handle);
+                const halfHeight = nativeRapier.getColliderHalfHeight(this.id, handle);
+                const borderRadius = nativeRapier.getColliderBorderRadius(this.id, handle);
+                colliderDesc = ColliderDesc.roundCylinder(halfHeight, radius, borderRadius);
+            } else if (shapeType === 7) {
+                const data = nativeRapier.getColliderHeightfield(this.id, handle);
+                colliderDesc = ColliderDesc.heightfield(
+                    data[0],
+                    data[1],
+                    data.slice(5),
+                    new Vector3(data[2], data[3], data[4]),
+                );
+            } else if (shapeType === 8) {
+                const data = nativeRapier.getColliderCompoundShapes(this.id, handle);
+                colliderDesc = ColliderDesc.compound(decodeCompoundShapes(data));
             } else if (shapeType === 6) {
-                const vertices = nativeRapier.getColliderVertices(handle);
-                const indices = nativeRapier.getColliderIndices(handle);
-                const flags = nativeRapier.getColliderFlags(handle);
+                const vertices = nativeRapier.getColliderVertices(this.id, handle);
+                const indices = nativeRapier.getColliderIndices(this.id, handle);
+                const flags = nativeRapier.getColliderFlags(this.id, handle);
                 colliderDesc = ColliderDesc.trimesh(vertices, indices, flags);
             } else if (shapeType === 9) {
-                const vertices = nativeRapier.getColliderVertices(handle);
+                const vertices = nativeRapier.getColliderVertices(this.id, handle);
                 colliderDesc = ColliderDesc.convexHull(
                     vertices,
-                    nativeRapier.getColliderIndices(handle),
+                    nativeRapier.getColliderIndices(this.id, handle),
                 );
             }
-            const collider = new Collider(handle, colliderDesc, world);
-            world.colliders.set(handle, collider);
+            const collider = new Collider(handle, colliderDesc, this);
+            this.colliders.set(handle, collider);
         }
-        for (const body of world.#rigidBodies.values()) {
+        for (const body of this.#rigidBodies.values()) {
             for (
                 let colliderIndex = 0;
-                colliderIndex < nativeRapier.getBodyNumColliders(body.handle);
+                colliderIndex < nativeRapier.getBodyNumColliders(this.id, body.handle);
                 colliderIndex++
             ) {
                 const handle = nativeRapier.getBodyCollider(
+                    this.id,
                     body.handle,
                     colliderIndex,
                 );
-                const collider = world.colliders.get(handle);
+                const collider = this.colliders.get(handle);
                 body.colliders.push(collider);
             }
         }
-        const joints = nativeRapier.getWorldImpulseJoints();
+        const joints = nativeRapier.getWorldImpulseJoints(this.id);
         for (const handle of joints) {
-            let jointData;
-            const data = nativeRapier.getJointData(handle);
-            const body1 = world.#rigidBodies.get(data[1]);
-            const body2 = world.#rigidBodies.get(data[2]);
-            const anchor1 = new Vector3(data[3], data[4], data[5]);
-            const anchor2 = new Vector3(data[6], data[7], data[8]);
-            if (data[0] === 0) {
-                const axis = new Vector3(data[9], data[10], data[11]);
-                jointData = JointData.revolute(anchor1, anchor2, axis);
-            } else {
-                const frame1 = new Quaternion(
-                    data[9],
-                    data[10],
-                    data[11],
-                    data[12],
-                );
-                const frame2 = new Quaternion(
-                    data[13],
-                    data[14],
-                    data[15],
-                    data[16],
-                );
-                jointData = JointData.fixed(anchor1, frame1, anchor2, frame2);
-            }
-            const joint = new ImpulseJoint(handle, jointData, body1, body2);
-            world.impulseJoints.set(handle, joint);
+            const data = nativeRapier.getJointData(this.id, handle);
+            const body1 = this.#rigidBodies.get(data[1]);
+            const body2 = this.#rigidBodies.get(data[2]);
+            const jointData = decodeJointData(data);
+            const joint = new ImpulseJoint(handle, jointData, body1, body2, this);
+            this.impulseJoints.set(handle, joint);
+        }
+        const multibodyJoints = nativeRapier.getWorldMultibodyJoints(this.id);
+        for (const handle of multibodyJoints) {
+            const data = nativeRapier.getMultibodyJointData(this.id, handle);
+            const body1 = this.#rigidBodies.get(data[1]);
+            const body2 = this.#rigidBodies.get(data[2]);
+            const jointData = decodeJointData(data);
+            const joint = new MultibodyJoint(handle, jointData, body1, body2, this);
+            this.multibodyJoints.set(handle, joint);
         }
-        return world;
     }
 
     connectBodiesWithRevoluteJoint({ body1, body2, anchor1, anchor2, axis }) {
@@ -387,27 +1287,57 @@ class World {
         const jointData = JointData.fixed(anchor1, frame1, anchor2, frame2);
         return this.createImpulseJoint(jointData, body1, body2);
     }
+
+    connectBodiesWithPrismaticJoint({ body1, body2, anchor1, anchor2, axis }) {
+        const jointData = JointData.prismatic(anchor1, anchor2, axis);
+        return this.createImpulseJoint(jointData, body1, body2);
+    }
+
+    connectBodiesWithSphericalJoint({ body1, body2, anchor1, anchor2 }) {
+        const jointData = JointData.spherical(anchor1, anchor2);
+        return this.createImpulseJoint(jointData, body1, body2);
+    }
+
+    connectBodiesWithRopeJoint({ body1, body2, anchor1, anchor2, length }) {
+        const jointData = JointData.rope(length, anchor1, anchor2);
+        return this.createImpulseJoint(jointData, body1, body2);
+    }
+
+    connectBodiesWithSpringJoint(
+        { body1, body2, anchor1, anchor2, restLength, stiffness, damping },
+    ) {
+        const jointData = JointData.spring(
+            restLength,
+            stiffness,
+            damping,
+            anchor1,
+            anchor2,
+        );
+        return this.createImpulseJoint(jointData, body1, body2);
+    }
 }
 
 class RigidBody {
     colliders = [];
 
-    constructor(handle) {
+    constructor(handle, world) {
         this.handle = handle;
+        this.world = world;
     }
 
     translation() {
-        const pos = nativeRapier.getBodyTranslation(this.handle);
+        const pos = nativeRapier.getBodyTranslation(this.world.id, this.handle);
         return new Vector3(pos[0], pos[1], pos[2]);
     }
 
     rotation() {
-        const rot = nativeRapier.getBodyRotation(this.handle);
+        const rot = nativeRapier.getBodyRotation(this.world.id, this.handle);
         return new Quaternion(rot[0], rot[1], rot[2], rot[3]);
     }
 
     setTranslation(vector, wakeUp = true) {
         nativeRapier.setBodyTranslation(
+            this.world.id,
             this.handle,
             vector.x,
             vector.y,
@@ -419,6 +1349,7 @@ class RigidBody {
 
     setRotation(quaternion, wakeUp = true) {
         nativeRapier.setBodyRotation(
+            this.world.id,
             this.handle,
             quaternion.x,
             quaternion.y,
@@ -430,12 +1361,13 @@ class RigidBody {
     }
 
     linvel() {
-        const vel = nativeRapier.getBodyVelocity(this.handle);
+        const vel = nativeRapier.getBodyVelocity(this.world.id, this.handle);
         return new Vector3(vel[0], vel[1], vel[2]);
     }
 
     setLinvel(vector3, wakeUp = true) {
         nativeRapier.setBodyVelocity(
+            this.world.id,
             this.handle,
             vector3.x,
             vector3.y,
@@ -445,12 +1377,13 @@ class RigidBody {
     }
 
     angvel() {
-        const vel = nativeRapier.getBodyAngularVelocity(this.handle);
+        const vel = nativeRapier.getBodyAngularVelocity(this.world.id, this.handle);
         return new Vector3(vel[0], vel[1], vel[2]);
     }
 
     setAngvel(vector3, wakeUp = true) {
         nativeRapier.setBodyAngularVelocity(
+            this.world.id,
             this.handle,
             vector3.x,
             vector3.y,
@@ -461,6 +1394,7 @@ class RigidBody {
 
     setNextKinematicTranslation(vector3) {
         nativeRapier.setBodyNextKinematicTranslation(
+            this.world.id,
             this.handle,
             vector3.x,
             vector3.y,
@@ -470,6 +1404,7 @@ class RigidBody {
 
     setNextKinematicRotation(quaternion) {
         nativeRapier.setBodyNextKinematicRotation(
+            this.world.id,
             this.handle,
             quaternion.x,
             quaternion.y,
@@ -478,27 +1413,145 @@ class RigidBody {
         );
     }
 
-    applyImpulse(vector3, wakeUp = true) {
-        nativeRapier.applyImpulse(
+    applyImpulse(vector3, wakeUp = true) {
+        nativeRapier.applyImpulse(
+            this.world.id,
+            this.handle,
+            vector3.x,
+            vector3.y,
+            vector3.z,
+            wakeUp,
+        );
+    }
+
+    addForce(force, wakeUp = true) {
+        nativeRapier.addForce(
+            this.world.id,
+            this.handle,
+            force.x,
+            force.y,
+            force.z,
+            wakeUp,
+        );
+    }
+
+    addTorque(torque, wakeUp = true) {
+        nativeRapier.addTorque(
+            this.world.id,
+            this.handle,
+            torque.x,
+            torque.y,
+            torque.z,
+            wakeUp,
+        );
+    }
+
+    applyTorqueImpulse(torqueImpulse, wakeUp = true) {
+        nativeRapier.applyTorqueImpulse(
+            this.world.id,
+            this.handle,
+            torqueImpulse.x,
+            torqueImpulse.y,
+            torqueImpulse.z,
+            wakeUp,
+        );
+    }
+
+    applyImpulseAtPoint(impulse, point, wakeUp = true) {
+        nativeRapier.applyImpulseAtPoint(
+            this.world.id,
+            this.handle,
+            impulse.x,
+            impulse.y,
+            impulse.z,
+            point.x,
+            point.y,
+            point.z,
+            wakeUp,
+        );
+    }
+
+    addForceAtPoint(force, point, wakeUp = true) {
+        nativeRapier.addForceAtPoint(
+            this.world.id,
+            this.handle,
+            force.x,
+            force.y,
+            force.z,
+            point.x,
+            point.y,
+            point.z,
+            wakeUp,
+        );
+    }
+
+    resetForces(wakeUp = true) {
+        nativeRapier.resetForces(
+            this.world.id,
+            this.handle,
+            wakeUp,
+        );
+    }
+
+    resetTorques(wakeUp = true) {
+        nativeRapier.resetTorques(
+            this.world.id,
+            this.handle,
+            wakeUp,
+        );
+    }
+
+    setGravityScale(scale, wakeUp = true) {
+        nativeRapier.setGravityScale(
+            this.world.id,
+            this.handle,
+            scale,
+            wakeUp,
+        );
+        return this;
+    }
+
+    setDominanceGroup(group) {
+        nativeRapier.setDominanceGroup(
+            this.world.id,
+            this.handle,
+            group,
+        );
+        return this;
+    }
+
+    lockRotations(locked, wakeUp = true) {
+        nativeRapier.lockRotations(
+            this.world.id,
+            this.handle,
+            locked,
+            wakeUp,
+        );
+        return this;
+    }
+
+    lockTranslations(locked, wakeUp = true) {
+        nativeRapier.lockTranslations(
+            this.world.id,
             this.handle,
-            vector3.x,
-            vector3.y,
-            vector3.z,
+            locked,
             wakeUp,
         );
+        return this;
     }
 
     setEnabled(enabled) {
-        nativeRapier.setBodyEnabled(this.handle, enabled);
+        nativeRapier.setBodyEnabled(this.world.id, this.handle, enabled);
         return this;
     }
 
     isEnabled() {
-        return nativeRapier.isBodyEnabled(this.handle);
+        return nativeRapier.isBodyEnabled(this.world.id, this.handle);
     }
 
     setEnabledRotations(enableX, enableY, enableZ, wakeUp = true) {
         nativeRapier.setBodyEnabledRotations(
+            this.world.id,
             this.handle,
             enableX,
             enableY,
@@ -510,6 +1563,7 @@ class RigidBody {
 
     setEnabledTranslations(enableX, enableY, enableZ, wakeUp = true) {
         nativeRapier.setBodyEnabledTranslations(
+            this.world.id,
             this.handle,
             enableX,
             enableY,
@@ -520,45 +1574,79 @@ class RigidBody {
     }
 
     setSoftCcdPrediction(prediction) {
-        nativeRapier.setBodySoftCcdPrediction(this.handle, prediction);
+        nativeRapier.setBodySoftCcdPrediction(this.world.id, this.handle, prediction);
         return this;
     }
 
     setAdditionalSolverIterations(iterations) {
-        nativeRapier.setBodyAdditionalSolverIterations(this.handle, iterations);
+        nativeRapier.setBodyAdditionalSolverIterations(this.world.id, this.handle, iterations);
         return this;
     }
 
     enableCcd(enabled) {
-        nativeRapier.setBodyCcdEnabled(this.handle, enabled);
+        nativeRapier.setBodyCcdEnabled(this.world.id, this.handle, enabled);
         return this;
     }
 
     setAngularDamping(damping) {
-        nativeRapier.setBodyAngularDamping(this.handle, damping);
+        nativeRapier.setBodyAngularDamping(this.world.id, this.handle, damping);
         return this;
     }
 
     setLinearDamping(damping) {
-        nativeRapier.setBodyLinearDamping(this.handle, damping);
+        nativeRapier.setBodyLinearDamping(this.world.id, this.handle, damping);
         return this;
     }
 
     sleep() {
-        nativeRapier.bodySleep(this.handle);
+        nativeRapier.bodySleep(this.world.id, this.handle);
         return this;
     }
 
     isSleeping() {
-        return nativeRapier.isBodySleeping(this.handle);
+        return nativeRapier.isBodySleeping(this.world.id, this.handle);
     }
 
     numColliders() {
         return this.colliders.length;
     }
 
+    setAdditionalMass(mass, wakeUp = true) {
+        nativeRapier.setBodyAdditionalMass(this.world.id, this.handle, mass, wakeUp);
+        return this;
+    }
+
+    centerOfMass() {
+        const center = nativeRapier.getBodyCenterOfMass(this.world.id, this.handle);
+        return new Vector3(center[0], center[1], center[2]);
+    }
+
+    localCenterOfMass() {
+        const center = nativeRapier.getBodyLocalCenterOfMass(this.world.id, this.handle);
+        return new Vector3(center[0], center[1], center[2]);
+    }
+
+    principalInertia() {
+        const inertia = nativeRapier.getBodyAngularInertia(this.world.id, this.handle);
+        return new Vector3(inertia[0], inertia[1], inertia[2]);
+    }
+
+    principalInertiaLocalFrame() {
+        const inertia = nativeRapier.getBodyAngularInertia(this.world.id, this.handle);
+        return new Quaternion(inertia[3], inertia[4], inertia[5], inertia[6]);
+    }
+
+    setUserData(userData) {
+        nativeRapier.setBodyUserData(this.world.id, this.handle, checkUserData(userData));
+        return this;
+    }
+
+    userData() {
+        return nativeRapier.getBodyUserData(this.world.id, this.handle);
+    }
+
     mass() {
-        return nativeRapier.getBodyMass(this.handle);
+        return nativeRapier.getBodyMass(this.world.id, this.handle);
     }
 
     collider(index) {
@@ -575,41 +1663,160 @@ class Collider {
     }
 
     setFriction(friction) {
-        nativeRapier.setColliderFriction(this.handle, friction);
+        nativeRapier.setColliderFriction(this.world.id, this.handle, friction);
         return this;
     }
 
     setRestitution(restitution) {
-        nativeRapier.setColliderRestitution(this.handle, restitution);
+        nativeRapier.setColliderRestitution(this.world.id, this.handle, restitution);
+        return this;
+    }
+
+    setMass(mass) {
+        nativeRapier.setColliderMass(this.world.id, this.handle, mass);
+        return this;
+    }
+
+    setMassProperties(mass, centerOfMass, principalAngularInertia, angularInertiaLocalFrame) {
+        nativeRapier.setColliderMassProperties(
+            this.world.id,
+            this.handle,
+            mass,
+            centerOfMass.x,
+            centerOfMass.y,
+            centerOfMass.z,
+            principalAngularInertia.x,
+            principalAngularInertia.y,
+            principalAngularInertia.z,
+            angularInertiaLocalFrame.x,
+            angularInertiaLocalFrame.y,
+            angularInertiaLocalFrame.z,
+            angularInertiaLocalFrame.w,
+        );
+        return this;
+    }
+
+    setUserData(userData) {
+        nativeRapier.setColliderUserData(this.world.id, this.handle, checkUserData(userData));
         return this;
     }
 
+    userData() {
+        return nativeRapier.getColliderUserData(this.world.id, this.handle);
+    }
+
     setDensity(density) {
-        nativeRapier.setColliderDensity(this.handle, density);
+        nativeRapier.setColliderDensity(this.world.id, this.handle, density);
         return this;
     }
 
     setCollisionGroups(groups) {
-        nativeRapier.setColliderCollisionGroups(this.handle, groups);
+        const [memberships, filter] = interactionGroups(groups);
+        nativeRapier.setColliderCollisionGroups(
+            this.world.id,
+            this.handle,
+            memberships,
+            filter,
+        );
+        return this;
+    }
+
+    setSolverGroups(groups) {
+        const [memberships, filter] = interactionGroups(groups);
+        nativeRapier.setColliderSolverGroups(
+            this.world.id,
+            this.handle,
+            memberships,
+            filter,
+        );
+        return this;
+    }
+
+    collisionGroups() {
+        const groups = nativeRapier.getColliderCollisionGroups(this.world.id, this.handle);
+        return { memberships: groups[0], filter: groups[1] };
+    }
+
+    solverGroups() {
+        const groups = nativeRapier.getColliderSolverGroups(this.world.id, this.handle);
+        return { memberships: groups[0], filter: groups[1] };
+    }
+
+    setActiveEvents(activeEvents) {
+        nativeRapier.setColliderActiveEvents(this.world.id, this.handle, activeEvents);
+        return this;
+    }
+
+    setContactForceEventThreshold(threshold) {
+        nativeRapier.setColliderContactForceEventThreshold(this.world.id, this.handle, threshold);
+        return this;
+    }
+
+    setActiveHooks(activeHooks) {
+        nativeRapier.setColliderActiveHooks(this.world.id, this.handle, activeHooks);
+        return this;
+    }
+
+    setOneWayDirection(direction, allowedAngle = 0) {
+        nativeRapier.setColliderOneWayDirection(
+            this.world.id,
+            this.handle,
+            direction.x,
+            direction.y,
+            direction.z,
+            allowedAngle,
+        );
+        return this;
+    }
+
+    removeOneWayDirection() {
+        nativeRapier.removeColliderOneWayDirection(this.world.id, this.handle);
+        return this;
+    }
+
+    setSurfaceVelocity(velocity) {
+        nativeRapier.setColliderSurfaceVelocity(
+            this.world.id,
+            this.handle,
+            velocity.x,
+            velocity.y,
+            velocity.z,
+        );
+        return this;
+    }
+
+    setSurfaceAngularVelocity(angularVelocity) {
+        nativeRapier.setColliderSurfaceAngularVelocity(
+            this.world.id,
+            this.handle,
+            angularVelocity.x,
+            angularVelocity.y,
+            angularVelocity.z,
+        );
+        return this;
+    }
+
+    removeSurfaceVelocity() {
+        nativeRapier.removeColliderSurfaceVelocity(this.world.id, this.handle);
         return this;
     }
 
     setContactSkin(skin) {
-        nativeRapier.setColliderContactSkin(this.handle, skin);
+        nativeRapier.setColliderContactSkin(this.world.id, this.handle, skin);
         return this;
     }
 
     isEnabled() {
-        return nativeRapier.isColliderEnabled(this.handle);
+        return nativeRapier.isColliderEnabled(this.world.id, this.handle);
     }
 
     setEnabled(enabled) {
-        nativeRapier.setColliderEnabled(this.handle, enabled);
+        nativeRapier.setColliderEnabled(this.world.id, this.handle, enabled);
         return this;
     }
 
     parent() {
-        const handle = nativeRapier.getColliderParent(this.handle);
+        const handle = nativeRapier.getColliderParent(this.world.id, this.handle);
         return this.world.bodies.get(handle);
     }
 
@@ -618,22 +1825,22 @@ class Collider {
     }
 
     translation() {
-        const pos = nativeRapier.getColliderTranslation(this.handle);
+        const pos = nativeRapier.getColliderTranslation(this.world.id, this.handle);
         return new Vector3(pos[0], pos[1], pos[2]);
     }
 
     rotation() {
-        const rot = nativeRapier.getColliderRotation(this.handle);
+        const rot = nativeRapier.getColliderRotation(this.world.id, this.handle);
         return new Quaternion(rot[0], rot[1], rot[2], rot[3]);
     }
 
     vertices() {
-        const vertices = nativeRapier.getColliderVertices(this.handle);
+        const vertices = nativeRapier.getColliderVertices(this.world.id, this.handle);
         return new Float64Array(vertices);
     }
 
     indices() {
-        const indices = nativeRapier.getColliderIndices(this.handle);
+        const indices = nativeRapier.getColliderIndices(this.world.id, this.handle);
         return new Uint32Array(indices);
     }
 
@@ -659,29 +1866,186 @@ class ImpulseJoint {
     #body1;
     #body2;
 
-    constructor(handle, jointData, body1, body2) {
+    constructor(handle, jointData, body1, body2, world) {
+        this.handle = handle;
+        this.world = world;
+        this.#jointData = jointData;
+        this.#body1 = body1;
+        this.#body2 = body2;
+    }
+
+    setLimits(min, max, axis = 0) {
+        switch (this.#jointData.type) {
+            case "revolute":
+                nativeRapier.setRevoluteJointLimits(this.world.id, this.handle, min, max);
+                break;
+            case "prismatic":
+                nativeRapier.setPrismaticJointLimits(this.world.id, this.handle, min, max);
+                break;
+            case "spherical":
+                nativeRapier.setSphericalJointLimits(
+                    this.world.id,
+                    this.handle,
+                    axis,
+                    min,
+                    max,
+                );
+                break;
+            default:
+                throw new Error(`Joint type ${this.#jointData.type} has no limits.`);
+        }
+        return this;
+    }
+
+    configureMotor(targetPos, targetVel, stiffness, damping, axis = 0) {
+        switch (this.#jointData.type) {
+            case "revolute":
+                nativeRapier.configureRevoluteJointMotor(
+                    this.world.id,
+                    this.handle,
+                    targetPos,
+                    targetVel,
+                    stiffness,
+                    damping,
+                );
+                break;
+            case "prismatic":
+                nativeRapier.configurePrismaticJointMotor(
+                    this.world.id,
+                    this.handle,
+                    targetPos,
+                    targetVel,
+                    stiffness,
+                    damping,
+                );
+                break;
+            case "spherical":
+                nativeRapier.configureSphericalJointMotor(
+                    this.world.id,
+                    this.handle,
+                    axis,
+                    targetPos,
+                    targetVel,
+                    stiffness,
+                    damping,
+                );
+                break;
+            default:
+                throw new Error(`Joint type ${this.#jointData.type} has no motor.`);
+        }
+        return this;
+    }
+
+    setMotorMaxForce(maxForce) {
+        nativeRapier.setPrismaticJointMotorMaxForce(this.world.id, this.handle, maxForce);
+        return this;
+    }
+
+    setMaxDistance(length) {
+        nativeRapier.setRopeJointMaxDistance(this.world.id, this.handle, length);
+        this.#jointData.length = length;
+        return this;
+    }
+
+    setSpringParameters(restLength, stiffness, damping) {
+        nativeRapier.configureSpringJoint(
+            this.world.id,
+            this.handle,
+            restLength,
+            stiffness,
+            damping,
+        );
+        this.#jointData.restLength = restLength;
+        this.#jointData.stiffness = stiffness;
+        this.#jointData.damping = damping;
+        return this;
+    }
+
+    impulses() {
+        const impulses = nativeRapier.getJointImpulses(this.world.id, this.handle);
+        return {
+            linear: new Vector3(impulses[0], impulses[1], impulses[2]),
+            angular: new Vector3(impulses[3], impulses[4], impulses[5]),
+        };
+    }
+
+    /**
+     * The joint breaks once the linear or the angular impulse it applies in one step exceeds
+     * the matching threshold. Leave either one undefined to never break on it.
+     */
+    setBreakThreshold(linear, angular) {
+        nativeRapier.setJointBreakThreshold(this.world.id, this.handle, linear, angular);
+        return this;
+    }
+
+    removeBreakThreshold() {
+        nativeRapier.removeJointBreakThreshold(this.world.id, this.handle);
+        return this;
+    }
+
+    anchor1() {
+        return this.#jointData.anchor1;
+    }
+
+    anchor2() {
+        return this.#jointData.anchor2;
+    }
+
+    body1() {
+        return this.#body1;
+    }
+
+    body2() {
+        return this.#body2;
+    }
+}
+
+class MultibodyJoint {
+    #jointData;
+    #body1;
+    #body2;
+
+    constructor(handle, jointData, body1, body2, world) {
         this.handle = handle;
+        this.world = world;
         this.#jointData = jointData;
         this.#body1 = body1;
         this.#body2 = body2;
     }
 
     setLimits(min, max) {
-        nativeRapier.setRevoluteJointLimits(this.handle, min, max);
+        nativeRapier.setMultibodyJointLimits(this.world.id, this.handle, min, max);
         return this;
     }
 
-    configureMotor(targetPos, targetVel, damping, maxForce) {
-        nativeRapier.configureRevoluteJointMotor(
+    configureMotor(targetPos, targetVel, stiffness, damping) {
+        nativeRapier.configureMultibodyJointMotor(
+            this.world.id,
             this.handle,
             targetPos,
             targetVel,
+            stiffness,
             damping,
-            maxForce,
         );
         return this;
     }
 
+    setMotorMaxForce(maxForce) {
+        nativeRapier.setMultibodyJointMotorMaxForce(this.world.id, this.handle, maxForce);
+        return this;
+    }
+
+    linkPosition() {
+        const position = nativeRapier.getMultibodyJointLinkPosition(
+            this.world.id,
+            this.handle,
+        );
+        return {
+            translation: new Vector3(position[0], position[1], position[2]),
+            rotation: new Quaternion(position[3], position[4], position[5], position[6]),
+        };
+    }
+
     anchor1() {
         return this.#jointData.anchor1;
     }
@@ -699,10 +2063,36 @@ class ImpulseJoint {
     }
 }
 
+const RigidBodyType = {
+    dynamic: 0,
+    fixed: 1,
+    kinematic: 2,
+    kinematicVelocityBased: 3,
+};
+
+const LockedAxes = {
+    TRANSLATION_LOCKED_X: 1,
+    TRANSLATION_LOCKED_Y: 2,
+    TRANSLATION_LOCKED_Z: 4,
+    ROTATION_LOCKED_X: 8,
+    ROTATION_LOCKED_Y: 16,
+    ROTATION_LOCKED_Z: 32,
+};
+
 class RigidBodyDesc {
     constructor() {
         this.translation = new Vector3();
         this.rotation = new Quaternion();
+        this.linvel = new Vector3();
+        this.angvel = new Vector3();
+        this.linearDamping = 0;
+        this.angularDamping = 0;
+        this.gravityScale = 1;
+        this.ccdEnabled = false;
+        this.sleeping = false;
+        this.canSleep = true;
+        this.lockedAxes = 0;
+        this.userData = 0;
     }
 
     static fixed() {
@@ -723,6 +2113,12 @@ class RigidBodyDesc {
         return desc;
     }
 
+    static kinematicVelocityBased() {
+        const desc = new RigidBodyDesc();
+        desc.bodyType = "kinematicVelocityBased";
+        return desc;
+    }
+
     setTranslation(x, y, z) {
         this.translation = new Vector3(x, y, z);
     }
@@ -731,6 +2127,109 @@ class RigidBodyDesc {
         this.rotation = new Quaternion(x, y, z, w);
         return this;
     }
+
+    setLinvel(x, y, z) {
+        this.linvel = new Vector3(x, y, z);
+        return this;
+    }
+
+    setAngvel(vector3) {
+        this.angvel = vector3;
+        return this;
+    }
+
+    setLinearDamping(damping) {
+        this.linearDamping = damping;
+        return this;
+    }
+
+    setAngularDamping(damping) {
+        this.angularDamping = damping;
+        return this;
+    }
+
+    setGravityScale(scale) {
+        this.gravityScale = scale;
+        return this;
+    }
+
+    setCcdEnabled(enabled) {
+        this.ccdEnabled = enabled;
+        return this;
+    }
+
+    setSleeping(sleeping) {
+        this.sleeping = sleeping;
+        return this;
+    }
+
+    setCanSleep(canSleep) {
+        this.canSleep = canSleep;
+        return this;
+    }
+
+    enabledTranslations(enableX, enableY, enableZ) {
+        this.lockedAxes &= ~(LockedAxes.TRANSLATION_LOCKED_X |
+            LockedAxes.TRANSLATION_LOCKED_Y |
+            LockedAxes.TRANSLATION_LOCKED_Z);
+        if (!enableX) this.lockedAxes |= LockedAxes.TRANSLATION_LOCKED_X;
+        if (!enableY) this.lockedAxes |= LockedAxes.TRANSLATION_LOCKED_Y;
+        if (!enableZ) this.lockedAxes |= LockedAxes.TRANSLATION_LOCKED_Z;
+        return this;
+    }
+
+    enabledRotations(enableX, enableY, enableZ) {
+        this.lockedAxes &= ~(LockedAxes.ROTATION_LOCKED_X |
+            LockedAxes.ROTATION_LOCKED_Y |
+            LockedAxes.ROTATION_LOCKED_Z);
+        if (!enableX) this.lockedAxes |= LockedAxes.ROTATION_LOCKED_X;
+        if (!enableY) this.lockedAxes |= LockedAxes.ROTATION_LOCKED_Y;
+        if (!enableZ) this.lockedAxes |= LockedAxes.ROTATION_LOCKED_Z;
+        return this;
+    }
+
+    lockTranslations() {
+        return this.enabledTranslations(false, false, false);
+    }
+
+    lockRotations() {
+        return this.enabledRotations(false, false, false);
+    }
+
+    setUserData(userData) {
+        this.userData = checkUserData(userData);
+        return this;
+    }
+
+    encode() {
+        if (!Object.hasOwn(RigidBodyType, this.bodyType)) {
+            throw new Error(`Unsupported rigid body type: ${this.bodyType}`);
+        }
+        return [
+            RigidBodyType[this.bodyType],
+            this.translation.x,
+            this.translation.y,
+            this.translation.z,
+            this.rotation.x,
+            this.rotation.y,
+            this.rotation.z,
+            this.rotation.w,
+            this.linvel.x,
+            this.linvel.y,
+            this.linvel.z,
+            this.angvel.x,
+            this.angvel.y,
+            this.angvel.z,
+            this.linearDamping,
+            this.angularDamping,
+            this.gravityScale,
+            this.ccdEnabled ? 1 : 0,
+            this.sleeping ? 1 : 0,
+            this.canSleep ? 1 : 0,
+            this.lockedAxes,
+            this.userData,
+        ];
+    }
 }
 
 class ColliderDesc {
@@ -746,6 +2245,55 @@ class ColliderDesc {
         this.rotation = undefined;
     }
 
+    static ball(radius) {
+        const desc = new ColliderDesc();
+        desc.shape = { type: "ball", radius, shapeType: 0 };
+        return desc;
+    }
+
+    static capsule(halfHeight, radius) {
+        const desc = new ColliderDesc();
+        desc.shape = { type: "capsule", halfHeight, radius, shapeType: 2 };
+        return desc;
+    }
+
+    static cone(halfHeight, radius) {
+        const desc = new ColliderDesc();
+        desc.shape = { type: "cone", halfHeight, radius, shapeType: 11 };
+        return desc;
+    }
+
+    static roundCylinder(halfHeight, radius, borderRadius) {
+        const desc = new ColliderDesc();
+        desc.shape = {
+            type: "roundCylinder",
+            halfHeight,
+            radius,
+            borderRadius,
+            shapeType: 14,
+        };
+        return desc;
+    }
+
+    static heightfield(nrows, ncols, heights, scale) {
+        const desc = new ColliderDesc();
+        desc.shape = {
+            type: "heightfield",
+            nrows,
+            ncols,
+            heights,
+            scale,
+            shapeType: 7,
+        };
+        return desc;
+    }
+
+    static compound(shapes) {
+        const desc = new ColliderDesc();
+        desc.shape = { type: "compound", shapes, shapeType: 8 };
+        return desc;
+    }
+
     static cuboid(hx, hy, hz) {
         const desc = new ColliderDesc();
         desc.shape = { type: "cuboid", hx, hy, hz, shapeType: 1 };
@@ -800,6 +2348,22 @@ class JointData {
     static fixed(anchor1, frame1, anchor2, frame2) {
         return { type: "fixed", anchor1, anchor2, frame1, frame2 };
     }
+
+    static prismatic(anchor1, anchor2, axis) {
+        return { type: "prismatic", anchor1, anchor2, axis };
+    }
+
+    static spherical(anchor1, anchor2) {
+        return { type: "spherical", anchor1, anchor2 };
+    }
+
+    static rope(length, anchor1, anchor2) {
+        return { type: "rope", anchor1, anchor2, length };
+    }
+
+    static spring(restLength, stiffness, damping, anchor1, anchor2) {
+        return { type: "spring", anchor1, anchor2, restLength, stiffness, damping };
+    }
 }
 
 const TriMeshFlags = {
@@ -807,4 +2371,38 @@ const TriMeshFlags = {
     FIX_INTERNAL_EDGES: 144,
 };
 
-export { ColliderDesc, JointData, RigidBodyDesc, TriMeshFlags, World };
+const ActiveEvents = {
+    NONE: 0,
+    COLLISION_EVENTS: 1,
+    CONTACT_FORCE_EVENTS: 2,
+};
+
+const ActiveHooks = {
+    NONE: 0,
+    FILTER_CONTACT_PAIRS: 1,
+    FILTER_INTERSECTION_PAIR: 2,
+    MODIFY_SOLVER_CONTACTS: 4,
+};
+
+const CollisionEventFlags = {
+    STARTED: 1,
+    SENSOR: 2,
+    REMOVED: 4,
+};
+
+export {
+    ActiveEvents,
+    ActiveHooks,
+    ColliderDesc,
+    CollisionEventFlags,
+    JointData,
+    LockedAxes,
+    Ray,
+    RigidBodyDesc,
+    SnapshotCompression,
+    SnapshotError,
+    SnapshotErrorCode,
+    SnapshotKind,
+    TriMeshFlags,
+    World,
+};
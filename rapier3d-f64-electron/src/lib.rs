@@ -1,9 +1,24 @@
 use bincode::{deserialize, serialize};
-use nalgebra::{Quaternion, Translation3, Unit, UnitQuaternion, Vector3};
+use nalgebra::{DMatrix, Quaternion, Translation3, Unit, UnitQuaternion, Vector3};
+use neon::context::{Context, Cx};
+use neon::handle::Handle;
+use neon::object::Object;
+use neon::result::JsResult;
+use neon::types::buffer::TypedArray;
+use neon::types::extract::Float64Array;
+use neon::types::{JsFloat64Array, JsValue};
 use rapier3d_f64::geometry::{InteractionGroups, TriMeshFlags};
+use rapier3d_f64::parry::query::{
+    PointProjection, ShapeCastHit, ShapeCastOptions, intersection_test,
+};
 use rapier3d_f64::prelude::*;
+use serde::de::DeserializeOwned;
 use serde::{Deserialize, Serialize};
+use std::cell::{Cell, RefCell};
+use std::collections::{BTreeMap, HashMap, HashSet};
 use std::num::NonZeroUsize;
+use std::rc::Rc;
+use std::sync::Mutex;
 
 pub struct World {
     rigid_body_set: RigidBodySet,
@@ -17,8 +32,16 @@ pub struct World {
     impulse_joint_set: ImpulseJointSet,
     multibody_joint_set: MultibodyJointSet,
     ccd_solver: CCDSolver,
-    physics_hooks: (),
-    event_handler: (),
+    physics_hooks: ContactHooks,
+    event_handler: EventQueue,
+    pending_colliders: Vec<ColliderHandle>,
+    joint_break_thresholds: HashMap<ImpulseJointHandle, JointBreakThreshold>,
+    broken_joints: Vec<BrokenJoint>,
+    reported_active_bodies: HashSet<RigidBodyHandle>,
+    // The scene last captured or restored, which dynamic snapshots leave out.
+    scene: Option<Rc<LoadedScene>>,
+    // Bases that delta snapshots can be taken against, by id.
+    base_snapshots: HashMap<u32, BaseSnapshot>,
 }
 
 #[derive(Serialize, Deserialize)]
@@ -32,2211 +55,7588 @@ pub struct SerializableWorld {
     colliders: ColliderSet,
     impulse_joints: ImpulseJointSet,
     multibody_joints: MultibodyJointSet,
+    joint_break_thresholds: Vec<(ImpulseJointHandle, JointBreakThreshold)>,
 }
 
-impl World {
-    pub fn new(gravity_x: Real, gravity_y: Real, gravity_z: Real) -> Self {
-        let integration_parameters = IntegrationParameters::default();
+// The static part of a world: fixed bodies and the colliders that are parentless or attached
+// to one of them, keyed by their handles in the world.
+#[derive(Serialize, Deserialize)]
+struct SerializableScene {
+    bodies: Vec<(RigidBodyHandle, RigidBody)>,
+    colliders: Vec<(ColliderHandle, Collider)>,
+}
+
+// A scene snapshot once decoded. Its id is the crc32 of the serialized scene, so the same scene
+// keeps its id whatever compression it is stored with.
+struct LoadedScene {
+    id: u32,
+    bodies: HashMap<RigidBodyHandle, RigidBody>,
+    colliders: HashMap<ColliderHandle, Collider>,
+}
+
+impl LoadedScene {
+    fn new(id: u32, scene: SerializableScene) -> Self {
         Self {
-            rigid_body_set: RigidBodySet::new(),
-            collider_set: ColliderSet::new(),
-            gravity: Vector3::new(gravity_x, gravity_y, gravity_z),
-            integration_parameters: integration_parameters,
-            physics_pipeline: PhysicsPipeline::new(),
-            island_manager: IslandManager::new(),
-            broad_phase: DefaultBroadPhase::new(),
-            narrow_phase: NarrowPhase::new(),
-            impulse_joint_set: ImpulseJointSet::new(),
-            multibody_joint_set: MultibodyJointSet::new(),
-            ccd_solver: CCDSolver::new(),
-            physics_hooks: (),
-            event_handler: (),
+            id,
+            bodies: scene.bodies.into_iter().collect(),
+            colliders: scene.colliders.into_iter().collect(),
         }
     }
+}
 
-    pub fn bodies(&self) -> &RigidBodySet {
-        &self.rigid_body_set
+// The scene snapshot last taken or decoded on this thread, keyed by the crc32 in its header.
+// `World.restoreSnapshot` builds a new world each time, so the cache cannot live in a world.
+thread_local! {
+    static SCENE_CACHE: RefCell<Option<(u32, Rc<LoadedScene>)>> = const { RefCell::new(None) };
+}
+
+// Decodes a scene snapshot, or reuses the cached one. The container is validated first, so a
+// damaged snapshot is rejected even when its crc32 is cached.
+fn load_scene(snapshot: &[u8]) -> Result<Rc<LoadedScene>, SnapshotError> {
+    let (header, compression) = check_snapshot(snapshot)?;
+    if header.magic != SCENE_SNAPSHOT_MAGIC {
+        return Err(SnapshotError::UnexpectedKind(header.magic));
+    }
+    let cached = SCENE_CACHE.with_borrow(|cache| {
+        cache
+            .as_ref()
+            .filter(|(crc32, _)| *crc32 == header.crc32)
+            .map(|(_, scene)| scene.clone())
+    });
+    if let Some(scene) = cached {
+        return Ok(scene);
+    }
+    let raw = decompress_snapshot(snapshot, &header, compression)?;
+    let scene = deserialize::<SerializableScene>(&raw).map_err(|_| SnapshotError::Payload)?;
+    let scene = Rc::new(LoadedScene::new(crc32fast::hash(&raw), scene));
+    SCENE_CACHE.set(Some((header.crc32, scene.clone())));
+    Ok(scene)
+}
+
+// Everything but the scene a dynamic snapshot was taken against. Scene objects keep their arena
+// slots as `None`; the broad phase is left out and rebuilt on restore.
+#[derive(Serialize, Deserialize)]
+struct SerializableDynamicWorld {
+    scene: u32,
+    gravity: Vector3<Real>,
+    integration_parameters: IntegrationParameters,
+    islands: IslandManager,
+    narrow_phase: NarrowPhase,
+    bodies: RigidBodySetLayout<Option<RigidBody>>,
+    colliders: ColliderSetLayout<Option<Collider>>,
+    impulse_joints: ImpulseJointSet,
+    multibody_joints: MultibodyJointSet,
+    joint_break_thresholds: Vec<(ImpulseJointHandle, JointBreakThreshold)>,
+}
+
+// Mirrors of the serialized layout of `RigidBodySet` and `ColliderSet`, whose arenas are
+// private. They let a dynamic snapshot drop scene objects while every other handle keeps its
+// slot and generation. `ModifiedObjects` serializes exactly like the `Vec` it wraps.
+#[derive(Serialize, Deserialize)]
+struct ArenaLayout<T> {
+    items: Vec<ArenaEntry<T>>,
+    generation: u32,
+    free_list_head: Option<u32>,
+    len: usize,
+}
+
+#[derive(Serialize, Deserialize)]
+enum ArenaEntry<T> {
+    Free { next_free: Option<u32> },
+    Occupied { generation: u32, value: T },
+}
+
+impl<T> ArenaLayout<T> {
+    // `f` gets the index and generation of each occupied slot; None from it aborts the map.
+    fn map<U>(self, mut f: impl FnMut(u32, u32, T) -> Option<U>) -> Option<ArenaLayout<U>> {
+        let items = self
+            .items
+            .into_iter()
+            .enumerate()
+            .map(|(index, entry)| match entry {
+                ArenaEntry::Free { next_free } => Some(ArenaEntry::Free { next_free }),
+                ArenaEntry::Occupied { generation, value } => Some(ArenaEntry::Occupied {
+                    generation,
+                    value: f(index as u32, generation, value)?,
+                }),
+            })
+            .collect::<Option<Vec<_>>>()?;
+        Some(ArenaLayout {
+            items,
+            generation: self.generation,
+            free_list_head: self.free_list_head,
+            len: self.len,
+        })
+    }
+
+    // Rewires the free list so that the next inserts land in `slots`, in order, and sets the
+    // generation they get. Slots past the end are added as free ones.
+    fn reserve_slots(&mut self, slots: &[u32], generation: u32) -> Option<()> {
+        let end = slots
+            .iter()
+            .map(|slot| *slot as usize + 1)
+            .max()
+            .unwrap_or(0);
+        while self.items.len() < end {
+            self.items.push(ArenaEntry::Free { next_free: None });
+        }
+        let is_free = |entry: &ArenaEntry<T>| matches!(entry, ArenaEntry::Free { .. });
+        if !slots
+            .iter()
+            .all(|slot| is_free(&self.items[*slot as usize]))
+        {
+            return None;
+        }
+        let others = (0..self.items.len() as u32)
+            .filter(|slot| !slots.contains(slot) && is_free(&self.items[*slot as usize]));
+        let order: Vec<u32> = slots.iter().copied().chain(others).collect();
+        for (position, slot) in order.iter().enumerate() {
+            self.items[*slot as usize] = ArenaEntry::Free {
+                next_free: order.get(position + 1).copied(),
+            };
+        }
+        self.free_list_head = order.first().copied();
+        self.generation = generation;
+        Some(())
     }
+}
 
-    pub fn create_dynamic_body(&mut self) -> f64 {
-        let rigid_body = RigidBodyBuilder::dynamic().build();
-        let handle = self.rigid_body_set.insert(rigid_body);
-        let (index, generation) = handle.into_raw_parts();
-        encode_handle_for_js(index, generation)
+#[derive(Serialize, Deserialize)]
+struct RigidBodySetLayout<T> {
+    bodies: ArenaLayout<T>,
+    modified_bodies: Vec<RigidBodyHandle>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct ColliderSetLayout<T> {
+    colliders: ArenaLayout<T>,
+    modified_colliders: Vec<ColliderHandle>,
+    removed_colliders: Vec<ColliderHandle>,
+}
+
+// Reads a value back as another type with the same serialized layout.
+fn reencode<T: Serialize, U: DeserializeOwned>(value: &T) -> Option<U> {
+    deserialize(&serialize(value).ok()?).ok()
+}
+
+// Takes the slot of a scene collider while a collider set goes through its layout, so that the
+// scene shapes are never serialized.
+fn scene_placeholder() -> Collider {
+    ColliderBuilder::ball(0.0).build()
+}
+
+// Puts the scene objects back into the slots a dynamic snapshot left empty. Returns None when a
+// slot has no scene object to fill it.
+fn rebuild_dynamic_sets(
+    scene: &LoadedScene,
+    bodies: RigidBodySetLayout<Option<RigidBody>>,
+    colliders: ColliderSetLayout<Option<Collider>>,
+) -> Option<(RigidBodySet, ColliderSet)> {
+    let bodies: RigidBodySet = reencode(&RigidBodySetLayout {
+        bodies: bodies.bodies.map(|index, generation, body| {
+            let handle = RigidBodyHandle::from_raw_parts(index, generation);
+            body.or_else(|| scene.bodies.get(&handle).cloned())
+        })?,
+        modified_bodies: bodies.modified_bodies,
+    })?;
+
+    // Scene colliders are swapped in after the set is rebuilt, so that their shapes are shared
+    // with the scene instead of going through bincode again.
+    let mut scene_colliders = Vec::new();
+    let mut colliders: ColliderSet = reencode(&ColliderSetLayout {
+        colliders: colliders.colliders.map(|index, generation, collider| {
+            let handle = ColliderHandle::from_raw_parts(index, generation);
+            match collider {
+                Some(collider) => Some(collider),
+                None => scene.colliders.contains_key(&handle).then(|| {
+                    scene_colliders.push(handle);
+                    scene_placeholder()
+                }),
+            }
+        })?,
+        modified_colliders: colliders.modified_colliders,
+        removed_colliders: colliders.removed_colliders,
+    })?;
+    for handle in scene_colliders {
+        *colliders.get_mut(handle)? = scene.colliders.get(&handle)?.clone();
+    }
+    // Flags every collider so that the broad phase picks it up.
+    for (_, collider) in colliders.iter_mut() {
+        let position = *collider.position();
+        collider.set_position(position);
+    }
+    Some((bodies, colliders))
+}
+
+#[derive(Clone, Copy, PartialEq, Debug, Serialize, Deserialize)]
+struct BodyState {
+    position: Isometry<Real>,
+    linvel: Vector3<Real>,
+    angvel: Vector3<Real>,
+    sleeping: bool,
+}
+
+impl BodyState {
+    fn of(body: &RigidBody) -> Self {
+        Self {
+            position: *body.position(),
+            linvel: *body.linvel(),
+            angvel: *body.angvel(),
+            sleeping: body.is_sleeping(),
+        }
     }
 
-    pub fn create_kinematic_body(&mut self) -> f64 {
-        let rigid_body = RigidBodyBuilder::kinematic_position_based().build();
-        let handle = self.rigid_body_set.insert(rigid_body);
-        let (index, generation) = handle.into_raw_parts();
-        encode_handle_for_js(index, generation)
+    // Putting a body to sleep zeroes its velocities, so that goes first.
+    fn apply(&self, body: &mut RigidBody) {
+        if self.sleeping {
+            body.sleep();
+        } else if body.is_sleeping() {
+            body.wake_up(true);
+        }
+        body.set_position(self.position, false);
+        body.set_linvel(self.linvel, false);
+        body.set_angvel(self.angvel, false);
     }
+}
 
-    pub fn create_fixed_body(&mut self) -> f64 {
-        let rigid_body = RigidBodyBuilder::fixed().build();
-        let handle = self.rigid_body_set.insert(rigid_body);
-        let (index, generation) = handle.into_raw_parts();
-        encode_handle_for_js(index, generation)
+// What a delta snapshot is compared against: every body as it was when the base was taken.
+struct BaseSnapshot {
+    bodies: HashMap<RigidBodyHandle, BaseBody>,
+}
+
+struct BaseBody {
+    state: BodyState,
+    colliders: Vec<ColliderHandle>,
+}
+
+// The bodies that changed since a base snapshot, keyed by the crc32 in the base's header. Bodies
+// added since, or whose colliders changed, are carried in full along with their colliders.
+#[derive(Serialize, Deserialize)]
+struct SerializableDelta {
+    base: u32,
+    changed: Vec<(RigidBodyHandle, BodyState)>,
+    added: Vec<AddedBody>,
+    removed: Vec<RigidBodyHandle>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct AddedBody {
+    handle: RigidBodyHandle,
+    body: RigidBody,
+    colliders: Vec<(ColliderHandle, Collider)>,
+}
+
+// Applies a delta to the base it was taken against. Returns None when it does not fit the base.
+fn apply_delta(world: &mut SerializableWorld, delta: SerializableDelta) -> Option<()> {
+    // A body carried in full replaces the one that has its handle in the base.
+    let replaced: Vec<RigidBodyHandle> = delta
+        .added
+        .iter()
+        .map(|added| added.handle)
+        .filter(|handle| world.bodies.contains(*handle))
+        .collect();
+    for handle in delta.removed.into_iter().chain(replaced) {
+        world.bodies.remove(
+            handle,
+            &mut world.islands,
+            &mut world.colliders,
+            &mut world.impulse_joints,
+            &mut world.multibody_joints,
+            true,
+        )?;
+    }
+    world
+        .joint_break_thresholds
+        .retain(|(handle, _)| world.impulse_joints.contains(*handle));
+
+    let mut bodies = Vec::new();
+    let mut colliders = Vec::new();
+    for added in delta.added {
+        bodies.push((added.handle.into_raw_parts(), added.body));
+        colliders.extend(
+            added
+                .colliders
+                .into_iter()
+                .map(|(handle, collider)| (handle.into_raw_parts(), (added.handle, collider))),
+        );
     }
+    insert_at_handles::<_, RigidBodySetLayout<RigidBody>, _>(
+        &mut world.bodies,
+        bodies,
+        |bodies, body| bodies.insert(body).into_raw_parts(),
+    )?;
+    let world_bodies = &mut world.bodies;
+    insert_at_handles::<_, ColliderSetLayout<Collider>, _>(
+        &mut world.colliders,
+        colliders,
+        |colliders, (parent, collider)| {
+            colliders
+                .insert_with_parent(collider, parent, world_bodies)
+                .into_raw_parts()
+        },
+    )?;
+
+    for (handle, state) in delta.changed {
+        state.apply(world.bodies.get_mut(handle)?);
+    }
+    world
+        .bodies
+        .propagate_modified_body_positions_to_colliders(&mut world.colliders);
+    Some(())
+}
 
-    pub fn add_box_collider(
-        &mut self,
-        handle: f64,
-        half_x: Real,
-        half_y: Real,
-        half_z: Real,
-        is_sensor: bool,
-        pos_x: Option<Real>,
-        pos_y: Option<Real>,
-        pos_z: Option<Real>,
-        rot_x: Option<Real>,
-        rot_y: Option<Real>,
-        rot_z: Option<Real>,
-    ) -> f64 {
-        let collider = ColliderBuilder::cuboid(half_x, half_y, half_z)
-            .sensor(is_sensor)
-            .translation(Vector3::new(
-                pos_x.unwrap_or(0.0),
-                pos_y.unwrap_or(0.0),
-                pos_z.unwrap_or(0.0),
-            ))
-            .rotation(Vector3::new(
-                rot_x.unwrap_or(0.0),
-                rot_y.unwrap_or(0.0),
-                rot_z.unwrap_or(0.0),
-            ))
-            .build();
-        let (index, generation) = decode_handle_from_js(handle);
-        let handle: RigidBodyHandle = RigidBodyHandle::from_raw_parts(index, generation);
-        let handle =
-            self.collider_set
-                .insert_with_parent(collider, handle, &mut self.rigid_body_set);
-        let (index, generation) = handle.into_raw_parts();
-        encode_handle_for_js(index, generation)
+trait SetLayout {
+    type Value;
+
+    fn arena(&mut self) -> &mut ArenaLayout<Self::Value>;
+}
+
+impl<T> SetLayout for RigidBodySetLayout<T> {
+    type Value = T;
+
+    fn arena(&mut self) -> &mut ArenaLayout<T> {
+        &mut self.bodies
     }
+}
 
-    pub fn add_cylinder_collider(
-        &mut self,
-        handle: f64,
-        half_height: Real,
-        radius: Real,
-        is_sensor: bool,
-        pos_x: Option<Real>,
-        pos_y: Option<Real>,
-        pos_z: Option<Real>,
-        rot_x: Option<Real>,
-        rot_y: Option<Real>,
-        rot_z: Option<Real>,
-    ) -> f64 {
-        let collider = ColliderBuilder::cylinder(half_height, radius)
-            .sensor(is_sensor)
-            .translation(Vector3::new(
-                pos_x.unwrap_or(0.0),
-                pos_y.unwrap_or(0.0),
-                pos_z.unwrap_or(0.0),
-            ))
-            .rotation(Vector3::new(
-                rot_x.unwrap_or(0.0),
-                rot_y.unwrap_or(0.0),
-                rot_z.unwrap_or(0.0),
-            ))
-            .build();
-        let (index, generation) = decode_handle_from_js(handle);
-        let handle: RigidBodyHandle = RigidBodyHandle::from_raw_parts(index, generation);
-        let handle =
-            self.collider_set
-                .insert_with_parent(collider, handle, &mut self.rigid_body_set);
-        let (index, generation) = handle.into_raw_parts();
-        encode_handle_for_js(index, generation)
+impl<T> SetLayout for ColliderSetLayout<T> {
+    type Value = T;
+
+    fn arena(&mut self) -> &mut ArenaLayout<T> {
+        &mut self.colliders
     }
+}
 
-    pub fn add_trimesh_collider(
-        &mut self,
-        handle: f64,
-        vertices: Vec<Real>,
-        indices: Vec<u32>,
-        is_sensor: bool,
-        trimesh_flags: u32,
-        pos_x: Option<Real>,
-        pos_y: Option<Real>,
-        pos_z: Option<Real>,
-        rot_x: Option<Real>,
-        rot_y: Option<Real>,
-        rot_z: Option<Real>,
-    ) -> f64 {
-        let points: Vec<Point<Real>> = vertices
-            .chunks_exact(3)
-            .map(|chunk| Point::new(chunk[0], chunk[1], chunk[2]))
-            .collect();
+// Inserts each value at its (index, generation) handle, whose slot must be free. Rapier only
+// inserts at the head of an arena's free list, with the arena's current generation, so both are
+// rewritten through the set's layout before each run of handles that share a generation.
+fn insert_at_handles<S, L, T>(
+    set: &mut S,
+    mut values: Vec<((u32, u32), T)>,
+    mut insert: impl FnMut(&mut S, T) -> (u32, u32),
+) -> Option<()>
+where
+    S: Serialize + DeserializeOwned,
+    L: SetLayout + Serialize + DeserializeOwned,
+{
+    values.sort_by_key(|((index, generation), _)| (*generation, *index));
+    if values.windows(2).any(|pair| pair[0].0 == pair[1].0) {
+        return None;
+    }
+    let mut original_generation = None;
+    let mut last_generation = 0;
+    let mut values = values.into_iter().peekable();
+    while let Some(&((_, generation), _)) = values.peek() {
+        let run: Vec<_> =
+            std::iter::from_fn(|| values.next_if(|((_, next), _)| *next == generation)).collect();
+        let slots: Vec<u32> = run.iter().map(|((index, _), _)| *index).collect();
+        let mut layout: L = reencode(set)?;
+        original_generation.get_or_insert(layout.arena().generation);
+        layout.arena().reserve_slots(&slots, generation)?;
+        *set = reencode(&layout)?;
+        for (handle, value) in run {
+            if insert(set, value) != handle {
+                return None;
+            }
+        }
+        last_generation = generation;
+    }
+    // Later inserts must not hand out a generation older than the one the set had.
+    if let Some(original_generation) = original_generation.filter(|g| *g > last_generation) {
+        let mut layout: L = reencode(set)?;
+        layout.arena().generation = original_generation;
+        *set = reencode(&layout)?;
+    }
+    Some(())
+}
 
-        let triangles: Vec<[u32; 3]> = indices
-            .chunks_exact(3)
-            .map(|chunk| [chunk[0], chunk[1], chunk[2]])
-            .collect();
+fn read_world_snapshot(snapshot: &[u8]) -> Result<SerializableWorld, SnapshotError> {
+    let magic = SnapshotHeader::read(snapshot)?.magic;
+    if magic != SNAPSHOT_MAGIC {
+        return Err(SnapshotError::UnexpectedKind(magic));
+    }
+    let payload = unwrap_snapshot(snapshot)?;
+    deserialize(&payload).map_err(|_| SnapshotError::Payload)
+}
 
-        let mut flags = TriMeshFlags::empty();
-        if trimesh_flags & (TriMeshFlags::ORIENTED.bits() as u32) != 0 {
-            flags |= TriMeshFlags::ORIENTED;
+// World JSON schema, documented in the README. Ids are only used to link colliders and
+// joints to bodies; importing creates new handles.
+const WORLD_JSON_VERSION: u32 = 1;
+
+#[derive(Serialize, Deserialize)]
+pub struct WorldJson {
+    version: u32,
+    gravity: [Real; 3],
+    #[serde(default)]
+    integration_parameters: IntegrationParametersJson,
+    #[serde(default)]
+    bodies: Vec<BodyJson>,
+    #[serde(default)]
+    colliders: Vec<ColliderJson>,
+    #[serde(default)]
+    impulse_joints: Vec<JointJson>,
+    #[serde(default)]
+    multibody_joints: Vec<JointJson>,
+    // Pairs of collider ids that never touch.
+    #[serde(default)]
+    ignored_pairs: Vec<[u64; 2]>,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(default)]
+pub struct IntegrationParametersJson {
+    dt: Real,
+    num_solver_iterations: usize,
+    num_additional_friction_iterations: usize,
+    num_internal_pgs_iterations: usize,
+    min_island_size: usize,
+}
+
+impl IntegrationParametersJson {
+    fn of(parameters: &IntegrationParameters) -> Self {
+        Self {
+            dt: parameters.dt,
+            num_solver_iterations: parameters.num_solver_iterations.get(),
+            num_additional_friction_iterations: parameters.num_additional_friction_iterations,
+            num_internal_pgs_iterations: parameters.num_internal_pgs_iterations,
+            min_island_size: parameters.min_island_size,
         }
-        if trimesh_flags & (TriMeshFlags::FIX_INTERNAL_EDGES.bits() as u32) != 0 {
-            flags |= TriMeshFlags::FIX_INTERNAL_EDGES;
+    }
+
+    fn apply(&self, parameters: &mut IntegrationParameters) {
+        parameters.dt = self.dt;
+        parameters.num_solver_iterations =
+            NonZeroUsize::new(self.num_solver_iterations).unwrap_or(NonZeroUsize::MIN);
+        parameters.num_additional_friction_iterations = self.num_additional_friction_iterations;
+        parameters.num_internal_pgs_iterations = self.num_internal_pgs_iterations;
+        parameters.min_island_size = self.min_island_size;
+    }
+}
+
+impl Default for IntegrationParametersJson {
+    fn default() -> Self {
+        Self::of(&IntegrationParameters::default())
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy)]
+#[serde(rename_all = "snake_case")]
+pub enum BodyTypeJson {
+    Dynamic,
+    Fixed,
+    KinematicPosition,
+    KinematicVelocity,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct BodyJson {
+    id: u64,
+    #[serde(rename = "type")]
+    body_type: BodyTypeJson,
+    #[serde(default)]
+    translation: [Real; 3],
+    #[serde(default = "identity_rotation")]
+    rotation: [Real; 4],
+    #[serde(default)]
+    linvel: [Real; 3],
+    #[serde(default)]
+    angvel: [Real; 3],
+    #[serde(default)]
+    linear_damping: Real,
+    #[serde(default)]
+    angular_damping: Real,
+    #[serde(default = "default_one")]
+    gravity_scale: Real,
+    #[serde(default)]
+    ccd: bool,
+    #[serde(default)]
+    soft_ccd_prediction: Real,
+    #[serde(default)]
+    additional_solver_iterations: usize,
+    // Added on top of what the colliders contribute; at most one of the two is set.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    additional_mass: Option<Real>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    additional_mass_properties: Option<MassPropertiesJson>,
+    #[serde(default)]
+    sleeping: bool,
+    #[serde(default = "default_true")]
+    can_sleep: bool,
+    #[serde(default = "default_true")]
+    enabled: bool,
+    #[serde(default)]
+    locked_axes: u8,
+    #[serde(default)]
+    dominance_group: i8,
+    #[serde(default)]
+    user_data: u128,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct ColliderJson {
+    id: u64,
+    #[serde(default)]
+    body: Option<u64>,
+    shape: ShapeJson,
+    // Relative to the parent body, or in world space for colliders without one.
+    #[serde(default)]
+    translation: [Real; 3],
+    #[serde(default = "identity_rotation")]
+    rotation: [Real; 4],
+    #[serde(default)]
+    sensor: bool,
+    #[serde(default = "default_friction")]
+    friction: Real,
+    #[serde(default)]
+    friction_combine_rule: CombineRuleJson,
+    #[serde(default)]
+    restitution: Real,
+    #[serde(default)]
+    restitution_combine_rule: CombineRuleJson,
+    // At most one of the three is set; without any, the density is 1.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    density: Option<Real>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    mass: Option<Real>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    mass_properties: Option<MassPropertiesJson>,
+    #[serde(default)]
+    contact_skin: Real,
+    #[serde(default = "all_groups")]
+    collision_groups: [u32; 2],
+    #[serde(default = "all_groups")]
+    solver_groups: [u32; 2],
+    #[serde(default = "default_collision_types")]
+    active_collision_types: u16,
+    #[serde(default)]
+    active_events: u32,
+    #[serde(default = "unlimited_force")]
+    contact_force_event_threshold: Real,
+    #[serde(default)]
+    active_hooks: u32,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    one_way: Option<OneWayJson>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    surface_velocity: Option<SurfaceVelocityJson>,
+    #[serde(default = "default_true")]
+    enabled: bool,
+    #[serde(default)]
+    user_data: u128,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct MassPropertiesJson {
+    #[serde(default)]
+    local_com: [Real; 3],
+    mass: Real,
+    principal_inertia: [Real; 3],
+    #[serde(default = "identity_rotation")]
+    principal_inertia_frame: [Real; 4],
+}
+
+impl MassPropertiesJson {
+    fn of(mass_properties: &MassProperties) -> Self {
+        Self {
+            local_com: mass_properties.local_com.coords.into(),
+            mass: mass_properties.mass(),
+            principal_inertia: mass_properties.principal_inertia().into(),
+            principal_inertia_frame: rotation_to_json(
+                &mass_properties.principal_inertia_local_frame,
+            ),
         }
-        let collider_builder_result =
-            ColliderBuilder::trimesh_with_flags(points.clone(), triangles.clone(), flags);
-        if let Ok(collider_builder) = collider_builder_result {
-            let collider = collider_builder
-                .sensor(is_sensor)
-                .translation(Vector3::new(
-                    pos_x.unwrap_or(0.0),
-                    pos_y.unwrap_or(0.0),
-                    pos_z.unwrap_or(0.0),
-                ))
-                .rotation(Vector3::new(
-                    rot_x.unwrap_or(0.0),
-                    rot_y.unwrap_or(0.0),
-                    rot_z.unwrap_or(0.0),
-                ))
-                .build();
-            let (index, generation) = decode_handle_from_js(handle);
-            let handle: RigidBodyHandle = RigidBodyHandle::from_raw_parts(index, generation);
-            let handle =
-                self.collider_set
-                    .insert_with_parent(collider, handle, &mut self.rigid_body_set);
-            let (index, generation) = handle.into_raw_parts();
-            encode_handle_for_js(index, generation)
-        } else {
-            0.0
+    }
+
+    fn mass_properties(&self) -> MassProperties {
+        MassProperties::with_principal_inertia_frame(
+            Point::from(self.local_com),
+            self.mass,
+            self.principal_inertia.into(),
+            rotation_from_json(self.principal_inertia_frame),
+        )
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum CombineRuleJson {
+    #[default]
+    Average,
+    Min,
+    Multiply,
+    Max,
+}
+
+impl CombineRuleJson {
+    fn of(rule: CoefficientCombineRule) -> Self {
+        match rule {
+            CoefficientCombineRule::Average => CombineRuleJson::Average,
+            CoefficientCombineRule::Min => CombineRuleJson::Min,
+            CoefficientCombineRule::Multiply => CombineRuleJson::Multiply,
+            CoefficientCombineRule::Max => CombineRuleJson::Max,
         }
     }
 
-    pub fn add_convex_hull_collider(
-        &mut self,
-        handle: f64,
-        vertices: Vec<Real>,
-        is_sensor: bool,
-        pos_x: Option<Real>,
-        pos_y: Option<Real>,
-        pos_z: Option<Real>,
-        rot_x: Option<Real>,
-        rot_y: Option<Real>,
-        rot_z: Option<Real>,
-    ) -> f64 {
-        let points: Vec<Point<Real>> = vertices
-            .chunks_exact(3)
-            .map(|chunk| Point::new(chunk[0], chunk[1], chunk[2]))
-            .collect();
-
-        let collider_builder_opt = ColliderBuilder::convex_hull(&points);
-        if let Some(collider_builder) = collider_builder_opt {
-            let collider = collider_builder
-                .sensor(is_sensor)
-                .translation(Vector3::new(
-                    pos_x.unwrap_or(0.0),
-                    pos_y.unwrap_or(0.0),
-                    pos_z.unwrap_or(0.0),
-                ))
-                .rotation(Vector3::new(
-                    rot_x.unwrap_or(0.0),
-                    rot_y.unwrap_or(0.0),
-                    rot_z.unwrap_or(0.0),
-                ))
-                .build();
-            let (index, generation) = decode_handle_from_js(handle);
-            let handle: RigidBodyHandle = RigidBodyHandle::from_raw_parts(index, generation);
-            let handle =
-                self.collider_set
-                    .insert_with_parent(collider, handle, &mut self.rigid_body_set);
-            let (index, generation) = handle.into_raw_parts();
-            encode_handle_for_js(index, generation)
-        } else {
-            0.0
+    fn rule(self) -> CoefficientCombineRule {
+        match self {
+            CombineRuleJson::Average => CoefficientCombineRule::Average,
+            CombineRuleJson::Min => CoefficientCombineRule::Min,
+            CombineRuleJson::Multiply => CoefficientCombineRule::Multiply,
+            CombineRuleJson::Max => CoefficientCombineRule::Max,
         }
     }
+}
 
-    pub fn create_revolute_joint(
-        &mut self,
-        body1_handle: f64,
-        body2_handle: f64,
-        anchor1_x: Real,
-        anchor1_y: Real,
-        anchor1_z: Real,
-        anchor2_x: Real,
-        anchor2_y: Real,
-        anchor2_z: Real,
-        axis_x: Real,
-        axis_y: Real,
-        axis_z: Real,
-        wake_up: bool,
-    ) -> f64 {
-        let (index, generation) = decode_handle_from_js(body1_handle);
-        let handle1: RigidBodyHandle = RigidBodyHandle::from_raw_parts(index, generation);
-        let (index, generation) = decode_handle_from_js(body2_handle);
-        let handle2: RigidBodyHandle = RigidBodyHandle::from_raw_parts(index, generation);
-        if self.rigid_body_set.contains(handle1) && self.rigid_body_set.contains(handle2) {
-            let joint = RevoluteJointBuilder::new(Unit::new_normalize(Vector3::new(
-                axis_x, axis_y, axis_z,
-            )))
-            .local_anchor1(Point::new(anchor1_x, anchor1_y, anchor1_z))
-            .local_anchor2(Point::new(anchor2_x, anchor2_y, anchor2_z))
-            .build();
-            let handle = self
-                .impulse_joint_set
-                .insert(handle1, handle2, joint, wake_up);
-            let (index, generation) = handle.into_raw_parts();
-            return encode_handle_for_js(index, generation);
-        } else {
-            return 0.0;
+#[derive(Serialize, Deserialize)]
+pub struct OneWayJson {
+    normal: [Real; 3],
+    allowed_angle: Real,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SurfaceVelocityJson {
+    Linear([Real; 3]),
+    Angular([Real; 3]),
+}
+
+impl SurfaceVelocityJson {
+    fn of(surface_velocity: &SurfaceVelocity) -> Self {
+        match surface_velocity {
+            SurfaceVelocity::Linear(linvel) => SurfaceVelocityJson::Linear((*linvel).into()),
+            SurfaceVelocity::Angular(angvel) => SurfaceVelocityJson::Angular((*angvel).into()),
         }
     }
 
-    pub fn create_fixed_joint(
-        &mut self,
-        body1_handle: f64,
-        body2_handle: f64,
-        anchor1_x: Real,
-        anchor1_y: Real,
-        anchor1_z: Real,
-        anchor2_x: Real,
-        anchor2_y: Real,
-        anchor2_z: Real,
-        frame1_x: Real,
-        frame1_y: Real,
-        frame1_z: Real,
-        frame1_w: Real,
-        frame2_x: Real,
-        frame2_y: Real,
-        frame2_z: Real,
-        frame2_w: Real,
-        wake_up: bool,
-    ) -> f64 {
-        let (index, generation) = decode_handle_from_js(body1_handle);
-        let handle1: RigidBodyHandle = RigidBodyHandle::from_raw_parts(index, generation);
-        let (index, generation) = decode_handle_from_js(body2_handle);
-        let handle2: RigidBodyHandle = RigidBodyHandle::from_raw_parts(index, generation);
-        if self.rigid_body_set.contains(handle1) && self.rigid_body_set.contains(handle2) {
-            let joint = FixedJointBuilder::new()
-                .local_anchor1(Point::new(anchor1_x, anchor1_y, anchor1_z))
-                .local_anchor2(Point::new(anchor2_x, anchor2_y, anchor2_z))
-                .local_frame1(Isometry::from_parts(
-                    Translation3::identity(),
-                    UnitQuaternion::from_quaternion(Quaternion::new(
-                        frame1_w, frame1_x, frame1_y, frame1_z,
-                    )),
-                ))
-                .local_frame2(Isometry::from_parts(
-                    Translation3::identity(),
-                    UnitQuaternion::from_quaternion(Quaternion::new(
-                        frame2_w, frame2_x, frame2_y, frame2_z,
-                    )),
-                ))
-                .build();
-            let handle = self
-                .impulse_joint_set
-                .insert(handle1, handle2, joint, wake_up);
-            let (index, generation) = handle.into_raw_parts();
-            return encode_handle_for_js(index, generation);
-        } else {
-            return 0.0;
+    fn surface_velocity(&self) -> SurfaceVelocity {
+        match self {
+            SurfaceVelocityJson::Linear(linvel) => SurfaceVelocity::Linear((*linvel).into()),
+            SurfaceVelocityJson::Angular(angvel) => SurfaceVelocity::Angular((*angvel).into()),
         }
     }
+}
 
-    pub fn set_revolute_joint_limits(&mut self, handle: f64, min: Real, max: Real) -> bool {
-        let (index, generation) = decode_handle_from_js(handle);
-        let handle = ImpulseJointHandle::from_raw_parts(index, generation);
-        if let Some(joint) = self.impulse_joint_set.get_mut(handle, true) {
-            if let Some(revolute_joint) = joint.data.as_revolute_mut() {
-                revolute_joint.set_limits([min, max]);
-                return true;
-            } else {
-                return false;
-            }
-        } else {
-            return false;
+#[derive(Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ShapeJson {
+    Ball {
+        radius: Real,
+    },
+    Cuboid {
+        half_extents: [Real; 3],
+    },
+    Capsule {
+        half_height: Real,
+        radius: Real,
+    },
+    Cylinder {
+        half_height: Real,
+        radius: Real,
+    },
+    Cone {
+        half_height: Real,
+        radius: Real,
+    },
+    RoundCylinder {
+        half_height: Real,
+        radius: Real,
+        border_radius: Real,
+    },
+    Trimesh {
+        vertices: Vec<[Real; 3]>,
+        indices: Vec<[u32; 3]>,
+        #[serde(default)]
+        flags: u16,
+    },
+    ConvexHull {
+        points: Vec<[Real; 3]>,
+    },
+    Heightfield {
+        heights: Vec<Vec<Real>>,
+        scale: [Real; 3],
+    },
+    Compound {
+        shapes: Vec<CompoundPartJson>,
+    },
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct CompoundPartJson {
+    #[serde(default)]
+    translation: [Real; 3],
+    #[serde(default = "identity_rotation")]
+    rotation: [Real; 4],
+    shape: ShapeJson,
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy)]
+#[serde(rename_all = "snake_case")]
+pub enum JointAxisJson {
+    LinX,
+    LinY,
+    LinZ,
+    AngX,
+    AngY,
+    AngZ,
+}
+
+const JOINT_AXES: [JointAxisJson; 6] = [
+    JointAxisJson::LinX,
+    JointAxisJson::LinY,
+    JointAxisJson::LinZ,
+    JointAxisJson::AngX,
+    JointAxisJson::AngY,
+    JointAxisJson::AngZ,
+];
+
+impl JointAxisJson {
+    fn axis(self) -> JointAxis {
+        match self {
+            JointAxisJson::LinX => JointAxis::LinX,
+            JointAxisJson::LinY => JointAxis::LinY,
+            JointAxisJson::LinZ => JointAxis::LinZ,
+            JointAxisJson::AngX => JointAxis::AngX,
+            JointAxisJson::AngY => JointAxis::AngY,
+            JointAxisJson::AngZ => JointAxis::AngZ,
         }
     }
 
-    pub fn configure_revolute_joint_motor(
-        &mut self,
-        handle: f64,
-        target_pos: Real,
-        target_vel: Real,
-        stiffness: Real,
-        damping: Real,
-    ) -> bool {
-        let (index, generation) = decode_handle_from_js(handle);
-        let handle = ImpulseJointHandle::from_raw_parts(index, generation);
-        if let Some(joint) = self.impulse_joint_set.get_mut(handle, true) {
-            if let Some(revolute_joint) = joint.data.as_revolute_mut() {
-                revolute_joint.set_motor(target_pos, target_vel, stiffness, damping);
-                return true;
-            } else {
-                return false;
-            }
-        } else {
-            return false;
-        }
+    fn mask(self) -> JointAxesMask {
+        JointAxesMask::from_bits_truncate(1 << self as u8)
     }
+}
 
-    pub fn get_joint_data(
-        &self,
-        handle: f64,
-    ) -> Option<(
-        f64,
-        f64,
-        Real,
-        Real,
-        Real,
-        Real,
-        Real,
-        Real,
-        Real,
-        Real,
-        Real,
-    )> {
-        let (index, generation) = decode_handle_from_js(handle);
-        let handle = ImpulseJointHandle::from_raw_parts(index, generation);
-        if let Some(joint) = self.impulse_joint_set.get(handle) {
-            if let Some(revolute_joint) = joint.data.as_revolute() {
-                let (index1, generation1) = joint.body1.into_raw_parts();
-                let body1 = encode_handle_for_js(index1, generation1);
-                let (index2, generation2) = joint.body2.into_raw_parts();
-                let body2 = encode_handle_for_js(index2, generation2);
-                let anchor1 = revolute_joint.local_anchor1().coords;
-                let anchor2 = revolute_joint.local_anchor2().coords;
-                let axis = revolute_joint.data.local_axis1();
-                Some((
-                    body1, body2, anchor1.x, anchor1.y, anchor1.z, anchor2.x, anchor2.y, anchor2.z,
-                    axis.x, axis.y, axis.z,
-                ))
-            } else {
-                None
-            }
-        } else {
-            None
+#[derive(Serialize, Deserialize, Clone, Copy, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum MotorModelJson {
+    #[default]
+    AccelerationBased,
+    ForceBased,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct FrameJson {
+    #[serde(default)]
+    translation: [Real; 3],
+    #[serde(default = "identity_rotation")]
+    rotation: [Real; 4],
+}
+
+impl Default for FrameJson {
+    fn default() -> Self {
+        Self {
+            translation: [0.0; 3],
+            rotation: identity_rotation(),
         }
     }
+}
 
-    pub fn set_body_next_kinematic_translation(
-        &mut self,
-        handle: f64,
-        x: Real,
-        y: Real,
-        z: Real,
-    ) -> bool {
-        let (index, generation) = decode_handle_from_js(handle);
-        let handle = RigidBodyHandle::from_raw_parts(index, generation);
-        if let Some(body) = self.rigid_body_set.get_mut(handle) {
-            body.set_next_kinematic_translation(Vector3::new(x, y, z));
-            true
-        } else {
-            false
+#[derive(Serialize, Deserialize)]
+pub struct JointLimitJson {
+    axis: JointAxisJson,
+    min: Real,
+    max: Real,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct JointMotorJson {
+    axis: JointAxisJson,
+    #[serde(default)]
+    target_pos: Real,
+    #[serde(default)]
+    target_vel: Real,
+    #[serde(default)]
+    stiffness: Real,
+    #[serde(default)]
+    damping: Real,
+    #[serde(default = "unlimited_force")]
+    max_force: Real,
+    #[serde(default)]
+    model: MotorModelJson,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct JointJson {
+    id: u64,
+    body1: u64,
+    body2: u64,
+    #[serde(default)]
+    frame1: FrameJson,
+    #[serde(default)]
+    frame2: FrameJson,
+    #[serde(default)]
+    locked_axes: Vec<JointAxisJson>,
+    #[serde(default)]
+    coupled_axes: Vec<JointAxisJson>,
+    #[serde(default)]
+    limits: Vec<JointLimitJson>,
+    #[serde(default)]
+    motors: Vec<JointMotorJson>,
+    #[serde(default = "default_true")]
+    contacts_enabled: bool,
+    // Impulse joints only.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    break_threshold: Option<JointBreakThreshold>,
+}
+
+fn identity_rotation() -> [Real; 4] {
+    [0.0, 0.0, 0.0, 1.0]
+}
+
+fn default_one() -> Real {
+    1.0
+}
+
+fn default_true() -> bool {
+    true
+}
+
+fn default_friction() -> Real {
+    0.5
+}
+
+fn all_groups() -> [u32; 2] {
+    [u32::MAX, u32::MAX]
+}
+
+fn default_collision_types() -> u16 {
+    ActiveCollisionTypes::default().bits()
+}
+
+fn unlimited_force() -> Real {
+    Real::MAX
+}
+
+// Snapshot container, little-endian:
+// magic (4) | format version u16 | precision u8 | compression u8 | rapier version 3 x u16
+// | payload length u64 | raw length u64 | crc32 u32 | payload.
+// The crc32 covers the header bytes before it as well as the payload. The raw length is the
+// size of the payload once decompressed. The magic tells a whole world from a scene, from the
+// dynamic state taken against a scene and from the bodies changed since a base snapshot.
+const SNAPSHOT_MAGIC: [u8; 4] = *b"RPWS";
+const SCENE_SNAPSHOT_MAGIC: [u8; 4] = *b"RPSC";
+const DYNAMIC_SNAPSHOT_MAGIC: [u8; 4] = *b"RPDY";
+const DELTA_SNAPSHOT_MAGIC: [u8; 4] = *b"RPDL";
+// Indexed by the kind reported to JS.
+const SNAPSHOT_MAGICS: [[u8; 4]; 4] = [
+    SNAPSHOT_MAGIC,
+    SCENE_SNAPSHOT_MAGIC,
+    DYNAMIC_SNAPSHOT_MAGIC,
+    DELTA_SNAPSHOT_MAGIC,
+];
+const SNAPSHOT_FORMAT_VERSION: u16 = 1;
+const SNAPSHOT_PRECISION: u8 = 64;
+const SNAPSHOT_HEADER_LEN: usize = 34;
+// Keep in sync with the rapier3d-f64 dependency; a test checks it against Cargo.lock.
+const RAPIER_VERSION: [u16; 3] = [0, 27, 0];
+
+// Largest decompressed payload accepted, checked before any memory is reserved for it.
+const MAX_SNAPSHOT_RAW_LEN: u64 = 1 << 28;
+const ZSTD_LEVEL: i32 = 3;
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum SnapshotCompression {
+    None,
+    Lz4,
+    Zstd,
+}
+
+impl SnapshotCompression {
+    fn from_code(code: u8) -> Option<Self> {
+        match code {
+            0 => Some(SnapshotCompression::None),
+            1 => Some(SnapshotCompression::Lz4),
+            2 => Some(SnapshotCompression::Zstd),
+            _ => None,
         }
     }
 
-    pub fn set_body_translation(
-        &mut self,
-        handle: f64,
-        x: Real,
-        y: Real,
-        z: Real,
-        wake_up: bool,
-    ) -> bool {
-        let (index, generation) = decode_handle_from_js(handle);
-        let handle = RigidBodyHandle::from_raw_parts(index, generation);
-        if let Some(body) = self.rigid_body_set.get_mut(handle) {
-            body.set_translation(Vector3::new(x, y, z), wake_up);
-            true
-        } else {
-            false
+    fn code(self) -> u8 {
+        match self {
+            SnapshotCompression::None => 0,
+            SnapshotCompression::Lz4 => 1,
+            SnapshotCompression::Zstd => 2,
         }
     }
 
-    pub fn set_body_next_kinematic_rotation(
-        &mut self,
-        handle: f64,
-        x: Real,
-        y: Real,
-        z: Real,
-        w: Real,
-    ) -> bool {
-        let (index, generation) = decode_handle_from_js(handle);
-        let handle = RigidBodyHandle::from_raw_parts(index, generation);
-        if let Some(body) = self.rigid_body_set.get_mut(handle) {
-            let rotation = UnitQuaternion::from_quaternion(Quaternion::new(w, x, y, z));
-            body.set_next_kinematic_rotation(rotation);
-            true
-        } else {
-            false
+    fn compress(self, raw: Vec<u8>) -> Option<Vec<u8>> {
+        match self {
+            SnapshotCompression::None => Some(raw),
+            SnapshotCompression::Lz4 => Some(lz4_flex::block::compress(&raw)),
+            SnapshotCompression::Zstd => zstd::bulk::compress(&raw, ZSTD_LEVEL).ok(),
         }
     }
 
-    pub fn set_body_rotation(
-        &mut self,
-        handle: f64,
-        x: Real,
-        y: Real,
-        z: Real,
-        w: Real,
-        wake_up: bool,
-    ) -> bool {
-        let (index, generation) = decode_handle_from_js(handle);
-        let handle = RigidBodyHandle::from_raw_parts(index, generation);
-        if let Some(body) = self.rigid_body_set.get_mut(handle) {
-            let rotation = UnitQuaternion::from_quaternion(Quaternion::new(w, x, y, z));
-            body.set_rotation(rotation, wake_up);
-            true
-        } else {
-            false
+    fn decompress(self, payload: &[u8], raw_len: usize) -> Option<Vec<u8>> {
+        let raw = match self {
+            SnapshotCompression::None => payload.to_vec(),
+            SnapshotCompression::Lz4 => lz4_flex::block::decompress(payload, raw_len).ok()?,
+            SnapshotCompression::Zstd => zstd::bulk::decompress(payload, raw_len).ok()?,
+        };
+        (raw.len() == raw_len).then_some(raw)
+    }
+}
+
+pub struct SnapshotHeader {
+    magic: [u8; 4],
+    format_version: u16,
+    precision: u8,
+    compression: u8,
+    rapier_version: [u16; 3],
+    payload_len: u64,
+    raw_len: u64,
+    crc32: u32,
+}
+
+#[derive(Debug)]
+pub enum SnapshotError {
+    Truncated,
+    BadMagic,
+    UnsupportedFormatVersion(u16),
+    PrecisionMismatch(u8),
+    RapierVersionMismatch([u16; 3]),
+    LengthMismatch { expected: u64, found: u64 },
+    ChecksumMismatch { expected: u32, found: u32 },
+    Payload,
+    UnknownCompression(u8),
+    Decompression,
+    RawLengthTooLarge(u64),
+    UnexpectedKind([u8; 4]),
+    SceneRequired,
+    SceneMismatch { expected: u32, found: u32 },
+    BaseRequired,
+    BaseMismatch { expected: u32, found: u32 },
+}
+
+// `reference` is true when the snapshot passed along to restore against was the one rejected.
+#[derive(Debug)]
+pub struct RestoreError {
+    reference: bool,
+    error: SnapshotError,
+}
+
+impl RestoreError {
+    fn snapshot(error: SnapshotError) -> Self {
+        Self {
+            reference: false,
+            error,
         }
     }
 
-    pub fn set_body_velocity(
-        &mut self,
-        handle: f64,
-        vx: Real,
-        vy: Real,
-        vz: Real,
-        wake_up: bool,
-    ) -> bool {
-        let (index, generation) = decode_handle_from_js(handle);
-        let handle = RigidBodyHandle::from_raw_parts(index, generation);
-        if let Some(body) = self.rigid_body_set.get_mut(handle) {
-            let lin_velocity = Vector3::new(vx, vy, vz);
-            body.set_linvel(lin_velocity, wake_up);
-            true
-        } else {
-            false
+    fn reference(error: SnapshotError) -> Self {
+        Self {
+            reference: true,
+            error,
         }
     }
+}
 
-    pub fn set_body_angular_velocity(
-        &mut self,
-        handle: f64,
-        wx: Real,
-        wy: Real,
-        wz: Real,
-        wake_up: bool,
-    ) -> bool {
-        let (index, generation) = decode_handle_from_js(handle);
-        let handle = RigidBodyHandle::from_raw_parts(index, generation);
-        if let Some(body) = self.rigid_body_set.get_mut(handle) {
-            let ang_velocity = Vector3::new(wx, wy, wz);
-            body.set_angvel(ang_velocity, wake_up);
-            true
-        } else {
-            false
+impl SnapshotError {
+    fn code(&self) -> f64 {
+        match self {
+            SnapshotError::Truncated => 1.0,
+            SnapshotError::BadMagic => 2.0,
+            SnapshotError::UnsupportedFormatVersion(_) => 3.0,
+            SnapshotError::PrecisionMismatch(_) => 4.0,
+            SnapshotError::RapierVersionMismatch(_) => 5.0,
+            SnapshotError::LengthMismatch { .. } => 6.0,
+            SnapshotError::ChecksumMismatch { .. } => 7.0,
+            SnapshotError::Payload => 8.0,
+            SnapshotError::UnknownCompression(_) => 9.0,
+            SnapshotError::Decompression => 10.0,
+            SnapshotError::RawLengthTooLarge(_) => 11.0,
+            SnapshotError::UnexpectedKind(_) => 12.0,
+            SnapshotError::SceneRequired => 13.0,
+            SnapshotError::SceneMismatch { .. } => 14.0,
+            SnapshotError::BaseRequired => 15.0,
+            SnapshotError::BaseMismatch { .. } => 16.0,
         }
     }
+}
 
-    pub fn apply_impulse(&mut self, handle: f64, x: Real, y: Real, z: Real, wake_up: bool) -> bool {
-        let (index, generation) = decode_handle_from_js(handle);
-        let handle = RigidBodyHandle::from_raw_parts(index, generation);
-        if let Some(body) = self.rigid_body_set.get_mut(handle) {
-            let impulse = Vector3::new(x, y, z);
-            body.apply_impulse(impulse, wake_up);
-            true
-        } else {
-            false
+impl std::fmt::Display for SnapshotError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SnapshotError::Truncated => write!(f, "snapshot is shorter than its header"),
+            SnapshotError::BadMagic => write!(f, "not a world snapshot"),
+            SnapshotError::UnsupportedFormatVersion(version) => {
+                write!(f, "unsupported snapshot format version {version}")
+            }
+            SnapshotError::PrecisionMismatch(precision) => write!(
+                f,
+                "snapshot was taken with {precision}-bit precision, expected {SNAPSHOT_PRECISION}-bit"
+            ),
+            SnapshotError::RapierVersionMismatch([major, minor, patch]) => write!(
+                f,
+                "snapshot was taken with rapier {major}.{minor}.{patch}, expected {}.{}.x",
+                RAPIER_VERSION[0], RAPIER_VERSION[1]
+            ),
+            SnapshotError::LengthMismatch { expected, found } => {
+                write!(
+                    f,
+                    "snapshot payload is {found} bytes, header says {expected}"
+                )
+            }
+            SnapshotError::ChecksumMismatch { expected, found } => {
+                write!(
+                    f,
+                    "snapshot checksum is {found:08x}, header says {expected:08x}"
+                )
+            }
+            SnapshotError::Payload => write!(f, "snapshot payload could not be deserialized"),
+            SnapshotError::UnknownCompression(code) => {
+                write!(f, "unknown snapshot compression {code}")
+            }
+            SnapshotError::Decompression => write!(f, "snapshot payload could not be decompressed"),
+            SnapshotError::RawLengthTooLarge(raw_len) => write!(
+                f,
+                "snapshot payload unpacks to {raw_len} bytes, the limit is {MAX_SNAPSHOT_RAW_LEN}"
+            ),
+            SnapshotError::UnexpectedKind(magic) => write!(
+                f,
+                "{} snapshot cannot be used here",
+                String::from_utf8_lossy(magic)
+            ),
+            SnapshotError::SceneRequired => {
+                write!(
+                    f,
+                    "dynamic snapshot needs the scene snapshot it was taken against"
+                )
+            }
+            SnapshotError::SceneMismatch { expected, found } => write!(
+                f,
+                "dynamic snapshot was taken against scene {expected:08x}, not {found:08x}"
+            ),
+            SnapshotError::BaseRequired => {
+                write!(
+                    f,
+                    "delta snapshot needs the base snapshot it was taken against"
+                )
+            }
+            SnapshotError::BaseMismatch { expected, found } => write!(
+                f,
+                "delta snapshot was taken against base {expected:08x}, not {found:08x}"
+            ),
         }
     }
+}
 
-    pub fn get_body_translation(&self, handle: f64) -> Option<(Real, Real, Real)> {
-        let (index, generation) = decode_handle_from_js(handle);
-        let handle = RigidBodyHandle::from_raw_parts(index, generation);
-        if let Some(body) = self.rigid_body_set.get(handle) {
-            let translation = body.translation();
-            Some((translation.x, translation.y, translation.z))
-        } else {
-            None
+impl std::error::Error for SnapshotError {}
+
+impl SnapshotHeader {
+    fn new(
+        magic: [u8; 4],
+        compression: SnapshotCompression,
+        payload: &[u8],
+        raw_len: usize,
+    ) -> Self {
+        let mut header = Self {
+            magic,
+            format_version: SNAPSHOT_FORMAT_VERSION,
+            precision: SNAPSHOT_PRECISION,
+            compression: compression.code(),
+            rapier_version: RAPIER_VERSION,
+            payload_len: payload.len() as u64,
+            raw_len: raw_len as u64,
+            crc32: 0,
+        };
+        header.crc32 = header.checksum(payload);
+        header
+    }
+
+    fn checksum(&self, payload: &[u8]) -> u32 {
+        let mut header = Vec::with_capacity(SNAPSHOT_HEADER_LEN);
+        self.write(&mut header);
+        let mut hasher = crc32fast::Hasher::new();
+        hasher.update(&header[..SNAPSHOT_HEADER_LEN - 4]);
+        hasher.update(payload);
+        hasher.finalize()
+    }
+
+    fn write(&self, out: &mut Vec<u8>) {
+        out.extend_from_slice(&self.magic);
+        out.extend_from_slice(&self.format_version.to_le_bytes());
+        out.push(self.precision);
+        out.push(self.compression);
+        for part in self.rapier_version {
+            out.extend_from_slice(&part.to_le_bytes());
         }
+        out.extend_from_slice(&self.payload_len.to_le_bytes());
+        out.extend_from_slice(&self.raw_len.to_le_bytes());
+        out.extend_from_slice(&self.crc32.to_le_bytes());
     }
 
-    pub fn get_body_translations(&self) -> Vec<Real> {
-        self.rigid_body_set
+    fn read(snapshot: &[u8]) -> Result<Self, SnapshotError> {
+        if snapshot.len() < SNAPSHOT_HEADER_LEN {
+            return Err(SnapshotError::Truncated);
+        }
+        let mut magic = [0; 4];
+        magic.copy_from_slice(&snapshot[0..4]);
+        if !SNAPSHOT_MAGICS.contains(&magic) {
+            return Err(SnapshotError::BadMagic);
+        }
+        let u16_at = |at: usize| u16::from_le_bytes([snapshot[at], snapshot[at + 1]]);
+        let u64_at = |at: usize| {
+            let mut bytes = [0; 8];
+            bytes.copy_from_slice(&snapshot[at..at + 8]);
+            u64::from_le_bytes(bytes)
+        };
+        let mut crc32 = [0; 4];
+        crc32.copy_from_slice(&snapshot[30..34]);
+        Ok(Self {
+            magic,
+            format_version: u16_at(4),
+            precision: snapshot[6],
+            compression: snapshot[7],
+            rapier_version: [u16_at(8), u16_at(10), u16_at(12)],
+            payload_len: u64_at(14),
+            raw_len: u64_at(22),
+            crc32: u32::from_le_bytes(crc32),
+        })
+    }
+
+    fn to_vec(&self) -> Vec<f64> {
+        let mut out = vec![
+            self.format_version as f64,
+            self.precision as f64,
+            self.compression as f64,
+        ];
+        out.extend(self.rapier_version.iter().map(|part| *part as f64));
+        out.push(self.payload_len as f64);
+        out.push(self.raw_len as f64);
+        out.push(self.crc32 as f64);
+        let kind = SNAPSHOT_MAGICS
             .iter()
-            .flat_map(|(handle, body)| {
-                let translation = body.translation();
-                let (index, generation) = handle.into_raw_parts();
-                let encoded_handle = encode_handle_for_js(index, generation);
-                vec![encoded_handle, translation.x, translation.y, translation.z]
-            })
-            .collect()
+            .position(|magic| *magic == self.magic);
+        out.push(kind.unwrap_or_default() as f64);
+        out
     }
+}
 
-    pub fn get_body_rotation(&self, handle: f64) -> Option<(Real, Real, Real, Real)> {
-        let (index, generation) = decode_handle_from_js(handle);
-        let handle = RigidBodyHandle::from_raw_parts(index, generation);
-        if let Some(body) = self.rigid_body_set.get(handle) {
-            let rotation = body.rotation();
-            Some((rotation.i, rotation.j, rotation.k, rotation.w))
-        } else {
-            None
+fn wrap_snapshot(
+    magic: [u8; 4],
+    raw: Vec<u8>,
+    compression: SnapshotCompression,
+) -> Option<Vec<u8>> {
+    let raw_len = raw.len();
+    let payload = compression.compress(raw)?;
+    let mut out = Vec::with_capacity(SNAPSHOT_HEADER_LEN + payload.len());
+    SnapshotHeader::new(magic, compression, &payload, raw_len).write(&mut out);
+    out.extend_from_slice(&payload);
+    Some(out)
+}
+
+// Validates the container and returns its decompressed payload.
+fn unwrap_snapshot(snapshot: &[u8]) -> Result<Vec<u8>, SnapshotError> {
+    let (header, compression) = check_snapshot(snapshot)?;
+    decompress_snapshot(snapshot, &header, compression)
+}
+
+// Validates the container without decompressing it. The checksum is verified before any other
+// header field is trusted.
+fn check_snapshot(snapshot: &[u8]) -> Result<(SnapshotHeader, SnapshotCompression), SnapshotError> {
+    let header = SnapshotHeader::read(snapshot)?;
+    if header.format_version != SNAPSHOT_FORMAT_VERSION {
+        return Err(SnapshotError::UnsupportedFormatVersion(
+            header.format_version,
+        ));
+    }
+    let payload = &snapshot[SNAPSHOT_HEADER_LEN..];
+    if header.payload_len != payload.len() as u64 {
+        return Err(SnapshotError::LengthMismatch {
+            expected: header.payload_len,
+            found: payload.len() as u64,
+        });
+    }
+    let crc32 = header.checksum(payload);
+    if header.crc32 != crc32 {
+        return Err(SnapshotError::ChecksumMismatch {
+            expected: header.crc32,
+            found: crc32,
+        });
+    }
+    if header.precision != SNAPSHOT_PRECISION {
+        return Err(SnapshotError::PrecisionMismatch(header.precision));
+    }
+    // Patch releases keep the serialized layout, so only major and minor have to match.
+    if header.rapier_version[..2] != RAPIER_VERSION[..2] {
+        return Err(SnapshotError::RapierVersionMismatch(header.rapier_version));
+    }
+    let compression = SnapshotCompression::from_code(header.compression)
+        .ok_or(SnapshotError::UnknownCompression(header.compression))?;
+    if header.raw_len > MAX_SNAPSHOT_RAW_LEN {
+        return Err(SnapshotError::RawLengthTooLarge(header.raw_len));
+    }
+    Ok((header, compression))
+}
+
+fn decompress_snapshot(
+    snapshot: &[u8],
+    header: &SnapshotHeader,
+    compression: SnapshotCompression,
+) -> Result<Vec<u8>, SnapshotError> {
+    compression
+        .decompress(&snapshot[SNAPSHOT_HEADER_LEN..], header.raw_len as usize)
+        .ok_or(SnapshotError::Decompression)
+}
+
+pub struct BrokenJoint {
+    handle: ImpulseJointHandle,
+    body1: RigidBodyHandle,
+    body2: RigidBodyHandle,
+    linear_impulse: Real,
+    angular_impulse: Real,
+}
+
+// Limits on the linear and angular impulse a joint may apply in one step; a missing limit
+// never breaks the joint.
+#[derive(Clone, Copy, Serialize, Deserialize)]
+pub struct JointBreakThreshold {
+    linear: Option<Real>,
+    angular: Option<Real>,
+}
+
+#[derive(Default)]
+pub struct EventQueue {
+    collision_events: Mutex<Vec<(CollisionEvent, u128, u128)>>,
+    contact_force_events: Mutex<Vec<ContactForceEvent>>,
+}
+
+impl EventHandler for EventQueue {
+    fn handle_collision_event(
+        &self,
+        _bodies: &RigidBodySet,
+        colliders: &ColliderSet,
+        event: CollisionEvent,
+        _contact_pair: Option<&ContactPair>,
+    ) {
+        // Removed colliders are already gone from the set, so their user data
+        // is captured here rather than when the events are drained.
+        let user_data = |handle| {
+            colliders
+                .get(handle)
+                .map_or(0, |collider| collider.user_data)
+        };
+        if let Ok(mut collision_events) = self.collision_events.lock() {
+            collision_events.push((
+                event,
+                user_data(event.collider1()),
+                user_data(event.collider2()),
+            ));
         }
     }
 
-    pub fn get_body_rotations(&self) -> Vec<Real> {
-        self.rigid_body_set
-            .iter()
-            .flat_map(|(_handle, body)| {
-                let rotation = body.rotation();
-                let (index, generation) = _handle.into_raw_parts();
-                let encoded_handle = encode_handle_for_js(index, generation);
-                vec![
-                    encoded_handle,
-                    rotation.i,
-                    rotation.j,
-                    rotation.k,
-                    rotation.w,
-                ]
-            })
-            .collect()
+    fn handle_contact_force_event(
+        &self,
+        dt: Real,
+        _bodies: &RigidBodySet,
+        _colliders: &ColliderSet,
+        contact_pair: &ContactPair,
+        total_force_magnitude: Real,
+    ) {
+        if let Ok(mut contact_force_events) = self.contact_force_events.lock() {
+            contact_force_events.push(ContactForceEvent::from_contact_pair(
+                dt,
+                contact_pair,
+                total_force_magnitude,
+            ));
+        }
     }
+}
 
-    pub fn get_body_velocity(&self, handle: f64) -> Option<(Real, Real, Real)> {
-        let (index, generation) = decode_handle_from_js(handle);
-        let handle = RigidBodyHandle::from_raw_parts(index, generation);
-        if let Some(body) = self.rigid_body_set.get(handle) {
-            let linvel = body.linvel();
-            Some((linvel.x, linvel.y, linvel.z))
-        } else {
-            None
+#[derive(Default)]
+pub struct ContactHooks {
+    ignored_pairs: HashSet<(ColliderHandle, ColliderHandle)>,
+    one_way_surfaces: HashMap<ColliderHandle, (Vector3<Real>, Real)>,
+    surface_velocities: HashMap<ColliderHandle, SurfaceVelocity>,
+}
+
+pub enum SurfaceVelocity {
+    Linear(Vector3<Real>),
+    Angular(Vector3<Real>),
+}
+
+impl SurfaceVelocity {
+    fn velocity_at_point(&self, position: &Isometry<Real>, point: &Point<Real>) -> Vector3<Real> {
+        match self {
+            SurfaceVelocity::Linear(linvel) => position.rotation * linvel,
+            SurfaceVelocity::Angular(angvel) => {
+                (position.rotation * angvel).cross(&(point.coords - position.translation.vector))
+            }
         }
     }
+}
 
-    pub fn get_body_angular_velocity(&self, handle: f64) -> Option<(Real, Real, Real)> {
-        let (index, generation) = decode_handle_from_js(handle);
-        let handle = RigidBodyHandle::from_raw_parts(index, generation);
-        if let Some(body) = self.rigid_body_set.get(handle) {
-            let angvel = body.angvel();
-            Some((angvel.x, angvel.y, angvel.z))
-        } else {
-            None
-        }
+impl ContactHooks {
+    fn is_pair_ignored(&self, collider1: ColliderHandle, collider2: ColliderHandle) -> bool {
+        self.ignored_pairs
+            .contains(&ordered_collider_pair(collider1, collider2))
     }
 
-    pub fn set_body_enabled_translations(
-        &mut self,
-        handle: f64,
-        enable_x: bool,
-        enable_y: bool,
-        enable_z: bool,
-        wake_up: bool,
-    ) -> bool {
-        let (index, generation) = decode_handle_from_js(handle);
-        let handle = RigidBodyHandle::from_raw_parts(index, generation);
-        if let Some(body) = self.rigid_body_set.get_mut(handle) {
-            body.set_enabled_translations(enable_x, enable_y, enable_z, wake_up);
-            true
-        } else {
-            false
-        }
+    fn remove_collider(&mut self, handle: ColliderHandle) {
+        self.ignored_pairs
+            .retain(|(collider1, collider2)| *collider1 != handle && *collider2 != handle);
+        self.one_way_surfaces.remove(&handle);
+        self.surface_velocities.remove(&handle);
     }
+}
 
-    pub fn set_body_enabled_rotations(
-        &mut self,
-        handle: f64,
-        enable_x: bool,
-        enable_y: bool,
-        enable_z: bool,
-        wake_up: bool,
-    ) -> bool {
-        let (index, generation) = decode_handle_from_js(handle);
-        let handle = RigidBodyHandle::from_raw_parts(index, generation);
-        if let Some(body) = self.rigid_body_set.get_mut(handle) {
-            body.set_enabled_rotations(enable_x, enable_y, enable_z, wake_up);
-            true
+impl PhysicsHooks for ContactHooks {
+    fn filter_contact_pair(&self, context: &PairFilterContext) -> Option<SolverFlags> {
+        if self.is_pair_ignored(context.collider1, context.collider2) {
+            None
         } else {
-            false
+            Some(SolverFlags::COMPUTE_IMPULSES)
         }
     }
 
-    pub fn get_body_mass(&self, handle: f64) -> Option<Real> {
-        let (index, generation) = decode_handle_from_js(handle);
-        let handle = RigidBodyHandle::from_raw_parts(index, generation);
-        if let Some(body) = self.rigid_body_set.get(handle) {
-            Some(body.mass())
-        } else {
-            None
-        }
+    fn filter_intersection_pair(&self, context: &PairFilterContext) -> bool {
+        !self.is_pair_ignored(context.collider1, context.collider2)
     }
 
-    pub fn get_body_collider(&self, handle: f64, collider_index: usize) -> Option<f64> {
-        let (index, generation) = decode_handle_from_js(handle);
-        let handle = RigidBodyHandle::from_raw_parts(index, generation);
-        if let Some(body) = self.rigid_body_set.get(handle) {
-            if let Some(handle) = body.colliders().get(collider_index) {
-                let (index, generation) = handle.into_raw_parts();
-                Some(encode_handle_for_js(index, generation))
-            } else {
-                None
+    fn modify_solver_contacts(&self, context: &mut ContactModificationContext) {
+        if let Some((normal, allowed_angle)) = self.one_way_surfaces.get(&context.collider1) {
+            context.update_as_oneway_platform(normal, *allowed_angle);
+        } else if let Some((normal, allowed_angle)) = self.one_way_surfaces.get(&context.collider2)
+        {
+            // The allowed normal is read in collider1's local frame, so bring the platform's
+            // normal over from collider2's frame before flipping it.
+            let rotation1 = context.colliders[context.collider1].position().rotation;
+            let rotation2 = context.colliders[context.collider2].position().rotation;
+            let normal = rotation1.inverse_transform_vector(&(rotation2 * normal));
+            context.update_as_oneway_platform(&-normal, *allowed_angle);
+        }
+        let surfaces: [(ColliderHandle, Real); 2] =
+            [(context.collider1, 1.0), (context.collider2, -1.0)];
+        for (handle, sign) in surfaces {
+            if let Some(surface_velocity) = self.surface_velocities.get(&handle) {
+                let position = context.colliders[handle].position();
+                let normal = *context.normal;
+                for solver_contact in context.solver_contacts.iter_mut() {
+                    let velocity =
+                        surface_velocity.velocity_at_point(position, &solver_contact.point);
+                    let tangent_velocity = velocity - normal * normal.dot(&velocity);
+                    solver_contact.tangent_velocity += tangent_velocity * sign;
+                }
             }
-        } else {
-            None
         }
     }
+}
 
-    pub fn get_body_num_colliders(&self, handle: f64) -> usize {
-        let (index, generation) = decode_handle_from_js(handle);
-        let handle = RigidBodyHandle::from_raw_parts(index, generation);
-        if let Some(body) = self.rigid_body_set.get(handle) {
-            body.colliders().len()
-        } else {
-            0
+impl World {
+    pub fn new(gravity_x: Real, gravity_y: Real, gravity_z: Real) -> Self {
+        let integration_parameters = IntegrationParameters::default();
+        Self {
+            rigid_body_set: RigidBodySet::new(),
+            collider_set: ColliderSet::new(),
+            gravity: Vector3::new(gravity_x, gravity_y, gravity_z),
+            integration_parameters: integration_parameters,
+            physics_pipeline: PhysicsPipeline::new(),
+            island_manager: IslandManager::new(),
+            broad_phase: DefaultBroadPhase::new(),
+            narrow_phase: NarrowPhase::new(),
+            impulse_joint_set: ImpulseJointSet::new(),
+            multibody_joint_set: MultibodyJointSet::new(),
+            ccd_solver: CCDSolver::new(),
+            physics_hooks: ContactHooks::default(),
+            event_handler: EventQueue::default(),
+            pending_colliders: Vec::new(),
+            joint_break_thresholds: HashMap::new(),
+            broken_joints: Vec::new(),
+            reported_active_bodies: HashSet::new(),
+            scene: None,
+            base_snapshots: HashMap::new(),
         }
     }
 
-    pub fn step(&mut self, dt: Real) {
-        self.integration_parameters.dt = dt;
-
-        self.physics_pipeline.step(
-            &self.gravity,
-            &self.integration_parameters,
-            &mut self.island_manager,
-            &mut self.broad_phase,
-            &mut self.narrow_phase,
-            &mut self.rigid_body_set,
-            &mut self.collider_set,
-            &mut self.impulse_joint_set,
-            &mut self.multibody_joint_set,
-            &mut self.ccd_solver,
-            &self.event_handler,
-            &self.physics_hooks,
-        );
+    pub fn bodies(&self) -> &RigidBodySet {
+        &self.rigid_body_set
     }
 
-    pub fn set_gravity(&mut self, x: Real, y: Real, z: Real) {
-        self.gravity = Vector3::new(x, y, z);
+    pub fn create_dynamic_body(&mut self) -> f64 {
+        let rigid_body = RigidBodyBuilder::dynamic().build();
+        let handle = self.rigid_body_set.insert(rigid_body);
+        let (index, generation) = handle.into_raw_parts();
+        encode_handle_for_js(index, generation)
     }
 
-    pub fn set_body_enabled(&mut self, handle: f64, enabled: bool) -> bool {
-        let (index, generation) = decode_handle_from_js(handle);
-        let handle = RigidBodyHandle::from_raw_parts(index, generation);
-        if let Some(body) = self.rigid_body_set.get_mut(handle) {
-            body.set_enabled(enabled);
-            true
-        } else {
-            false
-        }
+    pub fn create_kinematic_body(&mut self) -> f64 {
+        let rigid_body = RigidBodyBuilder::kinematic_position_based().build();
+        let handle = self.rigid_body_set.insert(rigid_body);
+        let (index, generation) = handle.into_raw_parts();
+        encode_handle_for_js(index, generation)
     }
 
-    pub fn is_body_enabled(&self, handle: f64) -> bool {
-        let (index, generation) = decode_handle_from_js(handle);
-        let handle = RigidBodyHandle::from_raw_parts(index, generation);
-        if let Some(body) = self.rigid_body_set.get(handle) {
-            body.is_enabled()
-        } else {
-            false
-        }
+    pub fn create_fixed_body(&mut self) -> f64 {
+        let rigid_body = RigidBodyBuilder::fixed().build();
+        let handle = self.rigid_body_set.insert(rigid_body);
+        let (index, generation) = handle.into_raw_parts();
+        encode_handle_for_js(index, generation)
     }
 
-    pub fn body_sleep(&mut self, handle: f64) -> bool {
-        let (index, generation) = decode_handle_from_js(handle);
-        let handle = RigidBodyHandle::from_raw_parts(index, generation);
-        if let Some(body) = self.rigid_body_set.get_mut(handle) {
-            body.sleep();
-            true
-        } else {
-            false
-        }
+    // Returns None for an unknown body type or user data a JS number cannot hold.
+    pub fn create_body(&mut self, desc: &[f64]) -> Option<f64> {
+        let value = |index: usize, default: f64| desc.get(index).copied().unwrap_or(default);
+        let body_type = match value(0, 0.0) {
+            0.0 => RigidBodyType::Dynamic,
+            1.0 => RigidBodyType::Fixed,
+            2.0 => RigidBodyType::KinematicPositionBased,
+            3.0 => RigidBodyType::KinematicVelocityBased,
+            _ => return None,
+        };
+        let user_data = user_data_from_js(value(21, 0.0))?;
+        let rigid_body = RigidBodyBuilder::new(body_type)
+            .position(Isometry::from_parts(
+                Translation3::new(
+                    value(1, 0.0) as Real,
+                    value(2, 0.0) as Real,
+                    value(3, 0.0) as Real,
+                ),
+                UnitQuaternion::from_quaternion(Quaternion::new(
+                    value(7, 1.0) as Real,
+                    value(4, 0.0) as Real,
+                    value(5, 0.0) as Real,
+                    value(6, 0.0) as Real,
+                )),
+            ))
+            .linvel(Vector3::new(
+                value(8, 0.0) as Real,
+                value(9, 0.0) as Real,
+                value(10, 0.0) as Real,
+            ))
+            .angvel(Vector3::new(
+                value(11, 0.0) as Real,
+                value(12, 0.0) as Real,
+                value(13, 0.0) as Real,
+            ))
+            .linear_damping(value(14, 0.0) as Real)
+            .angular_damping(value(15, 0.0) as Real)
+            .gravity_scale(value(16, 1.0) as Real)
+            .ccd_enabled(value(17, 0.0) != 0.0)
+            .sleeping(value(18, 0.0) != 0.0)
+            .can_sleep(value(19, 1.0) != 0.0)
+            .locked_axes(LockedAxes::from_bits_truncate(value(20, 0.0) as u8))
+            .user_data(user_data)
+            .build();
+        let handle = self.rigid_body_set.insert(rigid_body);
+        let (index, generation) = handle.into_raw_parts();
+        Some(encode_handle_for_js(index, generation))
     }
 
-    pub fn is_body_sleeping(&self, handle: f64) -> bool {
+    // Rapier always removes the impulse and multibody joints attached to the body; only the
+    // colliders can be kept, in which case they stay in the world without a parent.
+    pub fn remove_rigid_body(&mut self, handle: f64, remove_attached_colliders: bool) -> bool {
         let (index, generation) = decode_handle_from_js(handle);
         let handle = RigidBodyHandle::from_raw_parts(index, generation);
-        if let Some(body) = self.rigid_body_set.get(handle) {
-            body.is_sleeping()
-        } else {
-            false
+        let Some(body) = self.rigid_body_set.get(handle) else {
+            return false;
+        };
+        let colliders = body.colliders().to_vec();
+        let joints: Vec<ImpulseJointHandle> = self
+            .impulse_joint_set
+            .attached_joints(handle)
+            .map(|(_, _, joint_handle, _)| joint_handle)
+            .collect();
+        let removed = self
+            .rigid_body_set
+            .remove(
+                handle,
+                &mut self.island_manager,
+                &mut self.collider_set,
+                &mut self.impulse_joint_set,
+                &mut self.multibody_joint_set,
+                remove_attached_colliders,
+            )
+            .is_some();
+        if removed {
+            if remove_attached_colliders {
+                for collider_handle in colliders {
+                    self.physics_hooks.remove_collider(collider_handle);
+                }
+            }
+            for joint_handle in joints {
+                self.joint_break_thresholds.remove(&joint_handle);
+            }
         }
+        removed
     }
 
-    pub fn set_body_soft_ccd_prediction(&mut self, handle: f64, prediction: Real) -> bool {
+    pub fn remove_collider(&mut self, handle: f64, wake_up: bool) -> bool {
         let (index, generation) = decode_handle_from_js(handle);
-        let handle = RigidBodyHandle::from_raw_parts(index, generation);
-        if let Some(body) = self.rigid_body_set.get_mut(handle) {
-            body.set_soft_ccd_prediction(prediction);
-            true
-        } else {
-            false
+        let handle = ColliderHandle::from_raw_parts(index, generation);
+        let removed = self
+            .collider_set
+            .remove(
+                handle,
+                &mut self.island_manager,
+                &mut self.rigid_body_set,
+                wake_up,
+            )
+            .is_some();
+        if removed {
+            self.physics_hooks.remove_collider(handle);
         }
+        removed
     }
 
-    pub fn set_body_ccd_enabled(&mut self, handle: f64, enabled: bool) -> bool {
+    pub fn remove_impulse_joint(&mut self, handle: f64, wake_up: bool) -> bool {
         let (index, generation) = decode_handle_from_js(handle);
-        let handle = RigidBodyHandle::from_raw_parts(index, generation);
-        if let Some(body) = self.rigid_body_set.get_mut(handle) {
-            body.enable_ccd(enabled);
-            true
-        } else {
-            false
+        let handle = ImpulseJointHandle::from_raw_parts(index, generation);
+        let removed = self.impulse_joint_set.remove(handle, wake_up).is_some();
+        if removed {
+            self.joint_break_thresholds.remove(&handle);
         }
+        removed
     }
 
-    pub fn set_body_additional_solver_iterations(
+    pub fn add_box_collider(
         &mut self,
         handle: f64,
-        iterations: usize,
-    ) -> bool {
-        let (index, generation) = decode_handle_from_js(handle);
-        let handle = RigidBodyHandle::from_raw_parts(index, generation);
-        if let Some(body) = self.rigid_body_set.get_mut(handle) {
-            body.set_additional_solver_iterations(iterations);
-            true
-        } else {
-            false
-        }
+        half_x: Real,
+        half_y: Real,
+        half_z: Real,
+        is_sensor: bool,
+        pos_x: Option<Real>,
+        pos_y: Option<Real>,
+        pos_z: Option<Real>,
+        rot_x: Option<Real>,
+        rot_y: Option<Real>,
+        rot_z: Option<Real>,
+    ) -> Option<f64> {
+        let collider = ColliderBuilder::cuboid(half_x, half_y, half_z)
+            .sensor(is_sensor)
+            .translation(Vector3::new(
+                pos_x.unwrap_or(0.0),
+                pos_y.unwrap_or(0.0),
+                pos_z.unwrap_or(0.0),
+            ))
+            .rotation(Vector3::new(
+                rot_x.unwrap_or(0.0),
+                rot_y.unwrap_or(0.0),
+                rot_z.unwrap_or(0.0),
+            ))
+            .build();
+        self.attach_collider(handle, collider)
     }
 
-    pub fn set_body_angular_damping(&mut self, handle: f64, damping: Real) -> bool {
-        let (index, generation) = decode_handle_from_js(handle);
-        let handle = RigidBodyHandle::from_raw_parts(index, generation);
-        if let Some(body) = self.rigid_body_set.get_mut(handle) {
-            body.set_angular_damping(damping);
-            true
-        } else {
-            false
-        }
+    pub fn add_cylinder_collider(
+        &mut self,
+        handle: f64,
+        half_height: Real,
+        radius: Real,
+        is_sensor: bool,
+        pos_x: Option<Real>,
+        pos_y: Option<Real>,
+        pos_z: Option<Real>,
+        rot_x: Option<Real>,
+        rot_y: Option<Real>,
+        rot_z: Option<Real>,
+    ) -> Option<f64> {
+        let collider = ColliderBuilder::cylinder(half_height, radius)
+            .sensor(is_sensor)
+            .translation(Vector3::new(
+                pos_x.unwrap_or(0.0),
+                pos_y.unwrap_or(0.0),
+                pos_z.unwrap_or(0.0),
+            ))
+            .rotation(Vector3::new(
+                rot_x.unwrap_or(0.0),
+                rot_y.unwrap_or(0.0),
+                rot_z.unwrap_or(0.0),
+            ))
+            .build();
+        self.attach_collider(handle, collider)
     }
 
-    pub fn set_body_linear_damping(&mut self, handle: f64, damping: Real) -> bool {
-        let (index, generation) = decode_handle_from_js(handle);
-        let handle = RigidBodyHandle::from_raw_parts(index, generation);
-        if let Some(body) = self.rigid_body_set.get_mut(handle) {
-            body.set_linear_damping(damping);
-            true
-        } else {
-            false
-        }
-    }
+    pub fn add_trimesh_collider(
+        &mut self,
+        handle: f64,
+        vertices: Vec<Real>,
+        indices: Vec<u32>,
+        is_sensor: bool,
+        trimesh_flags: u32,
+        pos_x: Option<Real>,
+        pos_y: Option<Real>,
+        pos_z: Option<Real>,
+        rot_x: Option<Real>,
+        rot_y: Option<Real>,
+        rot_z: Option<Real>,
+    ) -> Option<f64> {
+        let points: Vec<Point<Real>> = vertices
+            .chunks_exact(3)
+            .map(|chunk| Point::new(chunk[0], chunk[1], chunk[2]))
+            .collect();
 
-    pub fn set_collider_density(&mut self, handle: f64, density: Real) -> bool {
-        let (index, generation) = decode_handle_from_js(handle);
-        let handle = ColliderHandle::from_raw_parts(index, generation);
-        if let Some(collider) = self.collider_set.get_mut(handle) {
-            collider.set_density(density);
-            true
+        let triangles: Vec<[u32; 3]> = indices
+            .chunks_exact(3)
+            .map(|chunk| [chunk[0], chunk[1], chunk[2]])
+            .collect();
+
+        let mut flags = TriMeshFlags::empty();
+        if trimesh_flags & (TriMeshFlags::ORIENTED.bits() as u32) != 0 {
+            flags |= TriMeshFlags::ORIENTED;
+        }
+        if trimesh_flags & (TriMeshFlags::FIX_INTERNAL_EDGES.bits() as u32) != 0 {
+            flags |= TriMeshFlags::FIX_INTERNAL_EDGES;
+        }
+        let collider_builder_result =
+            ColliderBuilder::trimesh_with_flags(points.clone(), triangles.clone(), flags);
+        if let Ok(collider_builder) = collider_builder_result {
+            let collider = collider_builder
+                .sensor(is_sensor)
+                .translation(Vector3::new(
+                    pos_x.unwrap_or(0.0),
+                    pos_y.unwrap_or(0.0),
+                    pos_z.unwrap_or(0.0),
+                ))
+                .rotation(Vector3::new(
+                    rot_x.unwrap_or(0.0),
+                    rot_y.unwrap_or(0.0),
+                    rot_z.unwrap_or(0.0),
+                ))
+                .build();
+            self.attach_collider(handle, collider)
         } else {
-            false
+            None
         }
     }
 
-    pub fn set_collider_friction(&mut self, handle: f64, friction: Real) -> bool {
-        let (index, generation) = decode_handle_from_js(handle);
-        let handle = ColliderHandle::from_raw_parts(index, generation);
-        if let Some(collider) = self.collider_set.get_mut(handle) {
-            collider.set_friction(friction);
-            true
+    pub fn add_convex_hull_collider(
+        &mut self,
+        handle: f64,
+        vertices: Vec<Real>,
+        is_sensor: bool,
+        pos_x: Option<Real>,
+        pos_y: Option<Real>,
+        pos_z: Option<Real>,
+        rot_x: Option<Real>,
+        rot_y: Option<Real>,
+        rot_z: Option<Real>,
+    ) -> Option<f64> {
+        let points: Vec<Point<Real>> = vertices
+            .chunks_exact(3)
+            .map(|chunk| Point::new(chunk[0], chunk[1], chunk[2]))
+            .collect();
+
+        let collider_builder_opt = ColliderBuilder::convex_hull(&points);
+        if let Some(collider_builder) = collider_builder_opt {
+            let collider = collider_builder
+                .sensor(is_sensor)
+                .translation(Vector3::new(
+                    pos_x.unwrap_or(0.0),
+                    pos_y.unwrap_or(0.0),
+                    pos_z.unwrap_or(0.0),
+                ))
+                .rotation(Vector3::new(
+                    rot_x.unwrap_or(0.0),
+                    rot_y.unwrap_or(0.0),
+                    rot_z.unwrap_or(0.0),
+                ))
+                .build();
+            self.attach_collider(handle, collider)
         } else {
-            false
+            None
         }
     }
 
-    pub fn set_collider_restitution(&mut self, handle: f64, restitution: Real) -> bool {
-        let (index, generation) = decode_handle_from_js(handle);
-        let handle = ColliderHandle::from_raw_parts(index, generation);
-        if let Some(collider) = self.collider_set.get_mut(handle) {
-            collider.set_restitution(restitution);
-            true
-        } else {
-            false
-        }
-    }
-
-    pub fn set_collider_collision_groups(&mut self, handle: f64, groups: u32) -> bool {
-        let (index, generation) = decode_handle_from_js(handle);
-        let handle = ColliderHandle::from_raw_parts(index, generation);
-        if let Some(collider) = self.collider_set.get_mut(handle) {
-            collider.set_collision_groups(InteractionGroups::new(
-                Group::from_bits_retain((groups >> 16) as u32),
-                Group::from_bits_retain((groups & 0x0000_ffff) as u32),
-            ));
-            true
-        } else {
-            false
-        }
+    pub fn add_ball_collider(
+        &mut self,
+        handle: f64,
+        radius: Real,
+        is_sensor: bool,
+        pos_x: Option<Real>,
+        pos_y: Option<Real>,
+        pos_z: Option<Real>,
+        rot_x: Option<Real>,
+        rot_y: Option<Real>,
+        rot_z: Option<Real>,
+    ) -> Option<f64> {
+        let collider = ColliderBuilder::ball(radius)
+            .sensor(is_sensor)
+            .translation(Vector3::new(
+                pos_x.unwrap_or(0.0),
+                pos_y.unwrap_or(0.0),
+                pos_z.unwrap_or(0.0),
+            ))
+            .rotation(Vector3::new(
+                rot_x.unwrap_or(0.0),
+                rot_y.unwrap_or(0.0),
+                rot_z.unwrap_or(0.0),
+            ))
+            .build();
+        self.attach_collider(handle, collider)
     }
 
-    pub fn set_collider_contact_skin(&mut self, handle: f64, contact_skin: Real) -> bool {
-        let (index, generation) = decode_handle_from_js(handle);
-        let handle = ColliderHandle::from_raw_parts(index, generation);
-        if let Some(collider) = self.collider_set.get_mut(handle) {
-            collider.set_contact_skin(contact_skin);
-            true
-        } else {
-            false
-        }
+    pub fn add_capsule_collider(
+        &mut self,
+        handle: f64,
+        half_height: Real,
+        radius: Real,
+        is_sensor: bool,
+        pos_x: Option<Real>,
+        pos_y: Option<Real>,
+        pos_z: Option<Real>,
+        rot_x: Option<Real>,
+        rot_y: Option<Real>,
+        rot_z: Option<Real>,
+    ) -> Option<f64> {
+        let collider = ColliderBuilder::capsule_y(half_height, radius)
+            .sensor(is_sensor)
+            .translation(Vector3::new(
+                pos_x.unwrap_or(0.0),
+                pos_y.unwrap_or(0.0),
+                pos_z.unwrap_or(0.0),
+            ))
+            .rotation(Vector3::new(
+                rot_x.unwrap_or(0.0),
+                rot_y.unwrap_or(0.0),
+                rot_z.unwrap_or(0.0),
+            ))
+            .build();
+        self.attach_collider(handle, collider)
     }
 
-    pub fn set_collider_enabled(&mut self, handle: f64, enabled: bool) -> bool {
-        let (index, generation) = decode_handle_from_js(handle);
-        let handle = ColliderHandle::from_raw_parts(index, generation);
-        if let Some(collider) = self.collider_set.get_mut(handle) {
-            collider.set_enabled(enabled);
-            true
-        } else {
-            false
-        }
+    pub fn add_cone_collider(
+        &mut self,
+        handle: f64,
+        half_height: Real,
+        radius: Real,
+        is_sensor: bool,
+        pos_x: Option<Real>,
+        pos_y: Option<Real>,
+        pos_z: Option<Real>,
+        rot_x: Option<Real>,
+        rot_y: Option<Real>,
+        rot_z: Option<Real>,
+    ) -> Option<f64> {
+        let collider = ColliderBuilder::cone(half_height, radius)
+            .sensor(is_sensor)
+            .translation(Vector3::new(
+                pos_x.unwrap_or(0.0),
+                pos_y.unwrap_or(0.0),
+                pos_z.unwrap_or(0.0),
+            ))
+            .rotation(Vector3::new(
+                rot_x.unwrap_or(0.0),
+                rot_y.unwrap_or(0.0),
+                rot_z.unwrap_or(0.0),
+            ))
+            .build();
+        self.attach_collider(handle, collider)
     }
 
-    pub fn is_collider_enabled(&self, handle: f64) -> bool {
-        let (index, generation) = decode_handle_from_js(handle);
-        let handle = ColliderHandle::from_raw_parts(index, generation);
-        if let Some(collider) = self.collider_set.get(handle) {
-            collider.is_enabled()
-        } else {
-            false
-        }
+    pub fn add_round_cylinder_collider(
+        &mut self,
+        handle: f64,
+        half_height: Real,
+        radius: Real,
+        border_radius: Real,
+        is_sensor: bool,
+        pos_x: Option<Real>,
+        pos_y: Option<Real>,
+        pos_z: Option<Real>,
+        rot_x: Option<Real>,
+        rot_y: Option<Real>,
+        rot_z: Option<Real>,
+    ) -> Option<f64> {
+        let collider = ColliderBuilder::round_cylinder(half_height, radius, border_radius)
+            .sensor(is_sensor)
+            .translation(Vector3::new(
+                pos_x.unwrap_or(0.0),
+                pos_y.unwrap_or(0.0),
+                pos_z.unwrap_or(0.0),
+            ))
+            .rotation(Vector3::new(
+                rot_x.unwrap_or(0.0),
+                rot_y.unwrap_or(0.0),
+                rot_z.unwrap_or(0.0),
+            ))
+            .build();
+        self.attach_collider(handle, collider)
     }
 
-    pub fn get_collider_shape_type(&self, handle: f64) -> Option<u32> {
-        let (index, generation) = decode_handle_from_js(handle);
-        let handle = ColliderHandle::from_raw_parts(index, generation);
-        if let Some(collider) = self.collider_set.get(handle) {
-            let shape = collider.shape();
-            if shape.as_cuboid().is_some() {
-                Some(1)
-            } else if shape.as_cylinder().is_some() {
-                Some(10)
-            } else if shape.as_trimesh().is_some() {
-                Some(6)
-            } else if shape.as_convex_polyhedron().is_some() {
-                Some(9)
-            } else {
-                None
-            }
-        } else {
-            None
+    pub fn add_heightfield_collider(
+        &mut self,
+        handle: f64,
+        nrows: usize,
+        ncols: usize,
+        heights: Vec<Real>,
+        scale_x: Real,
+        scale_y: Real,
+        scale_z: Real,
+        is_sensor: bool,
+        pos_x: Option<Real>,
+        pos_y: Option<Real>,
+        pos_z: Option<Real>,
+        rot_x: Option<Real>,
+        rot_y: Option<Real>,
+        rot_z: Option<Real>,
+    ) -> Option<f64> {
+        if nrows == 0 || ncols == 0 || heights.len() != (nrows + 1) * (ncols + 1) {
+            return None;
         }
+        let heights = DMatrix::from_vec(nrows + 1, ncols + 1, heights);
+        let collider =
+            ColliderBuilder::heightfield(heights, Vector3::new(scale_x, scale_y, scale_z))
+                .sensor(is_sensor)
+                .translation(Vector3::new(
+                    pos_x.unwrap_or(0.0),
+                    pos_y.unwrap_or(0.0),
+                    pos_z.unwrap_or(0.0),
+                ))
+                .rotation(Vector3::new(
+                    rot_x.unwrap_or(0.0),
+                    rot_y.unwrap_or(0.0),
+                    rot_z.unwrap_or(0.0),
+                ))
+                .build();
+        self.attach_collider(handle, collider)
     }
 
-    pub fn get_collider_parent(&self, handle: f64) -> Option<f64> {
-        let (index, generation) = decode_handle_from_js(handle);
-        let handle = ColliderHandle::from_raw_parts(index, generation);
-        if let Some(collider) = self.collider_set.get(handle) {
-            if let Some(parent_handle) = collider.parent() {
-                let (index, generation) = parent_handle.into_raw_parts();
-                Some(encode_handle_for_js(index, generation))
-            } else {
-                None
-            }
+    pub fn add_compound_collider(
+        &mut self,
+        handle: f64,
+        shapes: Vec<Real>,
+        is_sensor: bool,
+        pos_x: Option<Real>,
+        pos_y: Option<Real>,
+        pos_z: Option<Real>,
+        rot_x: Option<Real>,
+        rot_y: Option<Real>,
+        rot_z: Option<Real>,
+    ) -> Option<f64> {
+        if let Some(shapes) = decode_compound_shapes(&shapes) {
+            let collider = ColliderBuilder::compound(shapes)
+                .sensor(is_sensor)
+                .translation(Vector3::new(
+                    pos_x.unwrap_or(0.0),
+                    pos_y.unwrap_or(0.0),
+                    pos_z.unwrap_or(0.0),
+                ))
+                .rotation(Vector3::new(
+                    rot_x.unwrap_or(0.0),
+                    rot_y.unwrap_or(0.0),
+                    rot_z.unwrap_or(0.0),
+                ))
+                .build();
+            self.attach_collider(handle, collider)
         } else {
             None
         }
     }
 
-    pub fn get_collider_translation(&self, handle: f64) -> Option<(Real, Real, Real)> {
+    fn attach_collider(&mut self, handle: f64, collider: Collider) -> Option<f64> {
         let (index, generation) = decode_handle_from_js(handle);
-        let handle = ColliderHandle::from_raw_parts(index, generation);
-        if let Some(collider) = self.collider_set.get(handle) {
-            let translation = collider.translation();
-            Some((translation.x, translation.y, translation.z))
-        } else {
-            None
+        let handle = RigidBodyHandle::from_raw_parts(index, generation);
+        if !self.rigid_body_set.contains(handle) {
+            return None;
         }
+        let handle =
+            self.collider_set
+                .insert_with_parent(collider, handle, &mut self.rigid_body_set);
+        self.pending_colliders.push(handle);
+        let (index, generation) = handle.into_raw_parts();
+        Some(encode_handle_for_js(index, generation))
     }
 
-    pub fn get_collider_rotation(&self, handle: f64) -> Option<(Real, Real, Real, Real)> {
-        let (index, generation) = decode_handle_from_js(handle);
-        let handle = ColliderHandle::from_raw_parts(index, generation);
-        if let Some(collider) = self.collider_set.get(handle) {
-            let rotation = collider.rotation();
-            Some((rotation.i, rotation.j, rotation.k, rotation.w))
+    pub fn create_revolute_joint(
+        &mut self,
+        body1_handle: f64,
+        body2_handle: f64,
+        anchor1_x: Real,
+        anchor1_y: Real,
+        anchor1_z: Real,
+        anchor2_x: Real,
+        anchor2_y: Real,
+        anchor2_z: Real,
+        axis_x: Real,
+        axis_y: Real,
+        axis_z: Real,
+        wake_up: bool,
+    ) -> Option<f64> {
+        let (index, generation) = decode_handle_from_js(body1_handle);
+        let handle1: RigidBodyHandle = RigidBodyHandle::from_raw_parts(index, generation);
+        let (index, generation) = decode_handle_from_js(body2_handle);
+        let handle2: RigidBodyHandle = RigidBodyHandle::from_raw_parts(index, generation);
+        if self.rigid_body_set.contains(handle1) && self.rigid_body_set.contains(handle2) {
+            let joint = RevoluteJointBuilder::new(Unit::new_normalize(Vector3::new(
+                axis_x, axis_y, axis_z,
+            )))
+            .local_anchor1(Point::new(anchor1_x, anchor1_y, anchor1_z))
+            .local_anchor2(Point::new(anchor2_x, anchor2_y, anchor2_z))
+            .build();
+            let handle = self
+                .impulse_joint_set
+                .insert(handle1, handle2, joint, wake_up);
+            let (index, generation) = handle.into_raw_parts();
+            return Some(encode_handle_for_js(index, generation));
         } else {
-            None
+            return None;
         }
     }
 
-    pub fn get_collider_vertices(&self, handle: f64) -> Option<Vec<Real>> {
-        let (index, generation) = decode_handle_from_js(handle);
-        let handle = ColliderHandle::from_raw_parts(index, generation);
-        if let Some(collider) = self.collider_set.get(handle) {
-            let shape = collider.shape();
-            if let Some(trimesh) = shape.as_trimesh() {
-                Some(
-                    trimesh
-                        .vertices()
-                        .iter()
-                        .flat_map(|v| v.coords.iter().cloned())
-                        .collect(),
-                )
-            } else if let Some(convex) = shape.as_convex_polyhedron() {
-                Some(
-                    convex
-                        .points()
-                        .iter()
-                        .flat_map(|p| p.coords.iter().cloned())
-                        .collect(),
-                )
+    pub fn create_fixed_joint(
+        &mut self,
+        body1_handle: f64,
+        body2_handle: f64,
+        anchor1_x: Real,
+        anchor1_y: Real,
+        anchor1_z: Real,
+        anchor2_x: Real,
+        anchor2_y: Real,
+        anchor2_z: Real,
+        frame1_x: Real,
+        frame1_y: Real,
+        frame1_z: Real,
+        frame1_w: Real,
+        frame2_x: Real,
+        frame2_y: Real,
+        frame2_z: Real,
+        frame2_w: Real,
+        wake_up: bool,
+    ) -> Option<f64> {
+        let (index, generation) = decode_handle_from_js(body1_handle);
+        let handle1: RigidBodyHandle = RigidBodyHandle::from_raw_parts(index, generation);
+        let (index, generation) = decode_handle_from_js(body2_handle);
+        let handle2: RigidBodyHandle = RigidBodyHandle::from_raw_parts(index, generation);
+        if self.rigid_body_set.contains(handle1) && self.rigid_body_set.contains(handle2) {
+            let joint = FixedJointBuilder::new()
+                .local_anchor1(Point::new(anchor1_x, anchor1_y, anchor1_z))
+                .local_anchor2(Point::new(anchor2_x, anchor2_y, anchor2_z))
+                .local_frame1(Isometry::from_parts(
+                    Translation3::identity(),
+                    UnitQuaternion::from_quaternion(Quaternion::new(
+                        frame1_w, frame1_x, frame1_y, frame1_z,
+                    )),
+                ))
+                .local_frame2(Isometry::from_parts(
+                    Translation3::identity(),
+                    UnitQuaternion::from_quaternion(Quaternion::new(
+                        frame2_w, frame2_x, frame2_y, frame2_z,
+                    )),
+                ))
+                .build();
+            let handle = self
+                .impulse_joint_set
+                .insert(handle1, handle2, joint, wake_up);
+            let (index, generation) = handle.into_raw_parts();
+            return Some(encode_handle_for_js(index, generation));
+        } else {
+            return None;
+        }
+    }
+
+    pub fn create_prismatic_joint(
+        &mut self,
+        body1_handle: f64,
+        body2_handle: f64,
+        anchor1_x: Real,
+        anchor1_y: Real,
+        anchor1_z: Real,
+        anchor2_x: Real,
+        anchor2_y: Real,
+        anchor2_z: Real,
+        axis_x: Real,
+        axis_y: Real,
+        axis_z: Real,
+        wake_up: bool,
+    ) -> Option<f64> {
+        let joint =
+            PrismaticJointBuilder::new(Unit::new_normalize(Vector3::new(axis_x, axis_y, axis_z)))
+                .local_anchor1(Point::new(anchor1_x, anchor1_y, anchor1_z))
+                .local_anchor2(Point::new(anchor2_x, anchor2_y, anchor2_z))
+                .build();
+        self.insert_impulse_joint(body1_handle, body2_handle, joint, wake_up)
+    }
+
+    pub fn create_spherical_joint(
+        &mut self,
+        body1_handle: f64,
+        body2_handle: f64,
+        anchor1_x: Real,
+        anchor1_y: Real,
+        anchor1_z: Real,
+        anchor2_x: Real,
+        anchor2_y: Real,
+        anchor2_z: Real,
+        wake_up: bool,
+    ) -> Option<f64> {
+        let joint = SphericalJointBuilder::new()
+            .local_anchor1(Point::new(anchor1_x, anchor1_y, anchor1_z))
+            .local_anchor2(Point::new(anchor2_x, anchor2_y, anchor2_z))
+            .build();
+        self.insert_impulse_joint(body1_handle, body2_handle, joint, wake_up)
+    }
+
+    pub fn create_rope_joint(
+        &mut self,
+        body1_handle: f64,
+        body2_handle: f64,
+        anchor1_x: Real,
+        anchor1_y: Real,
+        anchor1_z: Real,
+        anchor2_x: Real,
+        anchor2_y: Real,
+        anchor2_z: Real,
+        max_distance: Real,
+        wake_up: bool,
+    ) -> Option<f64> {
+        let joint = RopeJointBuilder::new(max_distance)
+            .local_anchor1(Point::new(anchor1_x, anchor1_y, anchor1_z))
+            .local_anchor2(Point::new(anchor2_x, anchor2_y, anchor2_z))
+            .build();
+        self.insert_impulse_joint(body1_handle, body2_handle, joint, wake_up)
+    }
+
+    pub fn create_spring_joint(
+        &mut self,
+        body1_handle: f64,
+        body2_handle: f64,
+        anchor1_x: Real,
+        anchor1_y: Real,
+        anchor1_z: Real,
+        anchor2_x: Real,
+        anchor2_y: Real,
+        anchor2_z: Real,
+        rest_length: Real,
+        stiffness: Real,
+        damping: Real,
+        wake_up: bool,
+    ) -> Option<f64> {
+        let joint = SpringJointBuilder::new(rest_length, stiffness, damping)
+            .local_anchor1(Point::new(anchor1_x, anchor1_y, anchor1_z))
+            .local_anchor2(Point::new(anchor2_x, anchor2_y, anchor2_z))
+            .build();
+        self.insert_impulse_joint(body1_handle, body2_handle, joint, wake_up)
+    }
+
+    fn insert_impulse_joint(
+        &mut self,
+        body1_handle: f64,
+        body2_handle: f64,
+        joint: impl Into<GenericJoint>,
+        wake_up: bool,
+    ) -> Option<f64> {
+        let (index, generation) = decode_handle_from_js(body1_handle);
+        let handle1 = RigidBodyHandle::from_raw_parts(index, generation);
+        let (index, generation) = decode_handle_from_js(body2_handle);
+        let handle2 = RigidBodyHandle::from_raw_parts(index, generation);
+        if self.rigid_body_set.contains(handle1) && self.rigid_body_set.contains(handle2) {
+            let handle = self
+                .impulse_joint_set
+                .insert(handle1, handle2, joint, wake_up);
+            let (index, generation) = handle.into_raw_parts();
+            Some(encode_handle_for_js(index, generation))
+        } else {
+            None
+        }
+    }
+
+    pub fn set_revolute_joint_limits(&mut self, handle: f64, min: Real, max: Real) -> bool {
+        let (index, generation) = decode_handle_from_js(handle);
+        let handle = ImpulseJointHandle::from_raw_parts(index, generation);
+        if let Some(joint) = self.impulse_joint_set.get_mut(handle, true) {
+            if let Some(revolute_joint) = joint.data.as_revolute_mut() {
+                revolute_joint.set_limits([min, max]);
+                return true;
             } else {
-                None
+                return false;
             }
         } else {
-            None
+            return false;
         }
     }
 
-    pub fn get_collider_indices(&self, handle: f64) -> Option<Vec<u32>> {
+    pub fn configure_revolute_joint_motor(
+        &mut self,
+        handle: f64,
+        target_pos: Real,
+        target_vel: Real,
+        stiffness: Real,
+        damping: Real,
+    ) -> bool {
         let (index, generation) = decode_handle_from_js(handle);
-        let handle = ColliderHandle::from_raw_parts(index, generation);
-        if let Some(collider) = self.collider_set.get(handle) {
-            let shape = collider.shape();
-            if let Some(trimesh) = shape.as_trimesh() {
-                Some(
-                    trimesh
-                        .indices()
-                        .iter()
-                        .flat_map(|arr| arr.iter().cloned())
-                        .collect(),
-                )
+        let handle = ImpulseJointHandle::from_raw_parts(index, generation);
+        if let Some(joint) = self.impulse_joint_set.get_mut(handle, true) {
+            if let Some(revolute_joint) = joint.data.as_revolute_mut() {
+                revolute_joint.set_motor(target_pos, target_vel, stiffness, damping);
+                return true;
             } else {
-                None
+                return false;
             }
         } else {
-            None
+            return false;
         }
     }
 
-    pub fn get_collider_half_extents(&self, handle: f64) -> Option<(Real, Real, Real)> {
+    pub fn set_prismatic_joint_limits(&mut self, handle: f64, min: Real, max: Real) -> bool {
         let (index, generation) = decode_handle_from_js(handle);
-        let handle = ColliderHandle::from_raw_parts(index, generation);
-        if let Some(collider) = self.collider_set.get(handle) {
-            if let Some(cuboid) = collider.shape().as_cuboid() {
-                Some((
-                    cuboid.half_extents.x,
-                    cuboid.half_extents.y,
-                    cuboid.half_extents.z,
-                ))
+        let handle = ImpulseJointHandle::from_raw_parts(index, generation);
+        if let Some(joint) = self.impulse_joint_set.get_mut(handle, true) {
+            if let Some(prismatic_joint) = joint.data.as_prismatic_mut() {
+                prismatic_joint.set_limits([min, max]);
+                true
             } else {
-                None
+                false
+            }
+        } else {
+            false
+        }
+    }
+
+    pub fn configure_prismatic_joint_motor(
+        &mut self,
+        handle: f64,
+        target_pos: Real,
+        target_vel: Real,
+        stiffness: Real,
+        damping: Real,
+    ) -> bool {
+        let (index, generation) = decode_handle_from_js(handle);
+        let handle = ImpulseJointHandle::from_raw_parts(index, generation);
+        if let Some(joint) = self.impulse_joint_set.get_mut(handle, true) {
+            if let Some(prismatic_joint) = joint.data.as_prismatic_mut() {
+                prismatic_joint.set_motor(target_pos, target_vel, stiffness, damping);
+                true
+            } else {
+                false
+            }
+        } else {
+            false
+        }
+    }
+
+    pub fn set_prismatic_joint_motor_max_force(&mut self, handle: f64, max_force: Real) -> bool {
+        let (index, generation) = decode_handle_from_js(handle);
+        let handle = ImpulseJointHandle::from_raw_parts(index, generation);
+        if let Some(joint) = self.impulse_joint_set.get_mut(handle, true) {
+            if let Some(prismatic_joint) = joint.data.as_prismatic_mut() {
+                prismatic_joint.set_motor_max_force(max_force);
+                true
+            } else {
+                false
+            }
+        } else {
+            false
+        }
+    }
+
+    pub fn set_spherical_joint_limits(
+        &mut self,
+        handle: f64,
+        axis: u32,
+        min: Real,
+        max: Real,
+    ) -> bool {
+        let (index, generation) = decode_handle_from_js(handle);
+        let handle = ImpulseJointHandle::from_raw_parts(index, generation);
+        if let Some(joint) = self.impulse_joint_set.get_mut(handle, true) {
+            if let (Some(spherical_joint), Some(axis)) =
+                (joint.data.as_spherical_mut(), angular_joint_axis(axis))
+            {
+                spherical_joint.set_limits(axis, [min, max]);
+                true
+            } else {
+                false
+            }
+        } else {
+            false
+        }
+    }
+
+    pub fn configure_spherical_joint_motor(
+        &mut self,
+        handle: f64,
+        axis: u32,
+        target_pos: Real,
+        target_vel: Real,
+        stiffness: Real,
+        damping: Real,
+    ) -> bool {
+        let (index, generation) = decode_handle_from_js(handle);
+        let handle = ImpulseJointHandle::from_raw_parts(index, generation);
+        if let Some(joint) = self.impulse_joint_set.get_mut(handle, true) {
+            if let (Some(spherical_joint), Some(axis)) =
+                (joint.data.as_spherical_mut(), angular_joint_axis(axis))
+            {
+                spherical_joint.set_motor(axis, target_pos, target_vel, stiffness, damping);
+                true
+            } else {
+                false
             }
         } else {
-            None
+            false
+        }
+    }
+
+    pub fn set_rope_joint_max_distance(&mut self, handle: f64, max_distance: Real) -> bool {
+        let (index, generation) = decode_handle_from_js(handle);
+        let handle = ImpulseJointHandle::from_raw_parts(index, generation);
+        if let Some(joint) = self.impulse_joint_set.get_mut(handle, true) {
+            if is_rope_joint(&joint.data) {
+                joint.data.set_limits(JointAxis::LinX, [0.0, max_distance]);
+                true
+            } else {
+                false
+            }
+        } else {
+            false
+        }
+    }
+
+    pub fn configure_spring_joint(
+        &mut self,
+        handle: f64,
+        rest_length: Real,
+        stiffness: Real,
+        damping: Real,
+    ) -> bool {
+        let (index, generation) = decode_handle_from_js(handle);
+        let handle = ImpulseJointHandle::from_raw_parts(index, generation);
+        if let Some(joint) = self.impulse_joint_set.get_mut(handle, true) {
+            if is_spring_joint(&joint.data) {
+                joint
+                    .data
+                    .set_motor_position(JointAxis::LinX, rest_length, stiffness, damping);
+                true
+            } else {
+                false
+            }
+        } else {
+            false
+        }
+    }
+
+    pub fn get_joint_data(&self, handle: f64) -> Option<Vec<f64>> {
+        let (index, generation) = decode_handle_from_js(handle);
+        let handle = ImpulseJointHandle::from_raw_parts(index, generation);
+        let joint = self.impulse_joint_set.get(handle)?;
+        encode_joint_data(joint.body1, joint.body2, &joint.data)
+    }
+
+    pub fn create_multibody_revolute_joint(
+        &mut self,
+        body1_handle: f64,
+        body2_handle: f64,
+        anchor1_x: Real,
+        anchor1_y: Real,
+        anchor1_z: Real,
+        anchor2_x: Real,
+        anchor2_y: Real,
+        anchor2_z: Real,
+        axis_x: Real,
+        axis_y: Real,
+        axis_z: Real,
+        wake_up: bool,
+    ) -> Option<f64> {
+        let joint =
+            RevoluteJointBuilder::new(Unit::new_normalize(Vector3::new(axis_x, axis_y, axis_z)))
+                .local_anchor1(Point::new(anchor1_x, anchor1_y, anchor1_z))
+                .local_anchor2(Point::new(anchor2_x, anchor2_y, anchor2_z))
+                .build();
+        self.insert_multibody_joint(body1_handle, body2_handle, joint, wake_up)
+    }
+
+    pub fn create_multibody_prismatic_joint(
+        &mut self,
+        body1_handle: f64,
+        body2_handle: f64,
+        anchor1_x: Real,
+        anchor1_y: Real,
+        anchor1_z: Real,
+        anchor2_x: Real,
+        anchor2_y: Real,
+        anchor2_z: Real,
+        axis_x: Real,
+        axis_y: Real,
+        axis_z: Real,
+        wake_up: bool,
+    ) -> Option<f64> {
+        let joint =
+            PrismaticJointBuilder::new(Unit::new_normalize(Vector3::new(axis_x, axis_y, axis_z)))
+                .local_anchor1(Point::new(anchor1_x, anchor1_y, anchor1_z))
+                .local_anchor2(Point::new(anchor2_x, anchor2_y, anchor2_z))
+                .build();
+        self.insert_multibody_joint(body1_handle, body2_handle, joint, wake_up)
+    }
+
+    pub fn create_multibody_fixed_joint(
+        &mut self,
+        body1_handle: f64,
+        body2_handle: f64,
+        anchor1_x: Real,
+        anchor1_y: Real,
+        anchor1_z: Real,
+        anchor2_x: Real,
+        anchor2_y: Real,
+        anchor2_z: Real,
+        frame1_x: Real,
+        frame1_y: Real,
+        frame1_z: Real,
+        frame1_w: Real,
+        frame2_x: Real,
+        frame2_y: Real,
+        frame2_z: Real,
+        frame2_w: Real,
+        wake_up: bool,
+    ) -> Option<f64> {
+        let joint = FixedJointBuilder::new()
+            .local_anchor1(Point::new(anchor1_x, anchor1_y, anchor1_z))
+            .local_anchor2(Point::new(anchor2_x, anchor2_y, anchor2_z))
+            .local_frame1(Isometry::from_parts(
+                Translation3::identity(),
+                UnitQuaternion::from_quaternion(Quaternion::new(
+                    frame1_w, frame1_x, frame1_y, frame1_z,
+                )),
+            ))
+            .local_frame2(Isometry::from_parts(
+                Translation3::identity(),
+                UnitQuaternion::from_quaternion(Quaternion::new(
+                    frame2_w, frame2_x, frame2_y, frame2_z,
+                )),
+            ))
+            .build();
+        self.insert_multibody_joint(body1_handle, body2_handle, joint, wake_up)
+    }
+
+    fn insert_multibody_joint(
+        &mut self,
+        body1_handle: f64,
+        body2_handle: f64,
+        joint: impl Into<GenericJoint>,
+        wake_up: bool,
+    ) -> Option<f64> {
+        let (index, generation) = decode_handle_from_js(body1_handle);
+        let handle1 = RigidBodyHandle::from_raw_parts(index, generation);
+        let (index, generation) = decode_handle_from_js(body2_handle);
+        let handle2 = RigidBodyHandle::from_raw_parts(index, generation);
+        if !self.rigid_body_set.contains(handle1) || !self.rigid_body_set.contains(handle2) {
+            return None;
+        }
+        if let Some(handle) = self
+            .multibody_joint_set
+            .insert(handle1, handle2, joint, wake_up)
+        {
+            let (index, generation) = handle.into_raw_parts();
+            Some(encode_handle_for_js(index, generation))
+        } else {
+            None
+        }
+    }
+
+    pub fn remove_multibody_joint(&mut self, handle: f64, wake_up: bool) -> bool {
+        let (index, generation) = decode_handle_from_js(handle);
+        let handle = MultibodyJointHandle::from_raw_parts(index, generation);
+        if self.multibody_joint_set.get(handle).is_some() {
+            self.multibody_joint_set.remove(handle, wake_up);
+            true
+        } else {
+            false
+        }
+    }
+
+    pub fn set_multibody_joint_limits(&mut self, handle: f64, min: Real, max: Real) -> bool {
+        self.update_multibody_joint(handle, |joint, axis| {
+            joint.set_limits(axis, [min, max]);
+        })
+    }
+
+    pub fn configure_multibody_joint_motor(
+        &mut self,
+        handle: f64,
+        target_pos: Real,
+        target_vel: Real,
+        stiffness: Real,
+        damping: Real,
+    ) -> bool {
+        self.update_multibody_joint(handle, |joint, axis| {
+            joint.set_motor(axis, target_pos, target_vel, stiffness, damping);
+        })
+    }
+
+    pub fn set_multibody_joint_motor_max_force(&mut self, handle: f64, max_force: Real) -> bool {
+        self.update_multibody_joint(handle, |joint, axis| {
+            joint.set_motor_max_force(axis, max_force);
+        })
+    }
+
+    // Applies `update` to the free axis of a revolute or prismatic link and
+    // wakes the link up so the change takes effect on the next step.
+    fn update_multibody_joint(
+        &mut self,
+        handle: f64,
+        update: impl FnOnce(&mut GenericJoint, JointAxis),
+    ) -> bool {
+        let (index, generation) = decode_handle_from_js(handle);
+        let handle = MultibodyJointHandle::from_raw_parts(index, generation);
+        let Some((multibody, link_id)) = self.multibody_joint_set.get_mut(handle) else {
+            return false;
+        };
+        let Some(link) = multibody.link_mut(link_id) else {
+            return false;
+        };
+        let joint = &mut link.joint.data;
+        let axis = if joint.as_revolute().is_some() {
+            JointAxis::AngX
+        } else if joint.as_prismatic().is_some() {
+            JointAxis::LinX
+        } else {
+            return false;
+        };
+        update(joint, axis);
+        let body_handle = link.rigid_body_handle();
+        self.island_manager
+            .wake_up(&mut self.rigid_body_set, body_handle, true);
+        true
+    }
+
+    pub fn get_multibody_joint_link_position(&self, handle: f64) -> Option<[Real; 7]> {
+        let (index, generation) = decode_handle_from_js(handle);
+        let handle = MultibodyJointHandle::from_raw_parts(index, generation);
+        let (multibody, link_id) = self.multibody_joint_set.get(handle)?;
+        let position = multibody.link(link_id)?.local_to_world();
+        Some([
+            position.translation.x,
+            position.translation.y,
+            position.translation.z,
+            position.rotation.i,
+            position.rotation.j,
+            position.rotation.k,
+            position.rotation.w,
+        ])
+    }
+
+    pub fn get_multibody_joint_data(&self, handle: f64) -> Option<Vec<f64>> {
+        let (index, generation) = decode_handle_from_js(handle);
+        let handle = MultibodyJointHandle::from_raw_parts(index, generation);
+        let (multibody, link_id) = self.multibody_joint_set.get(handle)?;
+        let link = multibody.link(link_id)?;
+        let parent = multibody.link(link.parent_id()?)?;
+        encode_joint_data(
+            parent.rigid_body_handle(),
+            link.rigid_body_handle(),
+            &link.joint.data,
+        )
+    }
+
+    pub fn set_body_next_kinematic_translation(
+        &mut self,
+        handle: f64,
+        x: Real,
+        y: Real,
+        z: Real,
+    ) -> bool {
+        let (index, generation) = decode_handle_from_js(handle);
+        let handle = RigidBodyHandle::from_raw_parts(index, generation);
+        if let Some(body) = self.rigid_body_set.get_mut(handle) {
+            body.set_next_kinematic_translation(Vector3::new(x, y, z));
+            true
+        } else {
+            false
+        }
+    }
+
+    pub fn set_body_translation(
+        &mut self,
+        handle: f64,
+        x: Real,
+        y: Real,
+        z: Real,
+        wake_up: bool,
+    ) -> bool {
+        let (index, generation) = decode_handle_from_js(handle);
+        let handle = RigidBodyHandle::from_raw_parts(index, generation);
+        if let Some(body) = self.rigid_body_set.get_mut(handle) {
+            body.set_translation(Vector3::new(x, y, z), wake_up);
+            true
+        } else {
+            false
+        }
+    }
+
+    pub fn set_body_next_kinematic_rotation(
+        &mut self,
+        handle: f64,
+        x: Real,
+        y: Real,
+        z: Real,
+        w: Real,
+    ) -> bool {
+        let (index, generation) = decode_handle_from_js(handle);
+        let handle = RigidBodyHandle::from_raw_parts(index, generation);
+        if let Some(body) = self.rigid_body_set.get_mut(handle) {
+            let rotation = UnitQuaternion::from_quaternion(Quaternion::new(w, x, y, z));
+            body.set_next_kinematic_rotation(rotation);
+            true
+        } else {
+            false
+        }
+    }
+
+    pub fn set_body_rotation(
+        &mut self,
+        handle: f64,
+        x: Real,
+        y: Real,
+        z: Real,
+        w: Real,
+        wake_up: bool,
+    ) -> bool {
+        let (index, generation) = decode_handle_from_js(handle);
+        let handle = RigidBodyHandle::from_raw_parts(index, generation);
+        if let Some(body) = self.rigid_body_set.get_mut(handle) {
+            let rotation = UnitQuaternion::from_quaternion(Quaternion::new(w, x, y, z));
+            body.set_rotation(rotation, wake_up);
+            true
+        } else {
+            false
+        }
+    }
+
+    pub fn set_body_velocity(
+        &mut self,
+        handle: f64,
+        vx: Real,
+        vy: Real,
+        vz: Real,
+        wake_up: bool,
+    ) -> bool {
+        let (index, generation) = decode_handle_from_js(handle);
+        let handle = RigidBodyHandle::from_raw_parts(index, generation);
+        if let Some(body) = self.rigid_body_set.get_mut(handle) {
+            let lin_velocity = Vector3::new(vx, vy, vz);
+            body.set_linvel(lin_velocity, wake_up);
+            true
+        } else {
+            false
+        }
+    }
+
+    pub fn set_body_angular_velocity(
+        &mut self,
+        handle: f64,
+        wx: Real,
+        wy: Real,
+        wz: Real,
+        wake_up: bool,
+    ) -> bool {
+        let (index, generation) = decode_handle_from_js(handle);
+        let handle = RigidBodyHandle::from_raw_parts(index, generation);
+        if let Some(body) = self.rigid_body_set.get_mut(handle) {
+            let ang_velocity = Vector3::new(wx, wy, wz);
+            body.set_angvel(ang_velocity, wake_up);
+            true
+        } else {
+            false
+        }
+    }
+
+    pub fn apply_impulse(&mut self, handle: f64, x: Real, y: Real, z: Real, wake_up: bool) -> bool {
+        let (index, generation) = decode_handle_from_js(handle);
+        let handle = RigidBodyHandle::from_raw_parts(index, generation);
+        if let Some(body) = self.rigid_body_set.get_mut(handle) {
+            let impulse = Vector3::new(x, y, z);
+            body.apply_impulse(impulse, wake_up);
+            true
+        } else {
+            false
+        }
+    }
+
+    pub fn add_force(&mut self, handle: f64, x: Real, y: Real, z: Real, wake_up: bool) -> bool {
+        let (index, generation) = decode_handle_from_js(handle);
+        let handle = RigidBodyHandle::from_raw_parts(index, generation);
+        if let Some(body) = self.rigid_body_set.get_mut(handle) {
+            body.add_force(Vector3::new(x, y, z), wake_up);
+            true
+        } else {
+            false
+        }
+    }
+
+    pub fn add_torque(&mut self, handle: f64, x: Real, y: Real, z: Real, wake_up: bool) -> bool {
+        let (index, generation) = decode_handle_from_js(handle);
+        let handle = RigidBodyHandle::from_raw_parts(index, generation);
+        if let Some(body) = self.rigid_body_set.get_mut(handle) {
+            body.add_torque(Vector3::new(x, y, z), wake_up);
+            true
+        } else {
+            false
+        }
+    }
+
+    pub fn apply_torque_impulse(
+        &mut self,
+        handle: f64,
+        x: Real,
+        y: Real,
+        z: Real,
+        wake_up: bool,
+    ) -> bool {
+        let (index, generation) = decode_handle_from_js(handle);
+        let handle = RigidBodyHandle::from_raw_parts(index, generation);
+        if let Some(body) = self.rigid_body_set.get_mut(handle) {
+            body.apply_torque_impulse(Vector3::new(x, y, z), wake_up);
+            true
+        } else {
+            false
+        }
+    }
+
+    pub fn apply_impulse_at_point(
+        &mut self,
+        handle: f64,
+        impulse_x: Real,
+        impulse_y: Real,
+        impulse_z: Real,
+        point_x: Real,
+        point_y: Real,
+        point_z: Real,
+        wake_up: bool,
+    ) -> bool {
+        let (index, generation) = decode_handle_from_js(handle);
+        let handle = RigidBodyHandle::from_raw_parts(index, generation);
+        if let Some(body) = self.rigid_body_set.get_mut(handle) {
+            body.apply_impulse_at_point(
+                Vector3::new(impulse_x, impulse_y, impulse_z),
+                Point::new(point_x, point_y, point_z),
+                wake_up,
+            );
+            true
+        } else {
+            false
+        }
+    }
+
+    pub fn add_force_at_point(
+        &mut self,
+        handle: f64,
+        force_x: Real,
+        force_y: Real,
+        force_z: Real,
+        point_x: Real,
+        point_y: Real,
+        point_z: Real,
+        wake_up: bool,
+    ) -> bool {
+        let (index, generation) = decode_handle_from_js(handle);
+        let handle = RigidBodyHandle::from_raw_parts(index, generation);
+        if let Some(body) = self.rigid_body_set.get_mut(handle) {
+            body.add_force_at_point(
+                Vector3::new(force_x, force_y, force_z),
+                Point::new(point_x, point_y, point_z),
+                wake_up,
+            );
+            true
+        } else {
+            false
+        }
+    }
+
+    pub fn reset_forces(&mut self, handle: f64, wake_up: bool) -> bool {
+        let (index, generation) = decode_handle_from_js(handle);
+        let handle = RigidBodyHandle::from_raw_parts(index, generation);
+        if let Some(body) = self.rigid_body_set.get_mut(handle) {
+            body.reset_forces(wake_up);
+            true
+        } else {
+            false
+        }
+    }
+
+    pub fn reset_torques(&mut self, handle: f64, wake_up: bool) -> bool {
+        let (index, generation) = decode_handle_from_js(handle);
+        let handle = RigidBodyHandle::from_raw_parts(index, generation);
+        if let Some(body) = self.rigid_body_set.get_mut(handle) {
+            body.reset_torques(wake_up);
+            true
+        } else {
+            false
+        }
+    }
+
+    pub fn set_gravity_scale(&mut self, handle: f64, scale: Real, wake_up: bool) -> bool {
+        let (index, generation) = decode_handle_from_js(handle);
+        let handle = RigidBodyHandle::from_raw_parts(index, generation);
+        if let Some(body) = self.rigid_body_set.get_mut(handle) {
+            body.set_gravity_scale(scale, wake_up);
+            true
+        } else {
+            false
+        }
+    }
+
+    pub fn set_dominance_group(&mut self, handle: f64, group: i8) -> bool {
+        let (index, generation) = decode_handle_from_js(handle);
+        let handle = RigidBodyHandle::from_raw_parts(index, generation);
+        if let Some(body) = self.rigid_body_set.get_mut(handle) {
+            body.set_dominance_group(group);
+            true
+        } else {
+            false
+        }
+    }
+
+    pub fn lock_rotations(&mut self, handle: f64, locked: bool, wake_up: bool) -> bool {
+        let (index, generation) = decode_handle_from_js(handle);
+        let handle = RigidBodyHandle::from_raw_parts(index, generation);
+        if let Some(body) = self.rigid_body_set.get_mut(handle) {
+            body.lock_rotations(locked, wake_up);
+            true
+        } else {
+            false
+        }
+    }
+
+    pub fn lock_translations(&mut self, handle: f64, locked: bool, wake_up: bool) -> bool {
+        let (index, generation) = decode_handle_from_js(handle);
+        let handle = RigidBodyHandle::from_raw_parts(index, generation);
+        if let Some(body) = self.rigid_body_set.get_mut(handle) {
+            body.lock_translations(locked, wake_up);
+            true
+        } else {
+            false
+        }
+    }
+
+    pub fn get_body_translation(&self, handle: f64) -> Option<(Real, Real, Real)> {
+        let (index, generation) = decode_handle_from_js(handle);
+        let handle = RigidBodyHandle::from_raw_parts(index, generation);
+        if let Some(body) = self.rigid_body_set.get(handle) {
+            let translation = body.translation();
+            Some((translation.x, translation.y, translation.z))
+        } else {
+            None
+        }
+    }
+
+    pub fn get_body_rotation(&self, handle: f64) -> Option<(Real, Real, Real, Real)> {
+        let (index, generation) = decode_handle_from_js(handle);
+        let handle = RigidBodyHandle::from_raw_parts(index, generation);
+        if let Some(body) = self.rigid_body_set.get(handle) {
+            let rotation = body.rotation();
+            Some((rotation.i, rotation.j, rotation.k, rotation.w))
+        } else {
+            None
+        }
+    }
+
+    pub fn get_body_count(&self) -> usize {
+        self.rigid_body_set.len()
+    }
+
+    pub fn get_body_handles(&self) -> Vec<f64> {
+        self.rigid_body_set
+            .iter()
+            .map(|(handle, _)| {
+                let (index, generation) = handle.into_raw_parts();
+                encode_handle_for_js(index, generation)
+            })
+            .collect()
+    }
+
+    pub fn get_body_translations(&self) -> Vec<Real> {
+        self.rigid_body_set
+            .iter()
+            .flat_map(|(_, body)| {
+                let translation = body.translation();
+                [translation.x, translation.y, translation.z]
+            })
+            .collect()
+    }
+
+    pub fn get_body_rotations(&self) -> Vec<Real> {
+        self.rigid_body_set
+            .iter()
+            .flat_map(|(_, body)| {
+                let rotation = body.rotation();
+                [rotation.i, rotation.j, rotation.k, rotation.w]
+            })
+            .collect()
+    }
+
+    // Writes [x, y, z, qx, qy, qz, qw] per body in the order of `get_body_handles`.
+    pub fn write_body_poses(&self, out: &mut [Real]) -> Option<usize> {
+        let count = self.rigid_body_set.len();
+        if out.len() < count * 7 {
+            return None;
+        }
+        for ((_, body), pose) in self.rigid_body_set.iter().zip(out.chunks_exact_mut(7)) {
+            let translation = body.translation();
+            let rotation = body.rotation();
+            pose.copy_from_slice(&[
+                translation.x,
+                translation.y,
+                translation.z,
+                rotation.i,
+                rotation.j,
+                rotation.k,
+                rotation.w,
+            ]);
+        }
+        Some(count)
+    }
+
+    // Returns [active_count, (handle, x, y, z, qx, qy, qz, qw) * active_count, asleep handles...],
+    // where the asleep handles are bodies reported active by the previous call that are now asleep.
+    pub fn get_active_body_poses(&mut self) -> Vec<f64> {
+        let active: Vec<RigidBodyHandle> = self
+            .island_manager
+            .active_dynamic_bodies()
+            .iter()
+            .chain(self.island_manager.active_kinematic_bodies())
+            .copied()
+            .filter(|handle| self.rigid_body_set.contains(*handle))
+            .collect();
+
+        let mut out = Vec::with_capacity(1 + active.len() * 8);
+        out.push(active.len() as f64);
+        for handle in &active {
+            let body = &self.rigid_body_set[*handle];
+            let translation = body.translation();
+            let rotation = body.rotation();
+            let (index, generation) = handle.into_raw_parts();
+            out.extend_from_slice(&[
+                encode_handle_for_js(index, generation),
+                translation.x,
+                translation.y,
+                translation.z,
+                rotation.i,
+                rotation.j,
+                rotation.k,
+                rotation.w,
+            ]);
+        }
+
+        let active: HashSet<RigidBodyHandle> = active.into_iter().collect();
+        let mut asleep: Vec<RigidBodyHandle> = self
+            .reported_active_bodies
+            .iter()
+            .filter(|handle| !active.contains(*handle) && self.rigid_body_set.contains(**handle))
+            .copied()
+            .collect();
+        asleep.sort_by_key(|handle| handle.into_raw_parts());
+        out.extend(asleep.into_iter().map(|handle| {
+            let (index, generation) = handle.into_raw_parts();
+            encode_handle_for_js(index, generation)
+        }));
+
+        self.reported_active_bodies = active;
+        out
+    }
+
+    pub fn get_body_velocity(&self, handle: f64) -> Option<(Real, Real, Real)> {
+        let (index, generation) = decode_handle_from_js(handle);
+        let handle = RigidBodyHandle::from_raw_parts(index, generation);
+        if let Some(body) = self.rigid_body_set.get(handle) {
+            let linvel = body.linvel();
+            Some((linvel.x, linvel.y, linvel.z))
+        } else {
+            None
+        }
+    }
+
+    pub fn get_body_angular_velocity(&self, handle: f64) -> Option<(Real, Real, Real)> {
+        let (index, generation) = decode_handle_from_js(handle);
+        let handle = RigidBodyHandle::from_raw_parts(index, generation);
+        if let Some(body) = self.rigid_body_set.get(handle) {
+            let angvel = body.angvel();
+            Some((angvel.x, angvel.y, angvel.z))
+        } else {
+            None
+        }
+    }
+
+    pub fn set_body_enabled_translations(
+        &mut self,
+        handle: f64,
+        enable_x: bool,
+        enable_y: bool,
+        enable_z: bool,
+        wake_up: bool,
+    ) -> bool {
+        let (index, generation) = decode_handle_from_js(handle);
+        let handle = RigidBodyHandle::from_raw_parts(index, generation);
+        if let Some(body) = self.rigid_body_set.get_mut(handle) {
+            body.set_enabled_translations(enable_x, enable_y, enable_z, wake_up);
+            true
+        } else {
+            false
+        }
+    }
+
+    pub fn set_body_enabled_rotations(
+        &mut self,
+        handle: f64,
+        enable_x: bool,
+        enable_y: bool,
+        enable_z: bool,
+        wake_up: bool,
+    ) -> bool {
+        let (index, generation) = decode_handle_from_js(handle);
+        let handle = RigidBodyHandle::from_raw_parts(index, generation);
+        if let Some(body) = self.rigid_body_set.get_mut(handle) {
+            body.set_enabled_rotations(enable_x, enable_y, enable_z, wake_up);
+            true
+        } else {
+            false
+        }
+    }
+
+    pub fn get_body_mass(&self, handle: f64) -> Option<Real> {
+        let (index, generation) = decode_handle_from_js(handle);
+        let handle = RigidBodyHandle::from_raw_parts(index, generation);
+        if let Some(body) = self.rigid_body_set.get(handle) {
+            Some(body.mass())
+        } else {
+            None
+        }
+    }
+
+    pub fn set_body_additional_mass(&mut self, handle: f64, mass: Real, wake_up: bool) -> bool {
+        let (index, generation) = decode_handle_from_js(handle);
+        let handle = RigidBodyHandle::from_raw_parts(index, generation);
+        if let Some(body) = self.rigid_body_set.get_mut(handle) {
+            body.set_additional_mass(mass, wake_up);
+            body.recompute_mass_properties_from_colliders(&self.collider_set);
+            true
+        } else {
+            false
+        }
+    }
+
+    pub fn get_body_center_of_mass(&self, handle: f64) -> Option<(Real, Real, Real)> {
+        let (index, generation) = decode_handle_from_js(handle);
+        let handle = RigidBodyHandle::from_raw_parts(index, generation);
+        if let Some(body) = self.rigid_body_set.get(handle) {
+            let center = body.center_of_mass();
+            Some((center.x, center.y, center.z))
+        } else {
+            None
+        }
+    }
+
+    pub fn get_body_local_center_of_mass(&self, handle: f64) -> Option<(Real, Real, Real)> {
+        let (index, generation) = decode_handle_from_js(handle);
+        let handle = RigidBodyHandle::from_raw_parts(index, generation);
+        if let Some(body) = self.rigid_body_set.get(handle) {
+            let center = body.mass_properties().local_mprops.local_com;
+            Some((center.x, center.y, center.z))
+        } else {
+            None
+        }
+    }
+
+    pub fn get_body_angular_inertia(&self, handle: f64) -> Option<[Real; 7]> {
+        let (index, generation) = decode_handle_from_js(handle);
+        let handle = RigidBodyHandle::from_raw_parts(index, generation);
+        if let Some(body) = self.rigid_body_set.get(handle) {
+            let local_mprops = &body.mass_properties().local_mprops;
+            let inertia = local_mprops.principal_inertia();
+            let frame = local_mprops.principal_inertia_local_frame;
+            Some([
+                inertia.x, inertia.y, inertia.z, frame.i, frame.j, frame.k, frame.w,
+            ])
+        } else {
+            None
+        }
+    }
+
+    pub fn set_body_user_data(&mut self, handle: f64, user_data: u128) -> bool {
+        let (index, generation) = decode_handle_from_js(handle);
+        let handle = RigidBodyHandle::from_raw_parts(index, generation);
+        if let Some(body) = self.rigid_body_set.get_mut(handle) {
+            body.user_data = user_data;
+            true
+        } else {
+            false
+        }
+    }
+
+    pub fn get_body_user_data(&self, handle: f64) -> Option<u128> {
+        let (index, generation) = decode_handle_from_js(handle);
+        let handle = RigidBodyHandle::from_raw_parts(index, generation);
+        self.rigid_body_set.get(handle).map(|body| body.user_data)
+    }
+
+    pub fn get_bodies_user_data(&self) -> Vec<f64> {
+        self.rigid_body_set
+            .iter()
+            .flat_map(|(handle, body)| {
+                let (index, generation) = handle.into_raw_parts();
+                [
+                    encode_handle_for_js(index, generation),
+                    body.user_data as f64,
+                ]
+            })
+            .collect()
+    }
+
+    pub fn set_collider_user_data(&mut self, handle: f64, user_data: u128) -> bool {
+        let (index, generation) = decode_handle_from_js(handle);
+        let handle = ColliderHandle::from_raw_parts(index, generation);
+        if let Some(collider) = self.collider_set.get_mut(handle) {
+            collider.user_data = user_data;
+            true
+        } else {
+            false
+        }
+    }
+
+    pub fn get_collider_user_data(&self, handle: f64) -> Option<u128> {
+        let (index, generation) = decode_handle_from_js(handle);
+        let handle = ColliderHandle::from_raw_parts(index, generation);
+        self.collider_set
+            .get(handle)
+            .map(|collider| collider.user_data)
+    }
+
+    pub fn get_body_collider(&self, handle: f64, collider_index: usize) -> Option<f64> {
+        let (index, generation) = decode_handle_from_js(handle);
+        let handle = RigidBodyHandle::from_raw_parts(index, generation);
+        if let Some(body) = self.rigid_body_set.get(handle) {
+            if let Some(handle) = body.colliders().get(collider_index) {
+                let (index, generation) = handle.into_raw_parts();
+                Some(encode_handle_for_js(index, generation))
+            } else {
+                None
+            }
+        } else {
+            None
+        }
+    }
+
+    pub fn get_body_num_colliders(&self, handle: f64) -> usize {
+        let (index, generation) = decode_handle_from_js(handle);
+        let handle = RigidBodyHandle::from_raw_parts(index, generation);
+        if let Some(body) = self.rigid_body_set.get(handle) {
+            body.colliders().len()
+        } else {
+            0
+        }
+    }
+
+    pub fn step(&mut self, dt: Real) {
+        self.integration_parameters.dt = dt;
+
+        self.physics_pipeline.step(
+            &self.gravity,
+            &self.integration_parameters,
+            &mut self.island_manager,
+            &mut self.broad_phase,
+            &mut self.narrow_phase,
+            &mut self.rigid_body_set,
+            &mut self.collider_set,
+            &mut self.impulse_joint_set,
+            &mut self.multibody_joint_set,
+            &mut self.ccd_solver,
+            &self.physics_hooks,
+            &self.event_handler,
+        );
+        self.pending_colliders.clear();
+        self.break_overloaded_joints();
+    }
+
+    // A joint breaks once the magnitude of the linear or the angular impulse it applied
+    // during the last step exceeds the matching threshold.
+    fn break_overloaded_joints(&mut self) {
+        let impulse_joint_set = &self.impulse_joint_set;
+        let exceeds =
+            |limit: Option<Real>, impulse: Real| limit.is_some_and(|limit| impulse > limit);
+        let mut overloaded: Vec<BrokenJoint> = self
+            .joint_break_thresholds
+            .iter()
+            .filter_map(|(handle, threshold)| {
+                let joint = impulse_joint_set.get(*handle)?;
+                let linear_impulse = joint.impulses.fixed_rows::<3>(0).norm();
+                let angular_impulse = joint.impulses.fixed_rows::<3>(3).norm();
+                (exceeds(threshold.linear, linear_impulse)
+                    || exceeds(threshold.angular, angular_impulse))
+                .then_some(BrokenJoint {
+                    handle: *handle,
+                    body1: joint.body1,
+                    body2: joint.body2,
+                    linear_impulse,
+                    angular_impulse,
+                })
+            })
+            .collect();
+        overloaded.sort_by_key(|broken_joint| broken_joint.handle.into_raw_parts());
+        for broken_joint in overloaded {
+            self.joint_break_thresholds.remove(&broken_joint.handle);
+            self.impulse_joint_set.remove(broken_joint.handle, true);
+            self.broken_joints.push(broken_joint);
+        }
+    }
+
+    pub fn get_joint_impulses(&self, handle: f64) -> Option<[Real; 6]> {
+        let (index, generation) = decode_handle_from_js(handle);
+        let handle = ImpulseJointHandle::from_raw_parts(index, generation);
+        if let Some(joint) = self.impulse_joint_set.get(handle) {
+            let impulses = joint.impulses;
+            Some([
+                impulses[0],
+                impulses[1],
+                impulses[2],
+                impulses[3],
+                impulses[4],
+                impulses[5],
+            ])
+        } else {
+            None
+        }
+    }
+
+    pub fn set_joint_break_threshold(
+        &mut self,
+        handle: f64,
+        linear: Option<Real>,
+        angular: Option<Real>,
+    ) -> bool {
+        let (index, generation) = decode_handle_from_js(handle);
+        let handle = ImpulseJointHandle::from_raw_parts(index, generation);
+        if self.impulse_joint_set.get(handle).is_some() {
+            self.joint_break_thresholds
+                .insert(handle, JointBreakThreshold { linear, angular });
+            true
+        } else {
+            false
+        }
+    }
+
+    pub fn remove_joint_break_threshold(&mut self, handle: f64) -> bool {
+        let (index, generation) = decode_handle_from_js(handle);
+        let handle = ImpulseJointHandle::from_raw_parts(index, generation);
+        self.joint_break_thresholds.remove(&handle).is_some()
+    }
+
+    pub fn drain_broken_joints(&mut self) -> Vec<f64> {
+        self.broken_joints
+            .drain(..)
+            .flat_map(|broken_joint| {
+                let (index, generation) = broken_joint.handle.into_raw_parts();
+                let (index1, generation1) = broken_joint.body1.into_raw_parts();
+                let (index2, generation2) = broken_joint.body2.into_raw_parts();
+                [
+                    encode_handle_for_js(index, generation),
+                    encode_handle_for_js(index1, generation1),
+                    encode_handle_for_js(index2, generation2),
+                    broken_joint.linear_impulse,
+                    broken_joint.angular_impulse,
+                ]
+            })
+            .collect()
+    }
+
+    pub fn drain_collision_events(&mut self) -> Vec<f64> {
+        let collision_events = self
+            .event_handler
+            .collision_events
+            .get_mut()
+            .unwrap_or_else(|poisoned| poisoned.into_inner());
+        collision_events
+            .drain(..)
+            .flat_map(|(event, user_data1, user_data2)| {
+                let (index1, generation1) = event.collider1().into_raw_parts();
+                let (index2, generation2) = event.collider2().into_raw_parts();
+                let mut flags = 0;
+                if event.started() {
+                    flags |= COLLISION_EVENT_STARTED;
+                }
+                if event.sensor() {
+                    flags |= COLLISION_EVENT_SENSOR;
+                }
+                if event.removed() {
+                    flags |= COLLISION_EVENT_REMOVED;
+                }
+                [
+                    encode_handle_for_js(index1, generation1),
+                    encode_handle_for_js(index2, generation2),
+                    flags as f64,
+                    user_data1 as f64,
+                    user_data2 as f64,
+                ]
+            })
+            .collect()
+    }
+
+    pub fn drain_contact_force_events(&mut self) -> Vec<f64> {
+        let contact_force_events = self
+            .event_handler
+            .contact_force_events
+            .get_mut()
+            .unwrap_or_else(|poisoned| poisoned.into_inner());
+        contact_force_events
+            .drain(..)
+            .flat_map(|event| {
+                let (index1, generation1) = event.collider1.into_raw_parts();
+                let (index2, generation2) = event.collider2.into_raw_parts();
+                [
+                    encode_handle_for_js(index1, generation1),
+                    encode_handle_for_js(index2, generation2),
+                    event.total_force_magnitude,
+                    event.max_force_direction.x,
+                    event.max_force_direction.y,
+                    event.max_force_direction.z,
+                ]
+            })
+            .collect()
+    }
+
+    pub fn set_gravity(&mut self, x: Real, y: Real, z: Real) {
+        self.gravity = Vector3::new(x, y, z);
+    }
+
+    pub fn set_body_enabled(&mut self, handle: f64, enabled: bool) -> bool {
+        let (index, generation) = decode_handle_from_js(handle);
+        let handle = RigidBodyHandle::from_raw_parts(index, generation);
+        if let Some(body) = self.rigid_body_set.get_mut(handle) {
+            body.set_enabled(enabled);
+            true
+        } else {
+            false
+        }
+    }
+
+    pub fn is_body_enabled(&self, handle: f64) -> bool {
+        let (index, generation) = decode_handle_from_js(handle);
+        let handle = RigidBodyHandle::from_raw_parts(index, generation);
+        if let Some(body) = self.rigid_body_set.get(handle) {
+            body.is_enabled()
+        } else {
+            false
+        }
+    }
+
+    pub fn body_sleep(&mut self, handle: f64) -> bool {
+        let (index, generation) = decode_handle_from_js(handle);
+        let handle = RigidBodyHandle::from_raw_parts(index, generation);
+        if let Some(body) = self.rigid_body_set.get_mut(handle) {
+            body.sleep();
+            true
+        } else {
+            false
+        }
+    }
+
+    pub fn is_body_sleeping(&self, handle: f64) -> bool {
+        let (index, generation) = decode_handle_from_js(handle);
+        let handle = RigidBodyHandle::from_raw_parts(index, generation);
+        if let Some(body) = self.rigid_body_set.get(handle) {
+            body.is_sleeping()
+        } else {
+            false
+        }
+    }
+
+    pub fn set_body_soft_ccd_prediction(&mut self, handle: f64, prediction: Real) -> bool {
+        let (index, generation) = decode_handle_from_js(handle);
+        let handle = RigidBodyHandle::from_raw_parts(index, generation);
+        if let Some(body) = self.rigid_body_set.get_mut(handle) {
+            body.set_soft_ccd_prediction(prediction);
+            true
+        } else {
+            false
+        }
+    }
+
+    pub fn set_body_ccd_enabled(&mut self, handle: f64, enabled: bool) -> bool {
+        let (index, generation) = decode_handle_from_js(handle);
+        let handle = RigidBodyHandle::from_raw_parts(index, generation);
+        if let Some(body) = self.rigid_body_set.get_mut(handle) {
+            body.enable_ccd(enabled);
+            true
+        } else {
+            false
+        }
+    }
+
+    pub fn set_body_additional_solver_iterations(
+        &mut self,
+        handle: f64,
+        iterations: usize,
+    ) -> bool {
+        let (index, generation) = decode_handle_from_js(handle);
+        let handle = RigidBodyHandle::from_raw_parts(index, generation);
+        if let Some(body) = self.rigid_body_set.get_mut(handle) {
+            body.set_additional_solver_iterations(iterations);
+            true
+        } else {
+            false
+        }
+    }
+
+    pub fn set_body_angular_damping(&mut self, handle: f64, damping: Real) -> bool {
+        let (index, generation) = decode_handle_from_js(handle);
+        let handle = RigidBodyHandle::from_raw_parts(index, generation);
+        if let Some(body) = self.rigid_body_set.get_mut(handle) {
+            body.set_angular_damping(damping);
+            true
+        } else {
+            false
+        }
+    }
+
+    pub fn set_body_linear_damping(&mut self, handle: f64, damping: Real) -> bool {
+        let (index, generation) = decode_handle_from_js(handle);
+        let handle = RigidBodyHandle::from_raw_parts(index, generation);
+        if let Some(body) = self.rigid_body_set.get_mut(handle) {
+            body.set_linear_damping(damping);
+            true
+        } else {
+            false
+        }
+    }
+
+    pub fn set_collider_mass(&mut self, handle: f64, mass: Real) -> bool {
+        let (index, generation) = decode_handle_from_js(handle);
+        let handle = ColliderHandle::from_raw_parts(index, generation);
+        if let Some(collider) = self.collider_set.get_mut(handle) {
+            collider.set_mass(mass);
+            self.refresh_parent_mass_properties(handle);
+            true
+        } else {
+            false
+        }
+    }
+
+    pub fn set_collider_mass_properties(
+        &mut self,
+        handle: f64,
+        mass: Real,
+        center_x: Real,
+        center_y: Real,
+        center_z: Real,
+        inertia_x: Real,
+        inertia_y: Real,
+        inertia_z: Real,
+        frame_x: Real,
+        frame_y: Real,
+        frame_z: Real,
+        frame_w: Real,
+    ) -> bool {
+        let (index, generation) = decode_handle_from_js(handle);
+        let handle = ColliderHandle::from_raw_parts(index, generation);
+        if let Some(collider) = self.collider_set.get_mut(handle) {
+            collider.set_mass_properties(MassProperties::with_principal_inertia_frame(
+                Point::new(center_x, center_y, center_z),
+                mass,
+                Vector3::new(inertia_x, inertia_y, inertia_z),
+                UnitQuaternion::from_quaternion(Quaternion::new(
+                    frame_w, frame_x, frame_y, frame_z,
+                )),
+            ));
+            self.refresh_parent_mass_properties(handle);
+            true
+        } else {
+            false
+        }
+    }
+
+    // Rapier only folds collider mass changes into the parent body during the
+    // next step; recompute now so the body getters reflect the new values.
+    fn refresh_parent_mass_properties(&mut self, handle: ColliderHandle) {
+        let parent = self
+            .collider_set
+            .get(handle)
+            .and_then(|collider| collider.parent());
+        if let Some(body) = parent.and_then(|parent| self.rigid_body_set.get_mut(parent)) {
+            body.recompute_mass_properties_from_colliders(&self.collider_set);
+        }
+    }
+
+    pub fn set_collider_density(&mut self, handle: f64, density: Real) -> bool {
+        let (index, generation) = decode_handle_from_js(handle);
+        let handle = ColliderHandle::from_raw_parts(index, generation);
+        if let Some(collider) = self.collider_set.get_mut(handle) {
+            collider.set_density(density);
+            true
+        } else {
+            false
+        }
+    }
+
+    pub fn set_collider_friction(&mut self, handle: f64, friction: Real) -> bool {
+        let (index, generation) = decode_handle_from_js(handle);
+        let handle = ColliderHandle::from_raw_parts(index, generation);
+        if let Some(collider) = self.collider_set.get_mut(handle) {
+            collider.set_friction(friction);
+            true
+        } else {
+            false
+        }
+    }
+
+    pub fn set_collider_restitution(&mut self, handle: f64, restitution: Real) -> bool {
+        let (index, generation) = decode_handle_from_js(handle);
+        let handle = ColliderHandle::from_raw_parts(index, generation);
+        if let Some(collider) = self.collider_set.get_mut(handle) {
+            collider.set_restitution(restitution);
+            true
+        } else {
+            false
+        }
+    }
+
+    pub fn set_collider_collision_groups(
+        &mut self,
+        handle: f64,
+        memberships: u32,
+        filter: u32,
+    ) -> bool {
+        let (index, generation) = decode_handle_from_js(handle);
+        let handle = ColliderHandle::from_raw_parts(index, generation);
+        if let Some(collider) = self.collider_set.get_mut(handle) {
+            collider.set_collision_groups(InteractionGroups::new(
+                Group::from_bits_retain(memberships),
+                Group::from_bits_retain(filter),
+            ));
+            true
+        } else {
+            false
+        }
+    }
+
+    pub fn set_collider_solver_groups(
+        &mut self,
+        handle: f64,
+        memberships: u32,
+        filter: u32,
+    ) -> bool {
+        let (index, generation) = decode_handle_from_js(handle);
+        let handle = ColliderHandle::from_raw_parts(index, generation);
+        if let Some(collider) = self.collider_set.get_mut(handle) {
+            collider.set_solver_groups(InteractionGroups::new(
+                Group::from_bits_retain(memberships),
+                Group::from_bits_retain(filter),
+            ));
+            true
+        } else {
+            false
+        }
+    }
+
+    pub fn get_collider_collision_groups(&self, handle: f64) -> Option<(u32, u32)> {
+        let (index, generation) = decode_handle_from_js(handle);
+        let handle = ColliderHandle::from_raw_parts(index, generation);
+        if let Some(collider) = self.collider_set.get(handle) {
+            let groups = collider.collision_groups();
+            Some((groups.memberships.bits(), groups.filter.bits()))
+        } else {
+            None
+        }
+    }
+
+    pub fn get_collider_solver_groups(&self, handle: f64) -> Option<(u32, u32)> {
+        let (index, generation) = decode_handle_from_js(handle);
+        let handle = ColliderHandle::from_raw_parts(index, generation);
+        if let Some(collider) = self.collider_set.get(handle) {
+            let groups = collider.solver_groups();
+            Some((groups.memberships.bits(), groups.filter.bits()))
+        } else {
+            None
+        }
+    }
+
+    pub fn set_collider_active_events(&mut self, handle: f64, active_events: u32) -> bool {
+        let (index, generation) = decode_handle_from_js(handle);
+        let handle = ColliderHandle::from_raw_parts(index, generation);
+        if let Some(collider) = self.collider_set.get_mut(handle) {
+            collider.set_active_events(ActiveEvents::from_bits_truncate(active_events));
+            true
+        } else {
+            false
+        }
+    }
+
+    pub fn set_collider_contact_force_event_threshold(
+        &mut self,
+        handle: f64,
+        threshold: Real,
+    ) -> bool {
+        let (index, generation) = decode_handle_from_js(handle);
+        let handle = ColliderHandle::from_raw_parts(index, generation);
+        if let Some(collider) = self.collider_set.get_mut(handle) {
+            collider.set_contact_force_event_threshold(threshold);
+            true
+        } else {
+            false
+        }
+    }
+
+    pub fn set_collider_active_hooks(&mut self, handle: f64, active_hooks: u32) -> bool {
+        let (index, generation) = decode_handle_from_js(handle);
+        let handle = ColliderHandle::from_raw_parts(index, generation);
+        if let Some(collider) = self.collider_set.get_mut(handle) {
+            collider.set_active_hooks(ActiveHooks::from_bits_truncate(active_hooks));
+            true
+        } else {
+            false
+        }
+    }
+
+    pub fn set_collider_pair_ignored(&mut self, handle1: f64, handle2: f64, ignored: bool) {
+        let (index1, generation1) = decode_handle_from_js(handle1);
+        let (index2, generation2) = decode_handle_from_js(handle2);
+        let pair = ordered_collider_pair(
+            ColliderHandle::from_raw_parts(index1, generation1),
+            ColliderHandle::from_raw_parts(index2, generation2),
+        );
+        if ignored {
+            self.physics_hooks.ignored_pairs.insert(pair);
+        } else {
+            self.physics_hooks.ignored_pairs.remove(&pair);
+        }
+    }
+
+    pub fn set_collider_one_way_direction(
+        &mut self,
+        handle: f64,
+        x: Real,
+        y: Real,
+        z: Real,
+        allowed_angle: Real,
+    ) -> bool {
+        let (index, generation) = decode_handle_from_js(handle);
+        let handle = ColliderHandle::from_raw_parts(index, generation);
+        if self.collider_set.contains(handle) {
+            self.physics_hooks
+                .one_way_surfaces
+                .insert(handle, (Vector3::new(x, y, z).normalize(), allowed_angle));
+            true
+        } else {
+            false
+        }
+    }
+
+    pub fn remove_collider_one_way_direction(&mut self, handle: f64) -> bool {
+        let (index, generation) = decode_handle_from_js(handle);
+        let handle = ColliderHandle::from_raw_parts(index, generation);
+        self.physics_hooks
+            .one_way_surfaces
+            .remove(&handle)
+            .is_some()
+    }
+
+    pub fn set_collider_surface_velocity(
+        &mut self,
+        handle: f64,
+        vx: Real,
+        vy: Real,
+        vz: Real,
+    ) -> bool {
+        let (index, generation) = decode_handle_from_js(handle);
+        let handle = ColliderHandle::from_raw_parts(index, generation);
+        if self.collider_set.contains(handle) {
+            self.physics_hooks
+                .surface_velocities
+                .insert(handle, SurfaceVelocity::Linear(Vector3::new(vx, vy, vz)));
+            true
+        } else {
+            false
+        }
+    }
+
+    pub fn set_collider_surface_angular_velocity(
+        &mut self,
+        handle: f64,
+        wx: Real,
+        wy: Real,
+        wz: Real,
+    ) -> bool {
+        let (index, generation) = decode_handle_from_js(handle);
+        let handle = ColliderHandle::from_raw_parts(index, generation);
+        if self.collider_set.contains(handle) {
+            self.physics_hooks
+                .surface_velocities
+                .insert(handle, SurfaceVelocity::Angular(Vector3::new(wx, wy, wz)));
+            true
+        } else {
+            false
+        }
+    }
+
+    pub fn remove_collider_surface_velocity(&mut self, handle: f64) -> bool {
+        let (index, generation) = decode_handle_from_js(handle);
+        let handle = ColliderHandle::from_raw_parts(index, generation);
+        self.physics_hooks
+            .surface_velocities
+            .remove(&handle)
+            .is_some()
+    }
+
+    pub fn set_collider_contact_skin(&mut self, handle: f64, contact_skin: Real) -> bool {
+        let (index, generation) = decode_handle_from_js(handle);
+        let handle = ColliderHandle::from_raw_parts(index, generation);
+        if let Some(collider) = self.collider_set.get_mut(handle) {
+            collider.set_contact_skin(contact_skin);
+            true
+        } else {
+            false
+        }
+    }
+
+    pub fn set_collider_enabled(&mut self, handle: f64, enabled: bool) -> bool {
+        let (index, generation) = decode_handle_from_js(handle);
+        let handle = ColliderHandle::from_raw_parts(index, generation);
+        if let Some(collider) = self.collider_set.get_mut(handle) {
+            collider.set_enabled(enabled);
+            true
+        } else {
+            false
+        }
+    }
+
+    pub fn is_collider_enabled(&self, handle: f64) -> bool {
+        let (index, generation) = decode_handle_from_js(handle);
+        let handle = ColliderHandle::from_raw_parts(index, generation);
+        if let Some(collider) = self.collider_set.get(handle) {
+            collider.is_enabled()
+        } else {
+            false
+        }
+    }
+
+    pub fn get_collider_shape_type(&self, handle: f64) -> Option<u32> {
+        let (index, generation) = decode_handle_from_js(handle);
+        let handle = ColliderHandle::from_raw_parts(index, generation);
+        if let Some(collider) = self.collider_set.get(handle) {
+            let shape = collider.shape();
+            if let Some((shape_type, _)) = primitive_shape_params(shape) {
+                Some(shape_type)
+            } else if shape.as_trimesh().is_some() {
+                Some(6)
+            } else if shape.as_heightfield().is_some() {
+                Some(7)
+            } else if shape.as_compound().is_some() {
+                Some(8)
+            } else if shape.as_convex_polyhedron().is_some() {
+                Some(9)
+            } else {
+                None
+            }
+        } else {
+            None
+        }
+    }
+
+    pub fn get_collider_parent(&self, handle: f64) -> Option<f64> {
+        let (index, generation) = decode_handle_from_js(handle);
+        let handle = ColliderHandle::from_raw_parts(index, generation);
+        if let Some(collider) = self.collider_set.get(handle) {
+            if let Some(parent_handle) = collider.parent() {
+                let (index, generation) = parent_handle.into_raw_parts();
+                Some(encode_handle_for_js(index, generation))
+            } else {
+                None
+            }
+        } else {
+            None
+        }
+    }
+
+    pub fn get_collider_translation(&self, handle: f64) -> Option<(Real, Real, Real)> {
+        let (index, generation) = decode_handle_from_js(handle);
+        let handle = ColliderHandle::from_raw_parts(index, generation);
+        if let Some(collider) = self.collider_set.get(handle) {
+            let translation = collider.translation();
+            Some((translation.x, translation.y, translation.z))
+        } else {
+            None
+        }
+    }
+
+    pub fn get_collider_rotation(&self, handle: f64) -> Option<(Real, Real, Real, Real)> {
+        let (index, generation) = decode_handle_from_js(handle);
+        let handle = ColliderHandle::from_raw_parts(index, generation);
+        if let Some(collider) = self.collider_set.get(handle) {
+            let rotation = collider.rotation();
+            Some((rotation.i, rotation.j, rotation.k, rotation.w))
+        } else {
+            None
+        }
+    }
+
+    pub fn get_collider_vertices(&self, handle: f64) -> Option<Vec<Real>> {
+        let (index, generation) = decode_handle_from_js(handle);
+        let handle = ColliderHandle::from_raw_parts(index, generation);
+        if let Some(collider) = self.collider_set.get(handle) {
+            let shape = collider.shape();
+            if let Some(trimesh) = shape.as_trimesh() {
+                Some(
+                    trimesh
+                        .vertices()
+                        .iter()
+                        .flat_map(|v| v.coords.iter().cloned())
+                        .collect(),
+                )
+            } else if let Some(convex) = shape.as_convex_polyhedron() {
+                Some(
+                    convex
+                        .points()
+                        .iter()
+                        .flat_map(|p| p.coords.iter().cloned())
+                        .collect(),
+                )
+            } else {
+                None
+            }
+        } else {
+            None
+        }
+    }
+
+    pub fn get_collider_indices(&self, handle: f64) -> Option<Vec<u32>> {
+        let (index, generation) = decode_handle_from_js(handle);
+        let handle = ColliderHandle::from_raw_parts(index, generation);
+        if let Some(collider) = self.collider_set.get(handle) {
+            let shape = collider.shape();
+            if let Some(trimesh) = shape.as_trimesh() {
+                Some(
+                    trimesh
+                        .indices()
+                        .iter()
+                        .flat_map(|arr| arr.iter().cloned())
+                        .collect(),
+                )
+            } else {
+                None
+            }
+        } else {
+            None
+        }
+    }
+
+    pub fn get_collider_half_extents(&self, handle: f64) -> Option<(Real, Real, Real)> {
+        let (index, generation) = decode_handle_from_js(handle);
+        let handle = ColliderHandle::from_raw_parts(index, generation);
+        if let Some(collider) = self.collider_set.get(handle) {
+            if let Some(cuboid) = collider.shape().as_cuboid() {
+                Some((
+                    cuboid.half_extents.x,
+                    cuboid.half_extents.y,
+                    cuboid.half_extents.z,
+                ))
+            } else {
+                None
+            }
+        } else {
+            None
+        }
+    }
+
+    pub fn get_collider_radius(&self, handle: f64) -> Option<Real> {
+        let (index, generation) = decode_handle_from_js(handle);
+        let handle = ColliderHandle::from_raw_parts(index, generation);
+        if let Some(collider) = self.collider_set.get(handle) {
+            let shape = collider.shape();
+            if let Some(ball) = shape.as_ball() {
+                Some(ball.radius)
+            } else if let Some(capsule) = shape.as_capsule() {
+                Some(capsule.radius)
+            } else if let Some(cylinder) = shape.as_cylinder() {
+                Some(cylinder.radius)
+            } else if let Some(cone) = shape.as_cone() {
+                Some(cone.radius)
+            } else if let Some(round_cylinder) = shape.as_round_cylinder() {
+                Some(round_cylinder.inner_shape.radius)
+            } else {
+                None
+            }
+        } else {
+            None
+        }
+    }
+
+    pub fn get_collider_half_height(&self, handle: f64) -> Option<Real> {
+        let (index, generation) = decode_handle_from_js(handle);
+        let handle = ColliderHandle::from_raw_parts(index, generation);
+        if let Some(collider) = self.collider_set.get(handle) {
+            let shape = collider.shape();
+            if let Some(cylinder) = shape.as_cylinder() {
+                Some(cylinder.half_height)
+            } else if let Some(capsule) = shape.as_capsule() {
+                Some(capsule.half_height())
+            } else if let Some(cone) = shape.as_cone() {
+                Some(cone.half_height)
+            } else if let Some(round_cylinder) = shape.as_round_cylinder() {
+                Some(round_cylinder.inner_shape.half_height)
+            } else if let Some(cuboid) = shape.as_cuboid() {
+                Some(cuboid.half_extents.z)
+            } else {
+                None
+            }
+        } else {
+            None
+        }
+    }
+
+    pub fn get_collider_border_radius(&self, handle: f64) -> Option<Real> {
+        let (index, generation) = decode_handle_from_js(handle);
+        let handle = ColliderHandle::from_raw_parts(index, generation);
+        if let Some(collider) = self.collider_set.get(handle) {
+            if let Some(round_cylinder) = collider.shape().as_round_cylinder() {
+                Some(round_cylinder.border_radius)
+            } else {
+                None
+            }
+        } else {
+            None
+        }
+    }
+
+    pub fn get_collider_heightfield(&self, handle: f64) -> Option<Vec<Real>> {
+        let (index, generation) = decode_handle_from_js(handle);
+        let handle = ColliderHandle::from_raw_parts(index, generation);
+        if let Some(collider) = self.collider_set.get(handle) {
+            if let Some(heightfield) = collider.shape().as_heightfield() {
+                let scale = heightfield.scale();
+                let mut data = vec![
+                    heightfield.nrows() as Real,
+                    heightfield.ncols() as Real,
+                    scale.x,
+                    scale.y,
+                    scale.z,
+                ];
+                data.extend(heightfield.heights().iter().cloned());
+                Some(data)
+            } else {
+                None
+            }
+        } else {
+            None
+        }
+    }
+
+    pub fn get_collider_compound_shapes(&self, handle: f64) -> Option<Vec<Real>> {
+        let (index, generation) = decode_handle_from_js(handle);
+        let handle = ColliderHandle::from_raw_parts(index, generation);
+        if let Some(collider) = self.collider_set.get(handle) {
+            if let Some(compound) = collider.shape().as_compound() {
+                encode_compound_shapes(compound.shapes())
+            } else {
+                None
+            }
+        } else {
+            None
+        }
+    }
+
+    pub fn get_collider_flags(&self, handle: f64) -> Option<u32> {
+        let (index, generation) = decode_handle_from_js(handle);
+        let handle = ColliderHandle::from_raw_parts(index, generation);
+        if let Some(collider) = self.collider_set.get(handle) {
+            if let Some(trimesh) = collider.shape().as_trimesh() {
+                Some(trimesh.flags().bits() as u32)
+            } else {
+                None
+            }
+        } else {
+            None
+        }
+    }
+
+    fn query_pipeline<'a>(&'a self, filter: QueryFilter<'a>) -> QueryPipeline<'a> {
+        self.broad_phase.as_query_pipeline(
+            self.narrow_phase.query_dispatcher(),
+            &self.rigid_body_set,
+            &self.collider_set,
+            filter,
+        )
+    }
+
+    pub fn cast_ray(
+        &self,
+        origin: Point<Real>,
+        direction: Vector3<Real>,
+        max_toi: Real,
+        solid: bool,
+        groups: Option<InteractionGroups>,
+        exclude_body: Option<f64>,
+    ) -> Option<(f64, Real)> {
+        let ray = Ray::new(origin, direction);
+        let query_pipeline = self.query_pipeline(query_filter(groups, exclude_body));
+        if let Some((handle, toi)) = query_pipeline.cast_ray(&ray, max_toi, solid) {
+            let (index, generation) = handle.into_raw_parts();
+            Some((encode_handle_for_js(index, generation), toi))
+        } else {
+            None
+        }
+    }
+
+    pub fn cast_ray_and_get_normal(
+        &self,
+        origin: Point<Real>,
+        direction: Vector3<Real>,
+        max_toi: Real,
+        solid: bool,
+        groups: Option<InteractionGroups>,
+        exclude_body: Option<f64>,
+    ) -> Option<(f64, Real, Vector3<Real>)> {
+        let ray = Ray::new(origin, direction);
+        let query_pipeline = self.query_pipeline(query_filter(groups, exclude_body));
+        if let Some((handle, intersection)) =
+            query_pipeline.cast_ray_and_get_normal(&ray, max_toi, solid)
+        {
+            let (index, generation) = handle.into_raw_parts();
+            Some((
+                encode_handle_for_js(index, generation),
+                intersection.time_of_impact,
+                intersection.normal,
+            ))
+        } else {
+            None
+        }
+    }
+
+    pub fn cast_shape(
+        &self,
+        shape: &dyn Shape,
+        position: &Isometry<Real>,
+        velocity: &Vector3<Real>,
+        max_toi: Real,
+        stop_at_penetration: bool,
+        groups: Option<InteractionGroups>,
+        exclude_body: Option<f64>,
+    ) -> Option<(f64, ShapeCastHit)> {
+        let query_pipeline = self.query_pipeline(query_filter(groups, exclude_body));
+        let options = ShapeCastOptions {
+            max_time_of_impact: max_toi,
+            stop_at_penetration,
+            ..ShapeCastOptions::default()
+        };
+        if let Some((handle, hit)) = query_pipeline.cast_shape(position, velocity, shape, options) {
+            let (index, generation) = handle.into_raw_parts();
+            Some((encode_handle_for_js(index, generation), hit))
+        } else {
+            None
+        }
+    }
+
+    pub fn project_point(
+        &self,
+        point: &Point<Real>,
+        max_dist: Real,
+        solid: bool,
+        groups: Option<InteractionGroups>,
+    ) -> Option<(f64, PointProjection)> {
+        let query_pipeline = self.query_pipeline(query_filter(groups, None));
+        if let Some((handle, projection)) = query_pipeline.project_point(point, max_dist, solid) {
+            let (index, generation) = handle.into_raw_parts();
+            Some((encode_handle_for_js(index, generation), projection))
+        } else {
+            None
+        }
+    }
+
+    pub fn intersections_with_point(
+        &self,
+        point: &Point<Real>,
+        groups: Option<InteractionGroups>,
+    ) -> Vec<f64> {
+        let query_pipeline = self.query_pipeline(query_filter(groups, None));
+        query_pipeline
+            .intersect_point(*point)
+            .map(|(handle, _)| {
+                let (index, generation) = handle.into_raw_parts();
+                encode_handle_for_js(index, generation)
+            })
+            .collect()
+    }
+
+    pub fn intersections_with_shape(
+        &self,
+        shape: &dyn Shape,
+        position: &Isometry<Real>,
+        groups: Option<InteractionGroups>,
+    ) -> Vec<f64> {
+        let query_pipeline = self.query_pipeline(query_filter(groups, None));
+        query_pipeline
+            .intersect_shape(*position, shape)
+            .map(|(handle, _)| {
+                let (index, generation) = handle.into_raw_parts();
+                encode_handle_for_js(index, generation)
+            })
+            .collect()
+    }
+
+    pub fn find_free_spawn_pose(
+        &self,
+        min: &Point<Real>,
+        max: &Point<Real>,
+        radius: Real,
+        half_height: Real,
+        groups: Option<InteractionGroups>,
+    ) -> Option<Vector3<Real>> {
+        if radius <= 0.0 || half_height <= 0.0 {
+            return None;
+        }
+        let shape = SharedShape::cylinder(half_height, radius);
+        let query_pipeline = self.query_pipeline(query_filter(groups, None));
+        let xs = spawn_grid_coordinates(min.x, max.x, radius);
+        let ys = spawn_grid_coordinates(min.y, max.y, half_height);
+        let zs = spawn_grid_coordinates(min.z, max.z, radius);
+        let mut candidates = 0;
+        for y in &ys {
+            for x in &xs {
+                for z in &zs {
+                    candidates += 1;
+                    if candidates > MAX_SPAWN_CANDIDATES {
+                        return None;
+                    }
+                    let position = Isometry::translation(*x, *y, *z);
+                    if query_pipeline
+                        .intersect_shape(position, shape.as_ref())
+                        .next()
+                        .is_none()
+                        && !self.overlaps_pending_collider(&position, shape.as_ref(), groups)
+                    {
+                        return Some(Vector3::new(*x, *y, *z));
+                    }
+                }
+            }
+        }
+        None
+    }
+
+    // Colliders added since the last step are not in the broad phase yet, so
+    // the query pipeline cannot see them; test them one by one instead.
+    fn overlaps_pending_collider(
+        &self,
+        position: &Isometry<Real>,
+        shape: &dyn Shape,
+        groups: Option<InteractionGroups>,
+    ) -> bool {
+        let filter = query_filter(groups, None);
+        self.pending_colliders.iter().any(|handle| {
+            if let Some(collider) = self.collider_set.get(*handle) {
+                filter.test(&self.rigid_body_set, *handle, collider)
+                    && intersection_test(position, shape, collider.position(), collider.shape())
+                        .unwrap_or(false)
+            } else {
+                false
+            }
+        })
+    }
+
+    pub fn take_snapshot(&self, compression: SnapshotCompression) -> Vec<u8> {
+        let serializable_world = SerializableWorld {
+            gravity: self.gravity,
+            integration_parameters: self.integration_parameters.clone(),
+            islands: self.island_manager.clone(),
+            broad_phase: self.broad_phase.clone(),
+            narrow_phase: self.narrow_phase.clone(),
+            bodies: self.rigid_body_set.clone(),
+            colliders: self.collider_set.clone(),
+            impulse_joints: self.impulse_joint_set.clone(),
+            multibody_joints: self.multibody_joint_set.clone(),
+            joint_break_thresholds: self.sorted_joint_break_thresholds(),
+        };
+
+        serialize(&serializable_world)
+            .ok()
+            .and_then(|raw| wrap_snapshot(SNAPSHOT_MAGIC, raw, compression))
+            .unwrap_or_default()
+    }
+
+    // Takes a whole-world snapshot and keeps the state of its bodies, so that deltas can be taken
+    // against it until it is released. Its id is the crc32 in its header.
+    pub fn take_base_snapshot(&mut self, compression: SnapshotCompression) -> Vec<u8> {
+        let snapshot = self.take_snapshot(compression);
+        if let Ok(header) = SnapshotHeader::read(&snapshot) {
+            let bodies = self
+                .rigid_body_set
+                .iter()
+                .map(|(handle, body)| {
+                    let base_body = BaseBody {
+                        state: BodyState::of(body),
+                        colliders: body.colliders().to_vec(),
+                    };
+                    (handle, base_body)
+                })
+                .collect();
+            self.base_snapshots
+                .insert(header.crc32, BaseSnapshot { bodies });
+        }
+        snapshot
+    }
+
+    pub fn release_base_snapshot(&mut self, base_id: u32) -> bool {
+        self.base_snapshots.remove(&base_id).is_some()
+    }
+
+    // Encodes the pose, velocity and sleep state of the bodies where any of them changed since
+    // the base, the bodies added since or whose colliders changed in full, and the handles of the
+    // bodies removed since. Returns None for an unknown base.
+    pub fn take_delta_snapshot(
+        &self,
+        base_id: u32,
+        compression: SnapshotCompression,
+    ) -> Option<Vec<u8>> {
+        let base = self.base_snapshots.get(&base_id)?;
+        let mut changed = Vec::new();
+        let mut added = Vec::new();
+        for (handle, body) in self.rigid_body_set.iter() {
+            let state = BodyState::of(body);
+            match base.bodies.get(&handle) {
+                Some(base_body) if base_body.colliders == body.colliders() => {
+                    if base_body.state != state {
+                        changed.push((handle, state));
+                    }
+                }
+                _ => added.push(AddedBody {
+                    handle,
+                    body: body.clone(),
+                    colliders: body
+                        .colliders()
+                        .iter()
+                        .filter_map(|collider| {
+                            Some((*collider, self.collider_set.get(*collider)?.clone()))
+                        })
+                        .collect(),
+                }),
+            }
+        }
+        let mut removed: Vec<RigidBodyHandle> = base
+            .bodies
+            .keys()
+            .filter(|handle| !self.rigid_body_set.contains(**handle))
+            .copied()
+            .collect();
+        removed.sort_by_key(|handle| handle.into_raw_parts());
+
+        let delta = SerializableDelta {
+            base: base_id,
+            changed,
+            added,
+            removed,
+        };
+        wrap_snapshot(DELTA_SNAPSHOT_MAGIC, serialize(&delta).ok()?, compression)
+    }
+
+    // Captures the fixed bodies and the colliders that are parentless or attached to one, and
+    // records them as the scene later dynamic snapshots leave out. Scene objects must not change
+    // afterwards: a dynamic snapshot restores them as they were here.
+    pub fn take_scene_snapshot(&mut self, compression: SnapshotCompression) -> Vec<u8> {
+        let scene = SerializableScene {
+            bodies: self
+                .rigid_body_set
+                .iter()
+                .filter(|(_, body)| body.is_fixed())
+                .map(|(handle, body)| (handle, body.clone()))
+                .collect(),
+            colliders: self
+                .collider_set
+                .iter()
+                .filter(|(_, collider)| self.is_scene_collider(collider))
+                .map(|(handle, collider)| (handle, collider.clone()))
+                .collect(),
+        };
+        let Ok(raw) = serialize(&scene) else {
+            return Vec::new();
+        };
+        let id = crc32fast::hash(&raw);
+        let Some(snapshot) = wrap_snapshot(SCENE_SNAPSHOT_MAGIC, raw, compression) else {
+            return Vec::new();
+        };
+        let scene = Rc::new(LoadedScene::new(id, scene));
+        // Restores against this snapshot on the same thread can share the objects just captured.
+        if let Ok(header) = SnapshotHeader::read(&snapshot) {
+            SCENE_CACHE.set(Some((header.crc32, scene.clone())));
+        }
+        self.scene = Some(scene);
+        snapshot
+    }
+
+    fn is_scene_collider(&self, collider: &Collider) -> bool {
+        match collider.parent() {
+            Some(parent) => self
+                .rigid_body_set
+                .get(parent)
+                .is_some_and(|body| body.is_fixed()),
+            None => true,
+        }
+    }
+
+    // Like `take_snapshot`, but leaves out the objects of the scene last captured or restored,
+    // and the broad phase. Returns an empty snapshot when there is no scene.
+    pub fn take_dynamic_snapshot(&self, compression: SnapshotCompression) -> Vec<u8> {
+        self.scene
+            .as_ref()
+            .and_then(|scene| self.dynamic_world(scene))
+            .and_then(|dynamic_world| serialize(&dynamic_world).ok())
+            .and_then(|raw| wrap_snapshot(DYNAMIC_SNAPSHOT_MAGIC, raw, compression))
+            .unwrap_or_default()
+    }
+
+    fn dynamic_world(&self, scene: &LoadedScene) -> Option<SerializableDynamicWorld> {
+        let bodies: RigidBodySetLayout<RigidBody> = reencode(&self.rigid_body_set)?;
+        let mut colliders = self.collider_set.clone();
+        for handle in scene.colliders.keys() {
+            if let Some(collider) = colliders.get_mut(*handle) {
+                *collider = scene_placeholder();
+            }
+        }
+        let colliders: ColliderSetLayout<Collider> = reencode(&colliders)?;
+
+        Some(SerializableDynamicWorld {
+            scene: scene.id,
+            gravity: self.gravity,
+            integration_parameters: self.integration_parameters.clone(),
+            islands: self.island_manager.clone(),
+            narrow_phase: self.narrow_phase.clone(),
+            bodies: RigidBodySetLayout {
+                bodies: bodies.bodies.map(|index, generation, body| {
+                    let handle = RigidBodyHandle::from_raw_parts(index, generation);
+                    Some((!scene.bodies.contains_key(&handle)).then_some(body))
+                })?,
+                modified_bodies: bodies.modified_bodies,
+            },
+            colliders: ColliderSetLayout {
+                colliders: colliders.colliders.map(|index, generation, collider| {
+                    let handle = ColliderHandle::from_raw_parts(index, generation);
+                    Some((!scene.colliders.contains_key(&handle)).then_some(collider))
+                })?,
+                modified_colliders: colliders.modified_colliders,
+                removed_colliders: colliders.removed_colliders,
+            },
+            impulse_joints: self.impulse_joint_set.clone(),
+            multibody_joints: self.multibody_joint_set.clone(),
+            joint_break_thresholds: self.sorted_joint_break_thresholds(),
+        })
+    }
+
+    // Sorted so that equal worlds produce equal snapshots.
+    fn sorted_joint_break_thresholds(&self) -> Vec<(ImpulseJointHandle, JointBreakThreshold)> {
+        let mut thresholds: Vec<_> = self
+            .joint_break_thresholds
+            .iter()
+            .map(|(handle, threshold)| (*handle, *threshold))
+            .collect();
+        thresholds.sort_by_key(|(handle, _)| handle.into_raw_parts());
+        thresholds
+    }
+
+    // `reference` is the scene snapshot a dynamic snapshot was taken against, or the base snapshot
+    // a delta snapshot was taken against; it is ignored for a whole world.
+    pub fn restore_snapshot(
+        &mut self,
+        snapshot: &[u8],
+        reference: Option<&[u8]>,
+    ) -> Result<(), RestoreError> {
+        let header = SnapshotHeader::read(snapshot).map_err(RestoreError::snapshot)?;
+        match header.magic {
+            SNAPSHOT_MAGIC => self.restore_world(snapshot).map_err(RestoreError::snapshot),
+            DYNAMIC_SNAPSHOT_MAGIC => self.restore_dynamic_world(snapshot, reference),
+            DELTA_SNAPSHOT_MAGIC => match reference {
+                Some(base) => self.apply_delta_snapshot(snapshot, base),
+                None => Err(RestoreError::reference(SnapshotError::BaseRequired)),
+            },
+            magic => Err(RestoreError::snapshot(SnapshotError::UnexpectedKind(magic))),
+        }
+    }
+
+    fn restore_world(&mut self, snapshot: &[u8]) -> Result<(), SnapshotError> {
+        self.install_world(read_world_snapshot(snapshot)?);
+        Ok(())
+    }
+
+    fn install_world(&mut self, world_data: SerializableWorld) {
+        self.gravity = world_data.gravity;
+        self.integration_parameters = world_data.integration_parameters;
+        self.island_manager = world_data.islands;
+        self.broad_phase = world_data.broad_phase;
+        self.narrow_phase = world_data.narrow_phase;
+        self.rigid_body_set = world_data.bodies;
+        self.collider_set = world_data.colliders;
+        self.impulse_joint_set = world_data.impulse_joints;
+        self.multibody_joint_set = world_data.multibody_joints;
+        self.event_handler = EventQueue::default();
+        self.pending_colliders.clear();
+        self.joint_break_thresholds = world_data.joint_break_thresholds.into_iter().collect();
+        self.broken_joints.clear();
+        self.reported_active_bodies.clear();
+        self.scene = None;
+    }
+
+    // Restores `base` and applies `delta` on top of it. Every body comes out with the handle,
+    // pose, velocity and sleep state it had when the delta was taken, bit for bit, and so do the
+    // colliders of bodies carried in full. Contacts and islands come from the base and catch up
+    // on the next step; joints and parentless colliders added since the base are not carried.
+    pub fn apply_delta_snapshot(&mut self, delta: &[u8], base: &[u8]) -> Result<(), RestoreError> {
+        let magic = SnapshotHeader::read(delta)
+            .map_err(RestoreError::snapshot)?
+            .magic;
+        if magic != DELTA_SNAPSHOT_MAGIC {
+            return Err(RestoreError::snapshot(SnapshotError::UnexpectedKind(magic)));
+        }
+        let payload = unwrap_snapshot(delta).map_err(RestoreError::snapshot)?;
+        let delta = deserialize::<SerializableDelta>(&payload)
+            .map_err(|_| RestoreError::snapshot(SnapshotError::Payload))?;
+        let base_id = SnapshotHeader::read(base)
+            .map_err(RestoreError::reference)?
+            .crc32;
+        if base_id != delta.base {
+            return Err(RestoreError::reference(SnapshotError::BaseMismatch {
+                expected: delta.base,
+                found: base_id,
+            }));
+        }
+        let mut world_data = read_world_snapshot(base).map_err(RestoreError::reference)?;
+        apply_delta(&mut world_data, delta)
+            .ok_or(RestoreError::snapshot(SnapshotError::Payload))?;
+        self.install_world(world_data);
+        Ok(())
+    }
+
+    // Scene objects are shared clones of the cached scene; none of their shapes is rebuilt.
+    fn restore_dynamic_world(
+        &mut self,
+        snapshot: &[u8],
+        reference: Option<&[u8]>,
+    ) -> Result<(), RestoreError> {
+        let payload = unwrap_snapshot(snapshot).map_err(RestoreError::snapshot)?;
+        let world_data = deserialize::<SerializableDynamicWorld>(&payload)
+            .map_err(|_| RestoreError::snapshot(SnapshotError::Payload))?;
+        let reference = reference.ok_or(RestoreError::reference(SnapshotError::SceneRequired))?;
+        let scene = load_scene(reference).map_err(RestoreError::reference)?;
+        if scene.id != world_data.scene {
+            return Err(RestoreError::reference(SnapshotError::SceneMismatch {
+                expected: world_data.scene,
+                found: scene.id,
+            }));
+        }
+        let (bodies, colliders) =
+            rebuild_dynamic_sets(&scene, world_data.bodies, world_data.colliders)
+                .ok_or(RestoreError::snapshot(SnapshotError::Payload))?;
+
+        // Built from scratch so that queries see every collider before the next step. The pairs
+        // it reports are already in the restored narrow phase.
+        let mut broad_phase = DefaultBroadPhase::new();
+        let handles: Vec<ColliderHandle> = colliders.iter().map(|(handle, _)| handle).collect();
+        broad_phase.update(
+            &world_data.integration_parameters,
+            &colliders,
+            &bodies,
+            &handles,
+            &[],
+            &mut Vec::new(),
+        );
+
+        self.gravity = world_data.gravity;
+        self.integration_parameters = world_data.integration_parameters;
+        self.island_manager = world_data.islands;
+        self.broad_phase = broad_phase;
+        self.narrow_phase = world_data.narrow_phase;
+        self.rigid_body_set = bodies;
+        self.collider_set = colliders;
+        self.impulse_joint_set = world_data.impulse_joints;
+        self.multibody_joint_set = world_data.multibody_joints;
+        self.event_handler = EventQueue::default();
+        self.pending_colliders.clear();
+        self.joint_break_thresholds = world_data.joint_break_thresholds.into_iter().collect();
+        self.broken_joints.clear();
+        self.reported_active_bodies.clear();
+        self.scene = Some(scene);
+        Ok(())
+    }
+
+    // Bodies, colliders and joints are numbered from 0 in handle order, so an unchanged world
+    // always exports the same JSON.
+    pub fn export_world_json(&self) -> Result<String, String> {
+        let body_ids: HashMap<RigidBodyHandle, u64> = self
+            .rigid_body_set
+            .iter()
+            .enumerate()
+            .map(|(id, (handle, _))| (handle, id as u64))
+            .collect();
+        let collider_ids: HashMap<ColliderHandle, u64> = self
+            .collider_set
+            .iter()
+            .enumerate()
+            .map(|(id, (handle, _))| (handle, id as u64))
+            .collect();
+        let bodies = self
+            .rigid_body_set
+            .iter()
+            .map(|(handle, body)| body_to_json(body_ids[&handle], body))
+            .collect();
+        let colliders = self
+            .collider_set
+            .iter()
+            .map(|(handle, collider)| {
+                let body = collider.parent().map(|parent| body_ids[&parent]);
+                Ok(ColliderJson {
+                    one_way: self.physics_hooks.one_way_surfaces.get(&handle).map(
+                        |(normal, allowed_angle)| OneWayJson {
+                            normal: (*normal).into(),
+                            allowed_angle: *allowed_angle,
+                        },
+                    ),
+                    surface_velocity: self
+                        .physics_hooks
+                        .surface_velocities
+                        .get(&handle)
+                        .map(SurfaceVelocityJson::of),
+                    ..collider_to_json(collider_ids[&handle], body, collider)?
+                })
+            })
+            .collect::<Result<Vec<_>, String>>()?;
+        let impulse_joints = self
+            .impulse_joint_set
+            .iter()
+            .enumerate()
+            .map(|(id, (handle, joint))| JointJson {
+                break_threshold: self.joint_break_thresholds.get(&handle).copied(),
+                ..joint_to_json(
+                    id as u64,
+                    body_ids[&joint.body1],
+                    body_ids[&joint.body2],
+                    &joint.data,
+                )
+            })
+            .collect();
+        let multibody_joints = self
+            .multibody_joint_set
+            .iter()
+            .enumerate()
+            .filter_map(|(id, (_, _, multibody, link))| {
+                let parent = multibody.link(link.parent_id()?)?;
+                Some(joint_to_json(
+                    id as u64,
+                    body_ids[&parent.rigid_body_handle()],
+                    body_ids[&link.rigid_body_handle()],
+                    &link.joint.data,
+                ))
+            })
+            .collect();
+        // Pairs can be ignored before their colliders exist; only the live ones are exported.
+        let mut ignored_pairs: Vec<[u64; 2]> = self
+            .physics_hooks
+            .ignored_pairs
+            .iter()
+            .filter_map(|(collider1, collider2)| {
+                let mut pair = [*collider_ids.get(collider1)?, *collider_ids.get(collider2)?];
+                pair.sort_unstable();
+                Some(pair)
+            })
+            .collect();
+        ignored_pairs.sort_unstable();
+        let world = WorldJson {
+            version: WORLD_JSON_VERSION,
+            gravity: self.gravity.into(),
+            integration_parameters: IntegrationParametersJson::of(&self.integration_parameters),
+            bodies,
+            colliders,
+            impulse_joints,
+            multibody_joints,
+            ignored_pairs,
+        };
+        serde_json::to_string_pretty(&world).map_err(|error| error.to_string())
+    }
+
+    // Replaces the bodies, colliders, joints, contact hooks, gravity and the integration
+    // parameters the schema lists; the world is left untouched when the JSON is rejected.
+    // Handles are reassigned, so contacts, islands, queued events, the cached scene and the delta
+    // bases start over, while the pipeline and the CCD solver keep their buffers.
+    pub fn import_world_json(&mut self, json: &str) -> Result<(), String> {
+        let data: WorldJson = serde_json::from_str(json).map_err(|error| error.to_string())?;
+        if data.version != WORLD_JSON_VERSION {
+            return Err(format!("unsupported world JSON version {}", data.version));
+        }
+        let mut rigid_body_set = RigidBodySet::new();
+        let mut collider_set = ColliderSet::new();
+        let mut impulse_joint_set = ImpulseJointSet::new();
+        let mut multibody_joint_set = MultibodyJointSet::new();
+        let mut physics_hooks = ContactHooks::default();
+        let mut joint_break_thresholds = HashMap::new();
+
+        let mut bodies = HashMap::new();
+        for body in &data.bodies {
+            let built =
+                body_from_json(body).map_err(|error| format!("body {}: {error}", body.id))?;
+            let handle = rigid_body_set.insert(built);
+            if bodies.insert(body.id, handle).is_some() {
+                return Err(format!("duplicate body id {}", body.id));
+            }
+        }
+        let body_handle = |id: u64| {
+            bodies
+                .get(&id)
+                .copied()
+                .ok_or_else(|| format!("unknown body id {id}"))
+        };
+        let mut colliders = HashMap::new();
+        for collider in &data.colliders {
+            let built = collider_from_json(collider)
+                .map_err(|error| format!("collider {}: {error}", collider.id))?;
+            let handle = if let Some(body) = collider.body {
+                collider_set.insert_with_parent(built, body_handle(body)?, &mut rigid_body_set)
+            } else {
+                collider_set.insert(built)
+            };
+            if colliders.insert(collider.id, handle).is_some() {
+                return Err(format!("duplicate collider id {}", collider.id));
+            }
+            if let Some(one_way) = &collider.one_way {
+                let normal = Vector3::from(one_way.normal).normalize();
+                physics_hooks
+                    .one_way_surfaces
+                    .insert(handle, (normal, one_way.allowed_angle));
+            }
+            if let Some(surface_velocity) = &collider.surface_velocity {
+                physics_hooks
+                    .surface_velocities
+                    .insert(handle, surface_velocity.surface_velocity());
+            }
+        }
+        let collider_handle = |id: u64| {
+            colliders
+                .get(&id)
+                .copied()
+                .ok_or_else(|| format!("unknown collider id {id}"))
+        };
+        for &[collider1, collider2] in &data.ignored_pairs {
+            physics_hooks.ignored_pairs.insert(ordered_collider_pair(
+                collider_handle(collider1)?,
+                collider_handle(collider2)?,
+            ));
+        }
+        for joint in &data.impulse_joints {
+            let handle = impulse_joint_set.insert(
+                body_handle(joint.body1)?,
+                body_handle(joint.body2)?,
+                joint_from_json(joint),
+                true,
+            );
+            if let Some(threshold) = joint.break_threshold {
+                joint_break_thresholds.insert(handle, threshold);
+            }
+        }
+        for joint in &data.multibody_joints {
+            if joint.break_threshold.is_some() {
+                return Err(format!(
+                    "multibody joint {} cannot have a break threshold",
+                    joint.id
+                ));
+            }
+            multibody_joint_set
+                .insert(
+                    body_handle(joint.body1)?,
+                    body_handle(joint.body2)?,
+                    joint_from_json(joint),
+                    true,
+                )
+                .ok_or_else(|| format!("multibody joint {} would close a loop", joint.id))?;
+        }
+
+        self.gravity = data.gravity.into();
+        data.integration_parameters
+            .apply(&mut self.integration_parameters);
+        self.island_manager = IslandManager::new();
+        self.broad_phase = DefaultBroadPhase::new();
+        self.narrow_phase = NarrowPhase::new();
+        // None of the colliders is in the fresh broad phase until the next step.
+        self.pending_colliders = collider_set.iter().map(|(handle, _)| handle).collect();
+        self.rigid_body_set = rigid_body_set;
+        self.collider_set = collider_set;
+        self.impulse_joint_set = impulse_joint_set;
+        self.multibody_joint_set = multibody_joint_set;
+        self.physics_hooks = physics_hooks;
+        self.event_handler = EventQueue::default();
+        self.joint_break_thresholds = joint_break_thresholds;
+        self.broken_joints.clear();
+        self.reported_active_bodies.clear();
+        self.scene = None;
+        self.base_snapshots.clear();
+        Ok(())
+    }
+}
+
+const COLLISION_EVENT_STARTED: u32 = 1;
+const COLLISION_EVENT_SENSOR: u32 = 2;
+const COLLISION_EVENT_REMOVED: u32 = 4;
+
+const JOINT_TYPE_REVOLUTE: f64 = 0.0;
+const JOINT_TYPE_FIXED: f64 = 1.0;
+const JOINT_TYPE_PRISMATIC: f64 = 2.0;
+const JOINT_TYPE_SPHERICAL: f64 = 3.0;
+const JOINT_TYPE_ROPE: f64 = 4.0;
+const JOINT_TYPE_SPRING: f64 = 5.0;
+
+// Worlds live on the JS thread that created them. Exports borrow one for the duration of the
+// call, so no reference into the registry outlives it.
+thread_local! {
+    static WORLDS: RefCell<BTreeMap<u32, World>> = const { RefCell::new(BTreeMap::new()) };
+    static NEXT_WORLD_ID: Cell<u32> = const { Cell::new(1) };
+}
+
+fn with_world<R>(world_id: f64, f: impl FnOnce(&World) -> R) -> Option<R> {
+    WORLDS.with_borrow(|worlds| worlds.get(&(world_id as u32)).map(f))
+}
+
+fn with_world_mut<R>(world_id: f64, f: impl FnOnce(&mut World) -> R) -> Option<R> {
+    WORLDS.with_borrow_mut(|worlds| worlds.get_mut(&(world_id as u32)).map(f))
+}
+
+#[neon::export]
+fn init_world(gravity_x: f64, gravity_y: f64, gravity_z: f64) -> f64 {
+    let world_id = NEXT_WORLD_ID.get();
+    NEXT_WORLD_ID.set(world_id + 1);
+    let world = World::new(gravity_x, gravity_y, gravity_z);
+    WORLDS.with_borrow_mut(|worlds| worlds.insert(world_id, world));
+    world_id as f64
+}
+
+#[neon::export]
+fn destroy_world(world_id: f64) -> bool {
+    WORLDS.with_borrow_mut(|worlds| worlds.remove(&(world_id as u32)).is_some())
+}
+
+#[neon::export]
+fn set_world_gravity(world_id: f64, x: f64, y: f64, z: f64) -> bool {
+    with_world_mut(world_id, |world| {
+        world.set_gravity(x, y, z);
+        true
+    })
+    .unwrap_or(false)
+}
+
+#[neon::export]
+fn get_world_gravity(world_id: f64) -> Vec<f64> {
+    with_world(world_id, |world| {
+        vec![world.gravity.x, world.gravity.y, world.gravity.z]
+    })
+    .unwrap_or_else(|| vec![0.0, 0.0, 0.0])
+}
+
+#[neon::export]
+fn set_integration_parameters_num_solver_iterations(
+    world_id: f64,
+    num_solver_iterations: f64,
+) -> bool {
+    with_world_mut(world_id, |world| {
+        world.integration_parameters.num_solver_iterations =
+            NonZeroUsize::new(num_solver_iterations as usize).unwrap();
+        true
+    })
+    .unwrap_or(false)
+}
+
+#[neon::export]
+fn set_integration_parameters_num_additional_friction_iterations(
+    world_id: f64,
+    num_additional_friction_iterations: f64,
+) -> bool {
+    with_world_mut(world_id, |world| {
+        world
+            .integration_parameters
+            .num_additional_friction_iterations = num_additional_friction_iterations as usize;
+        true
+    })
+    .unwrap_or(false)
+}
+
+#[neon::export]
+fn set_integration_parameters_num_internal_pgs_iterations(
+    world_id: f64,
+    num_internal_pgs_iterations: f64,
+) -> bool {
+    with_world_mut(world_id, |world| {
+        world.integration_parameters.num_internal_pgs_iterations =
+            num_internal_pgs_iterations as usize;
+        true
+    })
+    .unwrap_or(false)
+}
+
+#[neon::export]
+fn set_integration_parameters_min_island_size(world_id: f64, min_island_size: f64) -> bool {
+    with_world_mut(world_id, |world| {
+        world.integration_parameters.min_island_size = min_island_size as usize;
+        true
+    })
+    .unwrap_or(false)
+}
+
+#[neon::export]
+fn set_integration_parameters_dt(world_id: f64, dt: f64) -> bool {
+    with_world_mut(world_id, |world| {
+        world.integration_parameters.dt = dt;
+        true
+    })
+    .unwrap_or(false)
+}
+
+#[neon::export]
+fn integration_parameters_switch_to_standard_pgs_solver() -> bool {
+    IntegrationParameters::pgs_legacy();
+    true
+}
+
+#[neon::export]
+fn integration_parameters_switch_to_small_steps_pgs_solver() -> bool {
+    IntegrationParameters::tgs_soft();
+    true
+}
+
+#[neon::export]
+fn integration_parameters_switch_to_small_steps_pgs_solver_without_warm_start() -> bool {
+    IntegrationParameters::tgs_soft_without_warmstart();
+    true
+}
+
+#[neon::export]
+fn get_world_bodies(world_id: f64) -> Vec<f64> {
+    with_world(world_id, |world| {
+        world
+            .rigid_body_set
+            .iter()
+            .map(|(handle, _)| {
+                let (index, generation) = handle.into_raw_parts();
+                encode_handle_for_js(index, generation)
+            })
+            .collect()
+    })
+    .unwrap_or_default()
+}
+
+#[neon::export]
+fn get_world_colliders(world_id: f64) -> Vec<f64> {
+    with_world(world_id, |world| {
+        world
+            .collider_set
+            .iter()
+            .map(|(handle, _)| {
+                let (index, generation) = handle.into_raw_parts();
+                encode_handle_for_js(index, generation)
+            })
+            .collect()
+    })
+    .unwrap_or_default()
+}
+
+#[neon::export]
+fn get_world_impulse_joints(world_id: f64) -> Vec<f64> {
+    with_world(world_id, |world| {
+        world
+            .impulse_joint_set
+            .iter()
+            .map(|(handle, _)| {
+                let (index, generation) = handle.into_raw_parts();
+                encode_handle_for_js(index, generation)
+            })
+            .collect()
+    })
+    .unwrap_or_default()
+}
+
+#[neon::export]
+fn get_world_multibody_joints(world_id: f64) -> Vec<f64> {
+    with_world(world_id, |world| {
+        world
+            .multibody_joint_set
+            .iter()
+            .map(|(handle, _, _, _)| {
+                let (index, generation) = handle.into_raw_parts();
+                encode_handle_for_js(index, generation)
+            })
+            .collect()
+    })
+    .unwrap_or_default()
+}
+
+#[neon::export]
+fn set_timestep(world_id: f64, dt: f64) -> bool {
+    with_world_mut(world_id, |world| {
+        world.integration_parameters.dt = dt;
+        true
+    })
+    .unwrap_or(false)
+}
+
+fn snapshot_compression(code: Option<f64>) -> Option<SnapshotCompression> {
+    match code {
+        None => Some(SnapshotCompression::None),
+        Some(code) if code.fract() == 0.0 && (0.0..=u8::MAX as f64).contains(&code) => {
+            SnapshotCompression::from_code(code as u8)
+        }
+        Some(_) => None,
+    }
+}
+
+// Returns an empty snapshot for an unknown compression.
+#[neon::export]
+fn take_snapshot(world_id: f64, compression: Option<f64>) -> Vec<u8> {
+    let compression = snapshot_compression(compression);
+    compression
+        .and_then(|compression| with_world(world_id, |world| world.take_snapshot(compression)))
+        .unwrap_or_default()
+}
+
+#[neon::export]
+fn take_scene_snapshot(world_id: f64, compression: Option<f64>) -> Vec<u8> {
+    let compression = snapshot_compression(compression);
+    compression
+        .and_then(|compression| {
+            with_world_mut(world_id, |world| world.take_scene_snapshot(compression))
+        })
+        .unwrap_or_default()
+}
+
+// Returns an empty snapshot for an unknown compression or a world without a scene.
+#[neon::export]
+fn take_dynamic_snapshot(world_id: f64, compression: Option<f64>) -> Vec<u8> {
+    let compression = snapshot_compression(compression);
+    compression
+        .and_then(|compression| {
+            with_world(world_id, |world| world.take_dynamic_snapshot(compression))
+        })
+        .unwrap_or_default()
+}
+
+#[neon::export]
+fn take_base_snapshot(world_id: f64, compression: Option<f64>) -> Vec<u8> {
+    let compression = snapshot_compression(compression);
+    compression
+        .and_then(|compression| {
+            with_world_mut(world_id, |world| world.take_base_snapshot(compression))
+        })
+        .unwrap_or_default()
+}
+
+#[neon::export]
+fn release_base_snapshot(world_id: f64, base_id: f64) -> bool {
+    with_world_mut(world_id, |world| {
+        world.release_base_snapshot(base_id as u32)
+    })
+    .unwrap_or(false)
+}
+
+// Returns an empty snapshot for an unknown compression or base.
+#[neon::export]
+fn take_delta_snapshot(world_id: f64, base_id: f64, compression: Option<f64>) -> Vec<u8> {
+    let compression = snapshot_compression(compression);
+    compression
+        .and_then(|compression| {
+            with_world(world_id, |world| {
+                world.take_delta_snapshot(base_id as u32, compression)
+            })
+        })
+        .flatten()
+        .unwrap_or_default()
+}
+
+// Returns null once the world holds the snapshot, or `{ code, message, reference }` saying why
+// it was rejected and whether the fault lies with the reference snapshot.
+#[neon::export]
+fn restore_snapshot<'cx>(
+    cx: &mut Cx<'cx>,
+    world_id: f64,
+    snapshot: Vec<u8>,
+    reference: Option<Vec<u8>>,
+) -> JsResult<'cx, JsValue> {
+    let result = with_world_mut(world_id, |world| {
+        world.restore_snapshot(&snapshot, reference.as_deref())
+    });
+    let (code, message, reference) = match result {
+        Some(Ok(())) => return Ok(cx.null().upcast()),
+        Some(Err(RestoreError { reference, error })) => {
+            (error.code(), error.to_string(), reference)
+        }
+        None => (-1.0, "world does not exist".to_string(), false),
+    };
+    let error = cx.empty_object();
+    let code = cx.number(code);
+    error.set(cx, "code", code)?;
+    let message = cx.string(message);
+    error.set(cx, "message", message)?;
+    let reference = cx.boolean(reference);
+    error.set(cx, "reference", reference)?;
+    Ok(error.upcast())
+}
+
+#[neon::export]
+fn read_snapshot_header(snapshot: Vec<u8>) -> Vec<f64> {
+    match SnapshotHeader::read(&snapshot) {
+        Ok(header) => header.to_vec(),
+        Err(_) => vec![],
+    }
+}
+
+// Returns null if the world does not exist or holds a shape the schema cannot describe.
+#[neon::export]
+fn export_world_json(world_id: f64) -> Option<String> {
+    with_world(world_id, |world| world.export_world_json().ok()).flatten()
+}
+
+// Returns null once the world holds the imported bodies, or why the JSON was rejected.
+#[neon::export]
+fn import_world_json(world_id: f64, json: String) -> Option<String> {
+    with_world_mut(world_id, |world| world.import_world_json(&json).err())
+        .unwrap_or_else(|| Some("world does not exist".to_string()))
+}
+
+#[neon::export]
+fn create_dynamic_body(world_id: f64) -> Option<f64> {
+    with_world_mut(world_id, |world| world.create_dynamic_body())
+}
+
+#[neon::export]
+fn create_kinematic_body(world_id: f64) -> Option<f64> {
+    with_world_mut(world_id, |world| world.create_kinematic_body())
+}
+
+#[neon::export]
+fn create_fixed_body(world_id: f64) -> Option<f64> {
+    with_world_mut(world_id, |world| world.create_fixed_body())
+}
+
+#[neon::export]
+fn create_body(world_id: f64, desc: Vec<f64>) -> Option<f64> {
+    with_world_mut(world_id, |world| world.create_body(&desc)).flatten()
+}
+
+#[neon::export]
+fn remove_rigid_body(world_id: f64, handle: f64, remove_attached_colliders: bool) -> bool {
+    with_world_mut(world_id, |world| {
+        world.remove_rigid_body(handle, remove_attached_colliders)
+    })
+    .unwrap_or(false)
+}
+
+#[neon::export]
+fn remove_collider(world_id: f64, handle: f64, wake_up: bool) -> bool {
+    with_world_mut(world_id, |world| world.remove_collider(handle, wake_up)).unwrap_or(false)
+}
+
+#[neon::export]
+fn remove_impulse_joint(world_id: f64, handle: f64, wake_up: bool) -> bool {
+    with_world_mut(world_id, |world| {
+        world.remove_impulse_joint(handle, wake_up)
+    })
+    .unwrap_or(false)
+}
+
+#[neon::export]
+fn add_box_collider(
+    world_id: f64,
+    handle: f64,
+    half_x: f64,
+    half_y: f64,
+    half_z: f64,
+    is_sensor: bool,
+    pos_x: Option<f64>,
+    pos_y: Option<f64>,
+    pos_z: Option<f64>,
+    rot_x: Option<f64>,
+    rot_y: Option<f64>,
+    rot_z: Option<f64>,
+) -> Option<f64> {
+    with_world_mut(world_id, |world| {
+        world.add_box_collider(
+            handle, half_x, half_y, half_z, is_sensor, pos_x, pos_y, pos_z, rot_x, rot_y, rot_z,
+        )
+    })
+    .flatten()
+}
+
+#[neon::export]
+fn add_cylinder_collider(
+    world_id: f64,
+    handle: f64,
+    half_height: f64,
+    radius: f64,
+    is_sensor: bool,
+    pos_x: Option<f64>,
+    pos_y: Option<f64>,
+    pos_z: Option<f64>,
+    rot_x: Option<f64>,
+    rot_y: Option<f64>,
+    rot_z: Option<f64>,
+) -> Option<f64> {
+    with_world_mut(world_id, |world| {
+        world.add_cylinder_collider(
+            handle,
+            half_height,
+            radius,
+            is_sensor,
+            pos_x,
+            pos_y,
+            pos_z,
+            rot_x,
+            rot_y,
+            rot_z,
+        )
+    })
+    .flatten()
+}
+
+#[neon::export]
+fn add_trimesh_collider(
+    world_id: f64,
+    handle: f64,
+    vertices: Vec<f64>,
+    indices: Vec<f64>,
+    is_sensor: bool,
+    trimesh_flags: f64,
+    pos_x: Option<f64>,
+    pos_y: Option<f64>,
+    pos_z: Option<f64>,
+    rot_x: Option<f64>,
+    rot_y: Option<f64>,
+    rot_z: Option<f64>,
+) -> Option<f64> {
+    with_world_mut(world_id, |world| {
+        world.add_trimesh_collider(
+            handle,
+            vertices.into_iter().collect(),
+            indices.into_iter().map(|v| v as u32).collect(),
+            is_sensor,
+            trimesh_flags as u32,
+            pos_x,
+            pos_y,
+            pos_z,
+            rot_x,
+            rot_y,
+            rot_z,
+        )
+    })
+    .flatten()
+}
+
+#[neon::export]
+fn add_convex_hull_collider(
+    world_id: f64,
+    handle: f64,
+    vertices: Vec<f64>,
+    is_sensor: bool,
+    pos_x: Option<f64>,
+    pos_y: Option<f64>,
+    pos_z: Option<f64>,
+    rot_x: Option<f64>,
+    rot_y: Option<f64>,
+    rot_z: Option<f64>,
+) -> Option<f64> {
+    with_world_mut(world_id, |world| {
+        world.add_convex_hull_collider(
+            handle,
+            vertices.into_iter().collect(),
+            is_sensor,
+            pos_x,
+            pos_y,
+            pos_z,
+            rot_x,
+            rot_y,
+            rot_z,
+        )
+    })
+    .flatten()
+}
+
+#[neon::export]
+fn add_ball_collider(
+    world_id: f64,
+    handle: f64,
+    radius: f64,
+    is_sensor: bool,
+    pos_x: Option<f64>,
+    pos_y: Option<f64>,
+    pos_z: Option<f64>,
+    rot_x: Option<f64>,
+    rot_y: Option<f64>,
+    rot_z: Option<f64>,
+) -> Option<f64> {
+    with_world_mut(world_id, |world| {
+        world.add_ball_collider(
+            handle, radius, is_sensor, pos_x, pos_y, pos_z, rot_x, rot_y, rot_z,
+        )
+    })
+    .flatten()
+}
+
+#[neon::export]
+fn add_capsule_collider(
+    world_id: f64,
+    handle: f64,
+    half_height: f64,
+    radius: f64,
+    is_sensor: bool,
+    pos_x: Option<f64>,
+    pos_y: Option<f64>,
+    pos_z: Option<f64>,
+    rot_x: Option<f64>,
+    rot_y: Option<f64>,
+    rot_z: Option<f64>,
+) -> Option<f64> {
+    with_world_mut(world_id, |world| {
+        world.add_capsule_collider(
+            handle,
+            half_height,
+            radius,
+            is_sensor,
+            pos_x,
+            pos_y,
+            pos_z,
+            rot_x,
+            rot_y,
+            rot_z,
+        )
+    })
+    .flatten()
+}
+
+#[neon::export]
+fn add_cone_collider(
+    world_id: f64,
+    handle: f64,
+    half_height: f64,
+    radius: f64,
+    is_sensor: bool,
+    pos_x: Option<f64>,
+    pos_y: Option<f64>,
+    pos_z: Option<f64>,
+    rot_x: Option<f64>,
+    rot_y: Option<f64>,
+    rot_z: Option<f64>,
+) -> Option<f64> {
+    with_world_mut(world_id, |world| {
+        world.add_cone_collider(
+            handle,
+            half_height,
+            radius,
+            is_sensor,
+            pos_x,
+            pos_y,
+            pos_z,
+            rot_x,
+            rot_y,
+            rot_z,
+        )
+    })
+    .flatten()
+}
+
+#[neon::export]
+fn add_round_cylinder_collider(
+    world_id: f64,
+    handle: f64,
+    half_height: f64,
+    radius: f64,
+    border_radius: f64,
+    is_sensor: bool,
+    pos_x: Option<f64>,
+    pos_y: Option<f64>,
+    pos_z: Option<f64>,
+    rot_x: Option<f64>,
+    rot_y: Option<f64>,
+    rot_z: Option<f64>,
+) -> Option<f64> {
+    with_world_mut(world_id, |world| {
+        world.add_round_cylinder_collider(
+            handle,
+            half_height,
+            radius,
+            border_radius,
+            is_sensor,
+            pos_x,
+            pos_y,
+            pos_z,
+            rot_x,
+            rot_y,
+            rot_z,
+        )
+    })
+    .flatten()
+}
+
+#[neon::export]
+fn add_heightfield_collider(
+    world_id: f64,
+    handle: f64,
+    nrows: f64,
+    ncols: f64,
+    heights: Vec<f64>,
+    scale_x: f64,
+    scale_y: f64,
+    scale_z: f64,
+    is_sensor: bool,
+    pos_x: Option<f64>,
+    pos_y: Option<f64>,
+    pos_z: Option<f64>,
+    rot_x: Option<f64>,
+    rot_y: Option<f64>,
+    rot_z: Option<f64>,
+) -> Option<f64> {
+    with_world_mut(world_id, |world| {
+        world.add_heightfield_collider(
+            handle,
+            nrows as usize,
+            ncols as usize,
+            heights,
+            scale_x,
+            scale_y,
+            scale_z,
+            is_sensor,
+            pos_x,
+            pos_y,
+            pos_z,
+            rot_x,
+            rot_y,
+            rot_z,
+        )
+    })
+    .flatten()
+}
+
+#[neon::export]
+fn add_compound_collider(
+    world_id: f64,
+    handle: f64,
+    shapes: Vec<f64>,
+    is_sensor: bool,
+    pos_x: Option<f64>,
+    pos_y: Option<f64>,
+    pos_z: Option<f64>,
+    rot_x: Option<f64>,
+    rot_y: Option<f64>,
+    rot_z: Option<f64>,
+) -> Option<f64> {
+    with_world_mut(world_id, |world| {
+        world.add_compound_collider(
+            handle, shapes, is_sensor, pos_x, pos_y, pos_z, rot_x, rot_y, rot_z,
+        )
+    })
+    .flatten()
+}
+
+#[neon::export]
+fn create_revolute_joint(
+    world_id: f64,
+    body1_handle: f64,
+    body2_handle: f64,
+    anchor1_x: f64,
+    anchor1_y: f64,
+    anchor1_z: f64,
+    anchor2_x: f64,
+    anchor2_y: f64,
+    anchor2_z: f64,
+    axis_x: f64,
+    axis_y: f64,
+    axis_z: f64,
+    wake_up: bool,
+) -> Option<f64> {
+    with_world_mut(world_id, |world| {
+        world.create_revolute_joint(
+            body1_handle,
+            body2_handle,
+            anchor1_x,
+            anchor1_y,
+            anchor1_z,
+            anchor2_x,
+            anchor2_y,
+            anchor2_z,
+            axis_x,
+            axis_y,
+            axis_z,
+            wake_up,
+        )
+    })
+    .flatten()
+}
+
+#[neon::export]
+fn create_fixed_joint(
+    world_id: f64,
+    body1_handle: f64,
+    body2_handle: f64,
+    anchor1_x: f64,
+    anchor1_y: f64,
+    anchor1_z: f64,
+    anchor2_x: f64,
+    anchor2_y: f64,
+    anchor2_z: f64,
+    frame1_x: f64,
+    frame1_y: f64,
+    frame1_z: f64,
+    frame1_w: f64,
+    frame2_x: f64,
+    frame2_y: f64,
+    frame2_z: f64,
+    frame2_w: f64,
+    wake_up: bool,
+) -> Option<f64> {
+    with_world_mut(world_id, |world| {
+        world.create_fixed_joint(
+            body1_handle,
+            body2_handle,
+            anchor1_x,
+            anchor1_y,
+            anchor1_z,
+            anchor2_x,
+            anchor2_y,
+            anchor2_z,
+            frame1_x,
+            frame1_y,
+            frame1_z,
+            frame1_w,
+            frame2_x,
+            frame2_y,
+            frame2_z,
+            frame2_w,
+            wake_up,
+        )
+    })
+    .flatten()
+}
+
+#[neon::export]
+fn set_revolute_joint_limits(world_id: f64, handle: f64, min_angle: f64, max_angle: f64) -> bool {
+    with_world_mut(world_id, |world| {
+        world.set_revolute_joint_limits(handle, min_angle, max_angle)
+    })
+    .unwrap_or(false)
+}
+
+#[neon::export]
+fn configure_revolute_joint_motor(
+    world_id: f64,
+    handle: f64,
+    target_pos: f64,
+    target_vel: f64,
+    stiffness: f64,
+    damping: f64,
+) -> bool {
+    with_world_mut(world_id, |world| {
+        world.configure_revolute_joint_motor(handle, target_pos, target_vel, stiffness, damping)
+    })
+    .unwrap_or(false)
+}
+
+#[neon::export]
+fn create_prismatic_joint(
+    world_id: f64,
+    body1_handle: f64,
+    body2_handle: f64,
+    anchor1_x: f64,
+    anchor1_y: f64,
+    anchor1_z: f64,
+    anchor2_x: f64,
+    anchor2_y: f64,
+    anchor2_z: f64,
+    axis_x: f64,
+    axis_y: f64,
+    axis_z: f64,
+    wake_up: bool,
+) -> Option<f64> {
+    with_world_mut(world_id, |world| {
+        world.create_prismatic_joint(
+            body1_handle,
+            body2_handle,
+            anchor1_x,
+            anchor1_y,
+            anchor1_z,
+            anchor2_x,
+            anchor2_y,
+            anchor2_z,
+            axis_x,
+            axis_y,
+            axis_z,
+            wake_up,
+        )
+    })
+    .flatten()
+}
+
+#[neon::export]
+fn create_spherical_joint(
+    world_id: f64,
+    body1_handle: f64,
+    body2_handle: f64,
+    anchor1_x: f64,
+    anchor1_y: f64,
+    anchor1_z: f64,
+    anchor2_x: f64,
+    anchor2_y: f64,
+    anchor2_z: f64,
+    wake_up: bool,
+) -> Option<f64> {
+    with_world_mut(world_id, |world| {
+        world.create_spherical_joint(
+            body1_handle,
+            body2_handle,
+            anchor1_x,
+            anchor1_y,
+            anchor1_z,
+            anchor2_x,
+            anchor2_y,
+            anchor2_z,
+            wake_up,
+        )
+    })
+    .flatten()
+}
+
+#[neon::export]
+fn create_rope_joint(
+    world_id: f64,
+    body1_handle: f64,
+    body2_handle: f64,
+    anchor1_x: f64,
+    anchor1_y: f64,
+    anchor1_z: f64,
+    anchor2_x: f64,
+    anchor2_y: f64,
+    anchor2_z: f64,
+    max_distance: f64,
+    wake_up: bool,
+) -> Option<f64> {
+    with_world_mut(world_id, |world| {
+        world.create_rope_joint(
+            body1_handle,
+            body2_handle,
+            anchor1_x,
+            anchor1_y,
+            anchor1_z,
+            anchor2_x,
+            anchor2_y,
+            anchor2_z,
+            max_distance,
+            wake_up,
+        )
+    })
+    .flatten()
+}
+
+#[neon::export]
+fn create_spring_joint(
+    world_id: f64,
+    body1_handle: f64,
+    body2_handle: f64,
+    anchor1_x: f64,
+    anchor1_y: f64,
+    anchor1_z: f64,
+    anchor2_x: f64,
+    anchor2_y: f64,
+    anchor2_z: f64,
+    rest_length: f64,
+    stiffness: f64,
+    damping: f64,
+    wake_up: bool,
+) -> Option<f64> {
+    with_world_mut(world_id, |world| {
+        world.create_spring_joint(
+            body1_handle,
+            body2_handle,
+            anchor1_x,
+            anchor1_y,
+            anchor1_z,
+            anchor2_x,
+            anchor2_y,
+            anchor2_z,
+            rest_length,
+            stiffness,
+            damping,
+            wake_up,
+        )
+    })
+    .flatten()
+}
+
+#[neon::export]
+fn set_prismatic_joint_limits(world_id: f64, handle: f64, min: f64, max: f64) -> bool {
+    with_world_mut(world_id, |world| {
+        world.set_prismatic_joint_limits(handle, min, max)
+    })
+    .unwrap_or(false)
+}
+
+#[neon::export]
+fn configure_prismatic_joint_motor(
+    world_id: f64,
+    handle: f64,
+    target_pos: f64,
+    target_vel: f64,
+    stiffness: f64,
+    damping: f64,
+) -> bool {
+    with_world_mut(world_id, |world| {
+        world.configure_prismatic_joint_motor(handle, target_pos, target_vel, stiffness, damping)
+    })
+    .unwrap_or(false)
+}
+
+#[neon::export]
+fn set_prismatic_joint_motor_max_force(world_id: f64, handle: f64, max_force: f64) -> bool {
+    with_world_mut(world_id, |world| {
+        world.set_prismatic_joint_motor_max_force(handle, max_force)
+    })
+    .unwrap_or(false)
+}
+
+#[neon::export]
+fn set_spherical_joint_limits(world_id: f64, handle: f64, axis: f64, min: f64, max: f64) -> bool {
+    with_world_mut(world_id, |world| {
+        world.set_spherical_joint_limits(handle, axis as u32, min, max)
+    })
+    .unwrap_or(false)
+}
+
+#[neon::export]
+fn configure_spherical_joint_motor(
+    world_id: f64,
+    handle: f64,
+    axis: f64,
+    target_pos: f64,
+    target_vel: f64,
+    stiffness: f64,
+    damping: f64,
+) -> bool {
+    with_world_mut(world_id, |world| {
+        world.configure_spherical_joint_motor(
+            handle,
+            axis as u32,
+            target_pos,
+            target_vel,
+            stiffness,
+            damping,
+        )
+    })
+    .unwrap_or(false)
+}
+
+#[neon::export]
+fn set_rope_joint_max_distance(world_id: f64, handle: f64, max_distance: f64) -> bool {
+    with_world_mut(world_id, |world| {
+        world.set_rope_joint_max_distance(handle, max_distance)
+    })
+    .unwrap_or(false)
+}
+
+#[neon::export]
+fn configure_spring_joint(
+    world_id: f64,
+    handle: f64,
+    rest_length: f64,
+    stiffness: f64,
+    damping: f64,
+) -> bool {
+    with_world_mut(world_id, |world| {
+        world.configure_spring_joint(handle, rest_length, stiffness, damping)
+    })
+    .unwrap_or(false)
+}
+
+#[neon::export]
+fn get_joint_impulses(world_id: f64, handle: f64) -> Vec<f64> {
+    with_world(world_id, |world| {
+        if let Some(impulses) = world.get_joint_impulses(handle) {
+            impulses.to_vec()
+        } else {
+            vec![]
+        }
+    })
+    .unwrap_or_default()
+}
+
+#[neon::export]
+fn set_joint_break_threshold(
+    world_id: f64,
+    handle: f64,
+    linear: Option<f64>,
+    angular: Option<f64>,
+) -> bool {
+    with_world_mut(world_id, |world| {
+        world.set_joint_break_threshold(handle, linear, angular)
+    })
+    .unwrap_or(false)
+}
+
+#[neon::export]
+fn remove_joint_break_threshold(world_id: f64, handle: f64) -> bool {
+    with_world_mut(world_id, |world| world.remove_joint_break_threshold(handle)).unwrap_or(false)
+}
+
+#[neon::export]
+fn drain_broken_joints(world_id: f64) -> Vec<f64> {
+    with_world_mut(world_id, |world| world.drain_broken_joints()).unwrap_or_default()
+}
+
+#[neon::export]
+fn create_multibody_revolute_joint(
+    world_id: f64,
+    body1_handle: f64,
+    body2_handle: f64,
+    anchor1_x: f64,
+    anchor1_y: f64,
+    anchor1_z: f64,
+    anchor2_x: f64,
+    anchor2_y: f64,
+    anchor2_z: f64,
+    axis_x: f64,
+    axis_y: f64,
+    axis_z: f64,
+    wake_up: bool,
+) -> Option<f64> {
+    with_world_mut(world_id, |world| {
+        world.create_multibody_revolute_joint(
+            body1_handle,
+            body2_handle,
+            anchor1_x,
+            anchor1_y,
+            anchor1_z,
+            anchor2_x,
+            anchor2_y,
+            anchor2_z,
+            axis_x,
+            axis_y,
+            axis_z,
+            wake_up,
+        )
+    })
+    .flatten()
+}
+
+#[neon::export]
+fn create_multibody_prismatic_joint(
+    world_id: f64,
+    body1_handle: f64,
+    body2_handle: f64,
+    anchor1_x: f64,
+    anchor1_y: f64,
+    anchor1_z: f64,
+    anchor2_x: f64,
+    anchor2_y: f64,
+    anchor2_z: f64,
+    axis_x: f64,
+    axis_y: f64,
+    axis_z: f64,
+    wake_up: bool,
+) -> Option<f64> {
+    with_world_mut(world_id, |world| {
+        world.create_multibody_prismatic_joint(
+            body1_handle,
+            body2_handle,
+            anchor1_x,
+            anchor1_y,
+            anchor1_z,
+            anchor2_x,
+            anchor2_y,
+            anchor2_z,
+            axis_x,
+            axis_y,
+            axis_z,
+            wake_up,
+        )
+    })
+    .flatten()
+}
+
+#[neon::export]
+fn create_multibody_fixed_joint(
+    world_id: f64,
+    body1_handle: f64,
+    body2_handle: f64,
+    anchor1_x: f64,
+    anchor1_y: f64,
+    anchor1_z: f64,
+    anchor2_x: f64,
+    anchor2_y: f64,
+    anchor2_z: f64,
+    frame1_x: f64,
+    frame1_y: f64,
+    frame1_z: f64,
+    frame1_w: f64,
+    frame2_x: f64,
+    frame2_y: f64,
+    frame2_z: f64,
+    frame2_w: f64,
+    wake_up: bool,
+) -> Option<f64> {
+    with_world_mut(world_id, |world| {
+        world.create_multibody_fixed_joint(
+            body1_handle,
+            body2_handle,
+            anchor1_x,
+            anchor1_y,
+            anchor1_z,
+            anchor2_x,
+            anchor2_y,
+            anchor2_z,
+            frame1_x,
+            frame1_y,
+            frame1_z,
+            frame1_w,
+            frame2_x,
+            frame2_y,
+            frame2_z,
+            frame2_w,
+            wake_up,
+        )
+    })
+    .flatten()
+}
+
+#[neon::export]
+fn remove_multibody_joint(world_id: f64, handle: f64, wake_up: bool) -> bool {
+    with_world_mut(world_id, |world| {
+        world.remove_multibody_joint(handle, wake_up)
+    })
+    .unwrap_or(false)
+}
+
+#[neon::export]
+fn set_multibody_joint_limits(world_id: f64, handle: f64, min: f64, max: f64) -> bool {
+    with_world_mut(world_id, |world| {
+        world.set_multibody_joint_limits(handle, min, max)
+    })
+    .unwrap_or(false)
+}
+
+#[neon::export]
+fn configure_multibody_joint_motor(
+    world_id: f64,
+    handle: f64,
+    target_pos: f64,
+    target_vel: f64,
+    stiffness: f64,
+    damping: f64,
+) -> bool {
+    with_world_mut(world_id, |world| {
+        world.configure_multibody_joint_motor(handle, target_pos, target_vel, stiffness, damping)
+    })
+    .unwrap_or(false)
+}
+
+#[neon::export]
+fn set_multibody_joint_motor_max_force(world_id: f64, handle: f64, max_force: f64) -> bool {
+    with_world_mut(world_id, |world| {
+        world.set_multibody_joint_motor_max_force(handle, max_force)
+    })
+    .unwrap_or(false)
+}
+
+#[neon::export]
+fn get_multibody_joint_link_position(world_id: f64, handle: f64) -> Vec<f64> {
+    with_world(world_id, |world| {
+        if let Some(position) = world.get_multibody_joint_link_position(handle) {
+            position.to_vec()
+        } else {
+            vec![]
         }
-    }
+    })
+    .unwrap_or_default()
+}
 
-    pub fn get_collider_radius(&self, handle: f64) -> Option<Real> {
-        let (index, generation) = decode_handle_from_js(handle);
-        let handle = ColliderHandle::from_raw_parts(index, generation);
-        if let Some(collider) = self.collider_set.get(handle) {
-            if let Some(cylinder) = collider.shape().as_cylinder() {
-                Some(cylinder.radius)
-            } else {
-                None
-            }
+#[neon::export]
+fn get_multibody_joint_data(world_id: f64, handle: f64) -> Vec<f64> {
+    with_world(world_id, |world| {
+        if let Some(data) = world.get_multibody_joint_data(handle) {
+            data
         } else {
-            None
+            vec![]
         }
-    }
+    })
+    .unwrap_or_default()
+}
 
-    pub fn get_collider_half_height(&self, handle: f64) -> Option<Real> {
-        let (index, generation) = decode_handle_from_js(handle);
-        let handle = ColliderHandle::from_raw_parts(index, generation);
-        if let Some(collider) = self.collider_set.get(handle) {
-            if let Some(cylinder) = collider.shape().as_cylinder() {
-                Some(cylinder.half_height)
-            } else if let Some(cuboid) = collider.shape().as_cuboid() {
-                Some(cuboid.half_extents.z)
-            } else {
-                None
-            }
+#[neon::export]
+fn get_joint_data(world_id: f64, handle: f64) -> Vec<f64> {
+    with_world(world_id, |world| {
+        if let Some(data) = world.get_joint_data(handle) {
+            data
         } else {
-            None
+            vec![]
         }
-    }
+    })
+    .unwrap_or_default()
+}
 
-    pub fn get_collider_flags(&self, handle: f64) -> Option<u32> {
+#[neon::export]
+fn intersection_pairs_with(world_id: f64, handle: f64) -> Vec<f64> {
+    with_world(world_id, |world| {
         let (index, generation) = decode_handle_from_js(handle);
         let handle = ColliderHandle::from_raw_parts(index, generation);
-        if let Some(collider) = self.collider_set.get(handle) {
-            if let Some(trimesh) = collider.shape().as_trimesh() {
-                Some(trimesh.flags().bits() as u32)
-            } else {
-                None
+        let mut result = Vec::new();
+        for contact_pair in world.narrow_phase.contact_pairs() {
+            let handle1 = contact_pair.collider1;
+            let handle2 = contact_pair.collider2;
+            if handle1 == handle || handle2 == handle {
+                let other_handle = if handle1 == handle { handle2 } else { handle1 };
+                let (index, generation) = other_handle.into_raw_parts();
+                result.push(encode_handle_for_js(index, generation));
             }
-        } else {
-            None
         }
-    }
-
-    pub fn take_snapshot(&self) -> Vec<u8> {
-        let serializable_world = SerializableWorld {
-            gravity: self.gravity,
-            integration_parameters: self.integration_parameters.clone(),
-            islands: self.island_manager.clone(),
-            broad_phase: self.broad_phase.clone(),
-            narrow_phase: self.narrow_phase.clone(),
-            bodies: self.rigid_body_set.clone(),
-            colliders: self.collider_set.clone(),
-            impulse_joints: self.impulse_joint_set.clone(),
-            multibody_joints: self.multibody_joint_set.clone(),
-        };
-
-        serialize(&serializable_world).unwrap_or_else(|_| Vec::new())
-    }
-
-    pub fn restore_snapshot(&mut self, snapshot: &[u8]) -> bool {
-        match deserialize::<SerializableWorld>(snapshot) {
-            Ok(world_data) => {
-                self.gravity = world_data.gravity;
-                self.integration_parameters = world_data.integration_parameters;
-                self.island_manager = world_data.islands;
-                self.broad_phase = world_data.broad_phase;
-                self.narrow_phase = world_data.narrow_phase;
-                self.rigid_body_set = world_data.bodies;
-                self.collider_set = world_data.colliders;
-                self.impulse_joint_set = world_data.impulse_joints;
-                self.multibody_joint_set = world_data.multibody_joints;
-                true
+        for intersection_pair in world.narrow_phase.intersection_pairs() {
+            let handle1 = intersection_pair.0;
+            let handle2 = intersection_pair.1;
+            if handle1 == handle || handle2 == handle {
+                let other_handle = if handle1 == handle { handle2 } else { handle1 };
+                let (index, generation) = other_handle.into_raw_parts();
+                result.push(encode_handle_for_js(index, generation));
             }
-            Err(_) => false,
         }
-    }
+        result
+    })
+    .unwrap_or_default()
 }
 
-static mut WORLD: Option<World> = None;
-
 #[neon::export]
-fn init_world(gravity_x: f64, gravity_y: f64, gravity_z: f64) -> bool {
-    unsafe {
-        WORLD = Some(World::new(gravity_x, gravity_y, gravity_z));
-    }
-    true
+fn drain_collision_events(world_id: f64) -> Vec<f64> {
+    with_world_mut(world_id, |world| world.drain_collision_events()).unwrap_or_default()
 }
 
 #[neon::export]
-fn destroy_world() -> bool {
-    unsafe {
-        WORLD = None;
-    }
-    true
+fn drain_contact_force_events(world_id: f64) -> Vec<f64> {
+    with_world_mut(world_id, |world| world.drain_contact_force_events()).unwrap_or_default()
 }
 
 #[neon::export]
-fn set_world_gravity(x: f64, y: f64, z: f64) -> bool {
-    unsafe {
-        if let Some(ref mut world) = WORLD {
-            world.set_gravity(x, y, z);
-            true
+fn cast_ray(
+    world_id: f64,
+    origin_x: f64,
+    origin_y: f64,
+    origin_z: f64,
+    dir_x: f64,
+    dir_y: f64,
+    dir_z: f64,
+    max_toi: f64,
+    solid: bool,
+    memberships: Option<f64>,
+    filter: Option<f64>,
+    exclude_body: Option<f64>,
+) -> Vec<f64> {
+    with_world(world_id, |world| {
+        if let Some((handle, toi)) = world.cast_ray(
+            Point::new(origin_x, origin_y, origin_z),
+            Vector3::new(dir_x, dir_y, dir_z),
+            max_toi,
+            solid,
+            interaction_groups(memberships, filter),
+            exclude_body,
+        ) {
+            vec![handle, toi]
         } else {
-            false
+            vec![]
         }
-    }
+    })
+    .unwrap_or_default()
 }
 
 #[neon::export]
-fn get_world_gravity() -> Vec<f64> {
-    unsafe {
-        if let Some(ref world) = WORLD {
-            vec![world.gravity.x, world.gravity.y, world.gravity.z]
+fn cast_ray_and_get_normal(
+    world_id: f64,
+    origin_x: f64,
+    origin_y: f64,
+    origin_z: f64,
+    dir_x: f64,
+    dir_y: f64,
+    dir_z: f64,
+    max_toi: f64,
+    solid: bool,
+    memberships: Option<f64>,
+    filter: Option<f64>,
+    exclude_body: Option<f64>,
+) -> Vec<f64> {
+    with_world(world_id, |world| {
+        if let Some((handle, toi, normal)) = world.cast_ray_and_get_normal(
+            Point::new(origin_x, origin_y, origin_z),
+            Vector3::new(dir_x, dir_y, dir_z),
+            max_toi,
+            solid,
+            interaction_groups(memberships, filter),
+            exclude_body,
+        ) {
+            vec![handle, toi, normal.x, normal.y, normal.z]
         } else {
-            vec![0.0, 0.0, 0.0]
+            vec![]
         }
-    }
+    })
+    .unwrap_or_default()
 }
 
 #[neon::export]
-fn set_integration_parameters_num_solver_iterations(num_solver_iterations: f64) -> bool {
-    unsafe {
-        if let Some(ref mut world) = WORLD {
-            world.integration_parameters.num_solver_iterations =
-                NonZeroUsize::new(num_solver_iterations as usize).unwrap();
-            true
+fn cast_shape(
+    world_id: f64,
+    shape_type: f64,
+    shape_params: Vec<f64>,
+    pos_x: f64,
+    pos_y: f64,
+    pos_z: f64,
+    rot_x: f64,
+    rot_y: f64,
+    rot_z: f64,
+    rot_w: f64,
+    vel_x: f64,
+    vel_y: f64,
+    vel_z: f64,
+    max_toi: f64,
+    stop_at_penetration: bool,
+    memberships: Option<f64>,
+    filter: Option<f64>,
+    exclude_body: Option<f64>,
+) -> Vec<f64> {
+    let shape_params: Vec<Real> = shape_params.into_iter().collect();
+    build_shape(shape_type as u32, &shape_params)
+        .and_then(|shape| {
+            with_world(world_id, |world| {
+                let position = Isometry::from_parts(
+                    Translation3::new(pos_x, pos_y, pos_z),
+                    UnitQuaternion::from_quaternion(Quaternion::new(rot_w, rot_x, rot_y, rot_z)),
+                );
+                let velocity = Vector3::new(vel_x, vel_y, vel_z);
+                if let Some((handle, hit)) = world.cast_shape(
+                    shape.as_ref(),
+                    &position,
+                    &velocity,
+                    max_toi,
+                    stop_at_penetration,
+                    interaction_groups(memberships, filter),
+                    exclude_body,
+                ) {
+                    vec![
+                        handle,
+                        hit.time_of_impact,
+                        hit.witness1.x,
+                        hit.witness1.y,
+                        hit.witness1.z,
+                        hit.witness2.x,
+                        hit.witness2.y,
+                        hit.witness2.z,
+                        hit.normal1.x,
+                        hit.normal1.y,
+                        hit.normal1.z,
+                        hit.normal2.x,
+                        hit.normal2.y,
+                        hit.normal2.z,
+                    ]
+                } else {
+                    vec![]
+                }
+            })
+        })
+        .unwrap_or_default()
+}
+
+#[neon::export]
+fn project_point(
+    world_id: f64,
+    x: f64,
+    y: f64,
+    z: f64,
+    max_dist: f64,
+    solid: bool,
+    memberships: Option<f64>,
+    filter: Option<f64>,
+) -> Vec<f64> {
+    with_world(world_id, |world| {
+        if let Some((handle, projection)) = world.project_point(
+            &Point::new(x, y, z),
+            max_dist,
+            solid,
+            interaction_groups(memberships, filter),
+        ) {
+            vec![
+                handle,
+                projection.point.x,
+                projection.point.y,
+                projection.point.z,
+                if projection.is_inside { 1.0 } else { 0.0 },
+            ]
         } else {
-            false
+            vec![]
         }
-    }
+    })
+    .unwrap_or_default()
 }
 
 #[neon::export]
-fn set_integration_parameters_num_additional_friction_iterations(
-    num_additional_friction_iterations: f64,
-) -> bool {
-    unsafe {
-        if let Some(ref mut world) = WORLD {
-            world
-                .integration_parameters
-                .num_additional_friction_iterations = num_additional_friction_iterations as usize;
-            true
+fn intersections_with_point(
+    world_id: f64,
+    x: f64,
+    y: f64,
+    z: f64,
+    memberships: Option<f64>,
+    filter: Option<f64>,
+) -> Vec<f64> {
+    with_world(world_id, |world| {
+        world.intersections_with_point(
+            &Point::new(x, y, z),
+            interaction_groups(memberships, filter),
+        )
+    })
+    .unwrap_or_default()
+}
+
+#[neon::export]
+fn intersections_with_shape(
+    world_id: f64,
+    shape_type: f64,
+    shape_params: Vec<f64>,
+    pos_x: f64,
+    pos_y: f64,
+    pos_z: f64,
+    rot_x: f64,
+    rot_y: f64,
+    rot_z: f64,
+    rot_w: f64,
+    memberships: Option<f64>,
+    filter: Option<f64>,
+) -> Vec<f64> {
+    let shape_params: Vec<Real> = shape_params.into_iter().collect();
+    build_shape(shape_type as u32, &shape_params)
+        .and_then(|shape| {
+            with_world(world_id, |world| {
+                let position = Isometry::from_parts(
+                    Translation3::new(pos_x, pos_y, pos_z),
+                    UnitQuaternion::from_quaternion(Quaternion::new(rot_w, rot_x, rot_y, rot_z)),
+                );
+                world.intersections_with_shape(
+                    shape.as_ref(),
+                    &position,
+                    interaction_groups(memberships, filter),
+                )
+            })
+        })
+        .unwrap_or_default()
+}
+
+#[neon::export]
+fn find_free_spawn_pose(
+    world_id: f64,
+    min_x: f64,
+    min_y: f64,
+    min_z: f64,
+    max_x: f64,
+    max_y: f64,
+    max_z: f64,
+    radius: f64,
+    half_height: f64,
+    memberships: Option<f64>,
+    filter: Option<f64>,
+) -> Vec<f64> {
+    with_world(world_id, |world| {
+        if let Some(translation) = world.find_free_spawn_pose(
+            &Point::new(min_x, min_y, min_z),
+            &Point::new(max_x, max_y, max_z),
+            radius,
+            half_height,
+            interaction_groups(memberships, filter),
+        ) {
+            vec![translation.x, translation.y, translation.z]
         } else {
-            false
+            vec![]
         }
-    }
+    })
+    .unwrap_or_default()
 }
 
 #[neon::export]
-fn set_integration_parameters_num_internal_pgs_iterations(
-    num_internal_pgs_iterations: f64,
+fn set_body_translation(world_id: f64, handle: f64, x: f64, y: f64, z: f64, wake_up: bool) -> bool {
+    with_world_mut(world_id, |world| {
+        world.set_body_translation(handle, x, y, z, wake_up)
+    })
+    .unwrap_or(false)
+}
+
+#[neon::export]
+fn set_body_next_kinematic_translation(world_id: f64, handle: f64, x: f64, y: f64, z: f64) -> bool {
+    with_world_mut(world_id, |world| {
+        world.set_body_next_kinematic_translation(handle, x, y, z)
+    })
+    .unwrap_or(false)
+}
+
+#[neon::export]
+fn set_body_rotation(
+    world_id: f64,
+    handle: f64,
+    x: f64,
+    y: f64,
+    z: f64,
+    w: f64,
+    wake_up: bool,
 ) -> bool {
-    unsafe {
-        if let Some(ref mut world) = WORLD {
-            world.integration_parameters.num_internal_pgs_iterations =
-                num_internal_pgs_iterations as usize;
-            true
-        } else {
-            false
-        }
-    }
+    with_world_mut(world_id, |world| {
+        world.set_body_rotation(handle, x, y, z, w, wake_up)
+    })
+    .unwrap_or(false)
 }
 
 #[neon::export]
-fn set_integration_parameters_min_island_size(min_island_size: f64) -> bool {
-    unsafe {
-        if let Some(ref mut world) = WORLD {
-            world.integration_parameters.min_island_size = min_island_size as usize;
-            true
+fn set_body_next_kinematic_rotation(
+    world_id: f64,
+    handle: f64,
+    x: f64,
+    y: f64,
+    z: f64,
+    w: f64,
+) -> bool {
+    with_world_mut(world_id, |world| {
+        world.set_body_next_kinematic_rotation(handle, x, y, z, w)
+    })
+    .unwrap_or(false)
+}
+
+#[neon::export]
+fn set_body_velocity(world_id: f64, handle: f64, vx: f64, vy: f64, vz: f64, wake_up: bool) -> bool {
+    with_world_mut(world_id, |world| {
+        world.set_body_velocity(handle, vx, vy, vz, wake_up)
+    })
+    .unwrap_or(false)
+}
+
+#[neon::export]
+fn set_body_angular_velocity(
+    world_id: f64,
+    handle: f64,
+    wx: f64,
+    wy: f64,
+    wz: f64,
+    wake_up: bool,
+) -> bool {
+    with_world_mut(world_id, |world| {
+        world.set_body_angular_velocity(handle, wx, wy, wz, wake_up)
+    })
+    .unwrap_or(false)
+}
+
+#[neon::export]
+fn apply_impulse(world_id: f64, handle: f64, x: f64, y: f64, z: f64, wake_up: bool) -> bool {
+    with_world_mut(world_id, |world| {
+        world.apply_impulse(handle, x, y, z, wake_up)
+    })
+    .unwrap_or(false)
+}
+
+#[neon::export]
+fn add_force(world_id: f64, handle: f64, x: f64, y: f64, z: f64, wake_up: bool) -> bool {
+    with_world_mut(world_id, |world| world.add_force(handle, x, y, z, wake_up)).unwrap_or(false)
+}
+
+#[neon::export]
+fn add_torque(world_id: f64, handle: f64, x: f64, y: f64, z: f64, wake_up: bool) -> bool {
+    with_world_mut(world_id, |world| world.add_torque(handle, x, y, z, wake_up)).unwrap_or(false)
+}
+
+#[neon::export]
+fn apply_torque_impulse(world_id: f64, handle: f64, x: f64, y: f64, z: f64, wake_up: bool) -> bool {
+    with_world_mut(world_id, |world| {
+        world.apply_torque_impulse(handle, x, y, z, wake_up)
+    })
+    .unwrap_or(false)
+}
+
+#[neon::export]
+fn apply_impulse_at_point(
+    world_id: f64,
+    handle: f64,
+    impulse_x: f64,
+    impulse_y: f64,
+    impulse_z: f64,
+    point_x: f64,
+    point_y: f64,
+    point_z: f64,
+    wake_up: bool,
+) -> bool {
+    with_world_mut(world_id, |world| {
+        world.apply_impulse_at_point(
+            handle, impulse_x, impulse_y, impulse_z, point_x, point_y, point_z, wake_up,
+        )
+    })
+    .unwrap_or(false)
+}
+
+#[neon::export]
+fn add_force_at_point(
+    world_id: f64,
+    handle: f64,
+    force_x: f64,
+    force_y: f64,
+    force_z: f64,
+    point_x: f64,
+    point_y: f64,
+    point_z: f64,
+    wake_up: bool,
+) -> bool {
+    with_world_mut(world_id, |world| {
+        world.add_force_at_point(
+            handle, force_x, force_y, force_z, point_x, point_y, point_z, wake_up,
+        )
+    })
+    .unwrap_or(false)
+}
+
+#[neon::export]
+fn reset_forces(world_id: f64, handle: f64, wake_up: bool) -> bool {
+    with_world_mut(world_id, |world| world.reset_forces(handle, wake_up)).unwrap_or(false)
+}
+
+#[neon::export]
+fn reset_torques(world_id: f64, handle: f64, wake_up: bool) -> bool {
+    with_world_mut(world_id, |world| world.reset_torques(handle, wake_up)).unwrap_or(false)
+}
+
+#[neon::export]
+fn set_gravity_scale(world_id: f64, handle: f64, scale: f64, wake_up: bool) -> bool {
+    with_world_mut(world_id, |world| {
+        world.set_gravity_scale(handle, scale, wake_up)
+    })
+    .unwrap_or(false)
+}
+
+#[neon::export]
+fn set_dominance_group(world_id: f64, handle: f64, group: f64) -> bool {
+    with_world_mut(world_id, |world| {
+        world.set_dominance_group(handle, group as i8)
+    })
+    .unwrap_or(false)
+}
+
+#[neon::export]
+fn lock_rotations(world_id: f64, handle: f64, locked: bool, wake_up: bool) -> bool {
+    with_world_mut(world_id, |world| {
+        world.lock_rotations(handle, locked, wake_up)
+    })
+    .unwrap_or(false)
+}
+
+#[neon::export]
+fn lock_translations(world_id: f64, handle: f64, locked: bool, wake_up: bool) -> bool {
+    with_world_mut(world_id, |world| {
+        world.lock_translations(handle, locked, wake_up)
+    })
+    .unwrap_or(false)
+}
+
+#[neon::export]
+fn get_body_translation(world_id: f64, handle: f64) -> Vec<f64> {
+    with_world(world_id, |world| {
+        if let Some((x, y, z)) = world.get_body_translation(handle) {
+            vec![x, y, z]
         } else {
-            false
+            vec![]
         }
-    }
+    })
+    .unwrap_or_default()
 }
 
 #[neon::export]
-fn set_integration_parameters_dt(dt: f64) -> bool {
-    unsafe {
-        if let Some(ref mut world) = WORLD {
-            world.integration_parameters.dt = dt;
-            true
+fn get_body_rotation(world_id: f64, handle: f64) -> Vec<f64> {
+    with_world(world_id, |world| {
+        if let Some((x, y, z, w)) = world.get_body_rotation(handle) {
+            vec![x, y, z, w]
         } else {
-            false
+            vec![]
         }
-    }
+    })
+    .unwrap_or_default()
 }
 
 #[neon::export]
-fn integration_parameters_switch_to_standard_pgs_solver() -> bool {
-    IntegrationParameters::pgs_legacy();
-    true
+fn get_body_count(world_id: f64) -> f64 {
+    with_world(world_id, |world| world.get_body_count() as f64).unwrap_or(0.0)
 }
 
 #[neon::export]
-fn integration_parameters_switch_to_small_steps_pgs_solver() -> bool {
-    IntegrationParameters::tgs_soft();
-    true
+fn get_body_handles(world_id: f64) -> Float64Array<Vec<f64>> {
+    Float64Array(with_world(world_id, |world| world.get_body_handles()).unwrap_or_default())
 }
 
 #[neon::export]
-fn integration_parameters_switch_to_small_steps_pgs_solver_without_warm_start() -> bool {
-    IntegrationParameters::tgs_soft_without_warmstart();
-    true
+fn get_body_translations(world_id: f64) -> Float64Array<Vec<f64>> {
+    Float64Array(with_world(world_id, |world| world.get_body_translations()).unwrap_or_default())
 }
 
 #[neon::export]
-fn get_world_bodies() -> Vec<f64> {
-    unsafe {
-        if let Some(ref world) = WORLD {
-            world
-                .rigid_body_set
-                .iter()
-                .map(|(handle, _)| {
-                    let (index, generation) = handle.into_raw_parts();
-                    encode_handle_for_js(index, generation)
-                })
-                .collect()
-        } else {
-            Vec::new()
+fn get_body_rotations(world_id: f64) -> Float64Array<Vec<f64>> {
+    Float64Array(with_world(world_id, |world| world.get_body_rotations()).unwrap_or_default())
+}
+
+#[neon::export]
+fn write_body_poses<'cx>(
+    cx: &mut Cx<'cx>,
+    world_id: f64,
+    mut buffer: Handle<'cx, JsFloat64Array>,
+) -> f64 {
+    with_world(world_id, |world| {
+        match world.write_body_poses(buffer.as_mut_slice(cx)) {
+            Some(count) => count as f64,
+            None => -1.0,
         }
-    }
+    })
+    .unwrap_or(-1.0)
 }
 
 #[neon::export]
-fn get_world_colliders() -> Vec<f64> {
-    unsafe {
-        if let Some(ref world) = WORLD {
-            world
-                .collider_set
-                .iter()
-                .map(|(handle, _)| {
-                    let (index, generation) = handle.into_raw_parts();
-                    encode_handle_for_js(index, generation)
-                })
-                .collect()
+fn get_active_body_poses(world_id: f64) -> Float64Array<Vec<f64>> {
+    Float64Array(
+        with_world_mut(world_id, |world| world.get_active_body_poses()).unwrap_or_default(),
+    )
+}
+
+#[neon::export]
+fn get_body_velocity(world_id: f64, handle: f64) -> Vec<f64> {
+    with_world(world_id, |world| {
+        if let Some((vx, vy, vz)) = world.get_body_velocity(handle) {
+            vec![vx, vy, vz]
         } else {
-            Vec::new()
+            vec![]
         }
-    }
+    })
+    .unwrap_or_default()
 }
 
 #[neon::export]
-fn get_world_impulse_joints() -> Vec<f64> {
-    unsafe {
-        if let Some(ref world) = WORLD {
-            world
-                .impulse_joint_set
-                .iter()
-                .map(|(handle, _)| {
-                    let (index, generation) = handle.into_raw_parts();
-                    encode_handle_for_js(index, generation)
-                })
-                .collect()
+fn get_body_angular_velocity(world_id: f64, handle: f64) -> Vec<f64> {
+    with_world(world_id, |world| {
+        if let Some((wx, wy, wz)) = world.get_body_angular_velocity(handle) {
+            vec![wx, wy, wz]
         } else {
-            Vec::new()
+            vec![]
         }
-    }
+    })
+    .unwrap_or_default()
+}
+
+#[neon::export]
+fn is_body_enabled(world_id: f64, handle: f64) -> bool {
+    with_world(world_id, |world| world.is_body_enabled(handle)).unwrap_or(false)
+}
+
+#[neon::export]
+fn set_body_enabled(world_id: f64, handle: f64, enabled: bool) -> bool {
+    with_world_mut(world_id, |world| world.set_body_enabled(handle, enabled)).unwrap_or(false)
+}
+
+#[neon::export]
+fn set_body_enabled_translations(
+    world_id: f64,
+    handle: f64,
+    enabled_x: bool,
+    enabled_y: bool,
+    enabled_z: bool,
+    wake_up: bool,
+) -> bool {
+    with_world_mut(world_id, |world| {
+        world.set_body_enabled_translations(handle, enabled_x, enabled_y, enabled_z, wake_up)
+    })
+    .unwrap_or(false)
+}
+
+#[neon::export]
+fn set_body_enabled_rotations(
+    world_id: f64,
+    handle: f64,
+    enabled_x: bool,
+    enabled_y: bool,
+    enabled_z: bool,
+    wake_up: bool,
+) -> bool {
+    with_world_mut(world_id, |world| {
+        world.set_body_enabled_rotations(handle, enabled_x, enabled_y, enabled_z, wake_up)
+    })
+    .unwrap_or(false)
+}
+
+#[neon::export]
+fn body_sleep(world_id: f64, handle: f64) -> bool {
+    with_world_mut(world_id, |world| world.body_sleep(handle)).unwrap_or(false)
+}
+
+#[neon::export]
+fn is_body_sleeping(world_id: f64, handle: f64) -> bool {
+    with_world(world_id, |world| world.is_body_sleeping(handle)).unwrap_or(false)
+}
+
+#[neon::export]
+fn set_body_soft_ccd_prediction(world_id: f64, handle: f64, precision: f64) -> bool {
+    with_world_mut(world_id, |world| {
+        world.set_body_soft_ccd_prediction(handle, precision)
+    })
+    .unwrap_or(false)
 }
 
 #[neon::export]
-fn set_timestep(dt: f64) -> bool {
-    unsafe {
-        if let Some(ref mut world) = WORLD {
-            world.integration_parameters.dt = dt;
-            true
-        } else {
-            false
-        }
-    }
+fn set_body_ccd_enabled(world_id: f64, handle: f64, enabled: bool) -> bool {
+    with_world_mut(world_id, |world| {
+        world.set_body_ccd_enabled(handle, enabled)
+    })
+    .unwrap_or(false)
 }
 
 #[neon::export]
-fn take_snapshot() -> Vec<u8> {
-    unsafe {
-        if let Some(ref world) = WORLD {
-            world.take_snapshot()
-        } else {
-            Vec::new()
-        }
-    }
+fn set_body_additional_solver_iterations(world_id: f64, handle: f64, iterations: f64) -> bool {
+    with_world_mut(world_id, |world| {
+        world.set_body_additional_solver_iterations(handle, iterations as usize)
+    })
+    .unwrap_or(false)
 }
 
 #[neon::export]
-fn restore_snapshot(snapshot: Vec<u8>) -> bool {
-    unsafe {
-        if let Some(ref mut world) = WORLD {
-            world.restore_snapshot(&snapshot)
+fn get_body_mass(world_id: f64, handle: f64) -> f64 {
+    with_world(world_id, |world| {
+        if let Some(mass) = world.get_body_mass(handle) {
+            mass
         } else {
-            false
+            -1.0
         }
-    }
+    })
+    .unwrap_or(-1.0)
 }
 
 #[neon::export]
-fn create_dynamic_body() -> f64 {
-    unsafe {
-        if let Some(ref mut world) = WORLD {
-            world.create_dynamic_body()
-        } else {
-            0.0
-        }
-    }
+fn set_body_angular_damping(world_id: f64, handle: f64, damping: f64) -> bool {
+    with_world_mut(world_id, |world| {
+        world.set_body_angular_damping(handle, damping)
+    })
+    .unwrap_or(false)
 }
 
 #[neon::export]
-fn create_kinematic_body() -> f64 {
-    unsafe {
-        if let Some(ref mut world) = WORLD {
-            world.create_kinematic_body()
-        } else {
-            0.0
-        }
-    }
+fn set_body_linear_damping(world_id: f64, handle: f64, damping: f64) -> bool {
+    with_world_mut(world_id, |world| {
+        world.set_body_linear_damping(handle, damping)
+    })
+    .unwrap_or(false)
 }
 
 #[neon::export]
-fn create_fixed_body() -> f64 {
-    unsafe {
-        if let Some(ref mut world) = WORLD {
-            world.create_fixed_body()
-        } else {
-            0.0
-        }
-    }
+fn get_body_num_colliders(world_id: f64, handle: f64) -> f64 {
+    with_world(world_id, |world| {
+        world.get_body_num_colliders(handle) as f64
+    })
+    .unwrap_or(-1.0)
 }
 
 #[neon::export]
-fn add_box_collider(
-    handle: f64,
-    half_x: f64,
-    half_y: f64,
-    half_z: f64,
-    is_sensor: bool,
-    pos_x: Option<f64>,
-    pos_y: Option<f64>,
-    pos_z: Option<f64>,
-    rot_x: Option<f64>,
-    rot_y: Option<f64>,
-    rot_z: Option<f64>,
-) -> f64 {
-    unsafe {
-        if let Some(ref mut world) = WORLD {
-            world.add_box_collider(
-                handle, half_x, half_y, half_z, is_sensor, pos_x, pos_y, pos_z, rot_x, rot_y, rot_z,
-            )
-        } else {
-            0.0
-        }
-    }
+fn set_body_user_data(world_id: f64, handle: f64, user_data: f64) -> bool {
+    user_data_from_js(user_data)
+        .and_then(|user_data| {
+            with_world_mut(world_id, |world| {
+                world.set_body_user_data(handle, user_data)
+            })
+        })
+        .unwrap_or(false)
 }
 
 #[neon::export]
-fn add_cylinder_collider(
-    handle: f64,
-    half_height: f64,
-    radius: f64,
-    is_sensor: bool,
-    pos_x: Option<f64>,
-    pos_y: Option<f64>,
-    pos_z: Option<f64>,
-    rot_x: Option<f64>,
-    rot_y: Option<f64>,
-    rot_z: Option<f64>,
-) -> f64 {
-    unsafe {
-        if let Some(ref mut world) = WORLD {
-            world.add_cylinder_collider(
-                handle,
-                half_height,
-                radius,
-                is_sensor,
-                pos_x,
-                pos_y,
-                pos_z,
-                rot_x,
-                rot_y,
-                rot_z,
-            )
+fn get_body_user_data(world_id: f64, handle: f64) -> f64 {
+    with_world(world_id, |world| {
+        if let Some(user_data) = world.get_body_user_data(handle) {
+            user_data as f64
         } else {
-            0.0
+            -1.0
         }
-    }
+    })
+    .unwrap_or(-1.0)
 }
 
 #[neon::export]
-fn add_trimesh_collider(
-    handle: f64,
-    vertices: Vec<f64>,
-    indices: Vec<f64>,
-    is_sensor: bool,
-    trimesh_flags: f64,
-    pos_x: Option<f64>,
-    pos_y: Option<f64>,
-    pos_z: Option<f64>,
-    rot_x: Option<f64>,
-    rot_y: Option<f64>,
-    rot_z: Option<f64>,
-) -> f64 {
-    unsafe {
-        if let Some(ref mut world) = WORLD {
-            world.add_trimesh_collider(
-                handle,
-                vertices.into_iter().collect(),
-                indices.into_iter().map(|v| v as u32).collect(),
-                is_sensor,
-                trimesh_flags as u32,
-                pos_x,
-                pos_y,
-                pos_z,
-                rot_x,
-                rot_y,
-                rot_z,
-            )
-        } else {
-            0.0
-        }
-    }
+fn set_collider_user_data(world_id: f64, handle: f64, user_data: f64) -> bool {
+    user_data_from_js(user_data)
+        .and_then(|user_data| {
+            with_world_mut(world_id, |world| {
+                world.set_collider_user_data(handle, user_data)
+            })
+        })
+        .unwrap_or(false)
 }
 
 #[neon::export]
-fn add_convex_hull_collider(
-    handle: f64,
-    vertices: Vec<f64>,
-    is_sensor: bool,
-    pos_x: Option<f64>,
-    pos_y: Option<f64>,
-    pos_z: Option<f64>,
-    rot_x: Option<f64>,
-    rot_y: Option<f64>,
-    rot_z: Option<f64>,
-) -> f64 {
-    unsafe {
-        if let Some(ref mut world) = WORLD {
-            world.add_convex_hull_collider(
-                handle,
-                vertices.into_iter().collect(),
-                is_sensor,
-                pos_x,
-                pos_y,
-                pos_z,
-                rot_x,
-                rot_y,
-                rot_z,
-            )
+fn get_collider_user_data(world_id: f64, handle: f64) -> f64 {
+    with_world(world_id, |world| {
+        if let Some(user_data) = world.get_collider_user_data(handle) {
+            user_data as f64
         } else {
-            0.0
+            -1.0
         }
-    }
+    })
+    .unwrap_or(-1.0)
 }
 
 #[neon::export]
-fn create_revolute_joint(
-    body1_handle: f64,
-    body2_handle: f64,
-    anchor1_x: f64,
-    anchor1_y: f64,
-    anchor1_z: f64,
-    anchor2_x: f64,
-    anchor2_y: f64,
-    anchor2_z: f64,
-    axis_x: f64,
-    axis_y: f64,
-    axis_z: f64,
-    wake_up: bool,
-) -> f64 {
-    unsafe {
-        if let Some(ref mut world) = WORLD {
-            world.create_revolute_joint(
-                body1_handle,
-                body2_handle,
-                anchor1_x,
-                anchor1_y,
-                anchor1_z,
-                anchor2_x,
-                anchor2_y,
-                anchor2_z,
-                axis_x,
-                axis_y,
-                axis_z,
-                wake_up,
-            )
-        } else {
-            0.0
-        }
-    }
+fn get_bodies_user_data(world_id: f64) -> Vec<f64> {
+    with_world(world_id, |world| world.get_bodies_user_data()).unwrap_or_default()
 }
 
 #[neon::export]
-fn create_fixed_joint(
-    body1_handle: f64,
-    body2_handle: f64,
-    anchor1_x: f64,
-    anchor1_y: f64,
-    anchor1_z: f64,
-    anchor2_x: f64,
-    anchor2_y: f64,
-    anchor2_z: f64,
-    frame1_x: f64,
-    frame1_y: f64,
-    frame1_z: f64,
-    frame1_w: f64,
-    frame2_x: f64,
-    frame2_y: f64,
-    frame2_z: f64,
-    frame2_w: f64,
-    wake_up: bool,
-) -> f64 {
-    unsafe {
-        if let Some(ref mut world) = WORLD {
-            world.create_fixed_joint(
-                body1_handle,
-                body2_handle,
-                anchor1_x,
-                anchor1_y,
-                anchor1_z,
-                anchor2_x,
-                anchor2_y,
-                anchor2_z,
-                frame1_x,
-                frame1_y,
-                frame1_z,
-                frame1_w,
-                frame2_x,
-                frame2_y,
-                frame2_z,
-                frame2_w,
-                wake_up,
-            )
-        } else {
-            0.0
-        }
-    }
+fn get_body_collider(world_id: f64, handle: f64, collider_index: f64) -> Option<f64> {
+    with_world(world_id, |world| {
+        world.get_body_collider(handle, collider_index as usize)
+    })
+    .flatten()
 }
 
 #[neon::export]
-fn set_revolute_joint_limits(handle: f64, min_angle: f64, max_angle: f64) -> bool {
-    unsafe {
-        if let Some(ref mut world) = WORLD {
-            world.set_revolute_joint_limits(handle, min_angle, max_angle)
-        } else {
-            false
-        }
-    }
+fn set_collider_mass(world_id: f64, handle: f64, mass: f64) -> bool {
+    with_world_mut(world_id, |world| world.set_collider_mass(handle, mass)).unwrap_or(false)
 }
 
 #[neon::export]
-fn configure_revolute_joint_motor(
+fn set_collider_mass_properties(
+    world_id: f64,
     handle: f64,
-    target_pos: f64,
-    target_vel: f64,
-    stiffness: f64,
-    damping: f64,
+    mass: f64,
+    center_x: f64,
+    center_y: f64,
+    center_z: f64,
+    inertia_x: f64,
+    inertia_y: f64,
+    inertia_z: f64,
+    frame_x: f64,
+    frame_y: f64,
+    frame_z: f64,
+    frame_w: f64,
 ) -> bool {
-    unsafe {
-        if let Some(ref mut world) = WORLD {
-            world.configure_revolute_joint_motor(handle, target_pos, target_vel, stiffness, damping)
-        } else {
-            false
-        }
-    }
+    with_world_mut(world_id, |world| {
+        world.set_collider_mass_properties(
+            handle, mass, center_x, center_y, center_z, inertia_x, inertia_y, inertia_z, frame_x,
+            frame_y, frame_z, frame_w,
+        )
+    })
+    .unwrap_or(false)
 }
 
 #[neon::export]
-fn get_joint_data(handle: f64) -> Vec<f64> {
-    unsafe {
-        if let Some(ref world) = WORLD {
-            let (index, generation) = decode_handle_from_js(handle);
-            let handle = ImpulseJointHandle::from_raw_parts(index, generation);
-            if let Some(data) = world.impulse_joint_set.get(handle) {
-                if let Some(revolute) = data.data.as_revolute() {
-                    let (index1, generation1) = data.body1.into_raw_parts();
-                    let (index2, generation2) = data.body2.into_raw_parts();
-                    let body1_handle = encode_handle_for_js(index1, generation1);
-                    let body2_handle = encode_handle_for_js(index2, generation2);
-                    vec![
-                        0.0,
-                        body1_handle,
-                        body2_handle,
-                        revolute.local_anchor1().x,
-                        revolute.local_anchor1().y,
-                        revolute.local_anchor1().z,
-                        revolute.local_anchor2().x,
-                        revolute.local_anchor2().y,
-                        revolute.local_anchor2().z,
-                        revolute.data.local_axis1().x,
-                        revolute.data.local_axis1().y,
-                        revolute.data.local_axis1().z,
-                    ]
-                } else if let Some(fixed) = data.data.as_fixed() {
-                    let (index1, generation1) = data.body1.into_raw_parts();
-                    let (index2, generation2) = data.body2.into_raw_parts();
-                    let body1_handle = encode_handle_for_js(index1, generation1);
-                    let body2_handle = encode_handle_for_js(index2, generation2);
-                    vec![
-                        1.0,
-                        body1_handle,
-                        body2_handle,
-                        fixed.local_anchor1().x,
-                        fixed.local_anchor1().y,
-                        fixed.local_anchor1().z,
-                        fixed.local_anchor2().x,
-                        fixed.local_anchor2().y,
-                        fixed.local_anchor2().z,
-                        fixed.local_frame1().rotation.i,
-                        fixed.local_frame1().rotation.j,
-                        fixed.local_frame1().rotation.k,
-                        fixed.local_frame1().rotation.w,
-                        fixed.local_frame2().rotation.i,
-                        fixed.local_frame2().rotation.j,
-                        fixed.local_frame2().rotation.k,
-                        fixed.local_frame2().rotation.w,
-                    ]
-                } else {
-                    vec![]
-                }
-            } else {
-                vec![]
-            }
+fn set_body_additional_mass(world_id: f64, handle: f64, mass: f64, wake_up: bool) -> bool {
+    with_world_mut(world_id, |world| {
+        world.set_body_additional_mass(handle, mass, wake_up)
+    })
+    .unwrap_or(false)
+}
+
+#[neon::export]
+fn get_body_center_of_mass(world_id: f64, handle: f64) -> Vec<f64> {
+    with_world(world_id, |world| {
+        if let Some((x, y, z)) = world.get_body_center_of_mass(handle) {
+            vec![x, y, z]
         } else {
             vec![]
         }
-    }
+    })
+    .unwrap_or_default()
 }
 
 #[neon::export]
-fn intersection_pairs_with(handle: f64) -> Vec<f64> {
-    unsafe {
-        if let Some(ref world) = WORLD {
-            let (index, generation) = decode_handle_from_js(handle);
-            let handle = ColliderHandle::from_raw_parts(index, generation);
-            let mut result = Vec::new();
-            for contact_pair in world.narrow_phase.contact_pairs() {
-                let handle1 = contact_pair.collider1;
-                let handle2 = contact_pair.collider2;
-                if handle1 == handle || handle2 == handle {
-                    let other_handle = if handle1 == handle { handle2 } else { handle1 };
-                    let (index, generation) = other_handle.into_raw_parts();
-                    result.push(encode_handle_for_js(index, generation));
-                }
-            }
-            for intersection_pair in world.narrow_phase.intersection_pairs() {
-                let handle1 = intersection_pair.0;
-                let handle2 = intersection_pair.1;
-                if handle1 == handle || handle2 == handle {
-                    let other_handle = if handle1 == handle { handle2 } else { handle1 };
-                    let (index, generation) = other_handle.into_raw_parts();
-                    result.push(encode_handle_for_js(index, generation));
-                }
-            }
-            result
+fn get_body_local_center_of_mass(world_id: f64, handle: f64) -> Vec<f64> {
+    with_world(world_id, |world| {
+        if let Some((x, y, z)) = world.get_body_local_center_of_mass(handle) {
+            vec![x, y, z]
         } else {
             vec![]
         }
-    }
+    })
+    .unwrap_or_default()
 }
 
 #[neon::export]
-fn set_body_translation(handle: f64, x: f64, y: f64, z: f64, wake_up: bool) -> bool {
-    unsafe {
-        if let Some(ref mut world) = WORLD {
-            world.set_body_translation(handle, x, y, z, wake_up)
+fn get_body_angular_inertia(world_id: f64, handle: f64) -> Vec<f64> {
+    with_world(world_id, |world| {
+        if let Some(inertia) = world.get_body_angular_inertia(handle) {
+            inertia.to_vec()
         } else {
-            false
+            vec![]
         }
-    }
+    })
+    .unwrap_or_default()
 }
 
 #[neon::export]
-fn set_body_next_kinematic_translation(handle: f64, x: f64, y: f64, z: f64) -> bool {
-    unsafe {
-        if let Some(ref mut world) = WORLD {
-            world.set_body_next_kinematic_translation(handle, x, y, z)
-        } else {
-            false
-        }
-    }
+fn set_collider_density(world_id: f64, handle: f64, density: f64) -> bool {
+    with_world_mut(world_id, |world| {
+        world.set_collider_density(handle, density)
+    })
+    .unwrap_or(false)
 }
 
 #[neon::export]
-fn set_body_rotation(handle: f64, x: f64, y: f64, z: f64, w: f64, wake_up: bool) -> bool {
-    unsafe {
-        if let Some(ref mut world) = WORLD {
-            world.set_body_rotation(handle, x, y, z, w, wake_up)
-        } else {
-            false
-        }
-    }
+fn set_collider_friction(world_id: f64, handle: f64, friction: f64) -> bool {
+    with_world_mut(world_id, |world| {
+        world.set_collider_friction(handle, friction)
+    })
+    .unwrap_or(false)
 }
 
 #[neon::export]
-fn set_body_next_kinematic_rotation(handle: f64, x: f64, y: f64, z: f64, w: f64) -> bool {
-    unsafe {
-        if let Some(ref mut world) = WORLD {
-            world.set_body_next_kinematic_rotation(handle, x, y, z, w)
-        } else {
-            false
-        }
-    }
+fn set_collider_restitution(world_id: f64, handle: f64, restitution: f64) -> bool {
+    with_world_mut(world_id, |world| {
+        world.set_collider_restitution(handle, restitution)
+    })
+    .unwrap_or(false)
 }
 
 #[neon::export]
-fn set_body_velocity(handle: f64, vx: f64, vy: f64, vz: f64, wake_up: bool) -> bool {
-    unsafe {
-        if let Some(ref mut world) = WORLD {
-            world.set_body_velocity(handle, vx, vy, vz, wake_up)
-        } else {
-            false
-        }
-    }
+fn set_collider_collision_groups(
+    world_id: f64,
+    handle: f64,
+    memberships: f64,
+    filter: f64,
+) -> bool {
+    with_world_mut(world_id, |world| {
+        world.set_collider_collision_groups(handle, memberships as u32, filter as u32)
+    })
+    .unwrap_or(false)
 }
 
 #[neon::export]
-fn set_body_angular_velocity(handle: f64, wx: f64, wy: f64, wz: f64, wake_up: bool) -> bool {
-    unsafe {
-        if let Some(ref mut world) = WORLD {
-            world.set_body_angular_velocity(handle, wx, wy, wz, wake_up)
-        } else {
-            false
-        }
-    }
+fn set_collider_solver_groups(world_id: f64, handle: f64, memberships: f64, filter: f64) -> bool {
+    with_world_mut(world_id, |world| {
+        world.set_collider_solver_groups(handle, memberships as u32, filter as u32)
+    })
+    .unwrap_or(false)
 }
 
 #[neon::export]
-fn apply_impulse(handle: f64, x: f64, y: f64, z: f64, wake_up: bool) -> bool {
-    unsafe {
-        if let Some(ref mut world) = WORLD {
-            world.apply_impulse(handle, x, y, z, wake_up)
+fn get_collider_collision_groups(world_id: f64, handle: f64) -> Vec<f64> {
+    with_world(world_id, |world| {
+        if let Some((memberships, filter)) = world.get_collider_collision_groups(handle) {
+            vec![memberships as f64, filter as f64]
         } else {
-            false
+            vec![]
         }
-    }
+    })
+    .unwrap_or_default()
 }
 
 #[neon::export]
-fn get_body_translation(handle: f64) -> Vec<f64> {
-    unsafe {
-        if let Some(ref world) = WORLD {
-            if let Some((x, y, z)) = world.get_body_translation(handle) {
-                vec![x, y, z]
-            } else {
-                vec![]
-            }
+fn get_collider_solver_groups(world_id: f64, handle: f64) -> Vec<f64> {
+    with_world(world_id, |world| {
+        if let Some((memberships, filter)) = world.get_collider_solver_groups(handle) {
+            vec![memberships as f64, filter as f64]
         } else {
             vec![]
         }
-    }
+    })
+    .unwrap_or_default()
 }
 
 #[neon::export]
-fn get_body_translations() -> Vec<f64> {
-    unsafe {
-        if let Some(ref world) = WORLD {
-            world.get_body_translations()
-        } else {
-            Vec::new()
-        }
-    }
+fn set_collider_active_events(world_id: f64, handle: f64, active_events: f64) -> bool {
+    with_world_mut(world_id, |world| {
+        world.set_collider_active_events(handle, active_events as u32)
+    })
+    .unwrap_or(false)
 }
 
 #[neon::export]
-fn get_body_rotation(handle: f64) -> Vec<f64> {
-    unsafe {
-        if let Some(ref world) = WORLD {
-            if let Some((x, y, z, w)) = world.get_body_rotation(handle) {
-                vec![x, y, z, w]
-            } else {
-                vec![]
-            }
-        } else {
-            vec![]
-        }
-    }
+fn set_collider_contact_force_event_threshold(world_id: f64, handle: f64, threshold: f64) -> bool {
+    with_world_mut(world_id, |world| {
+        world.set_collider_contact_force_event_threshold(handle, threshold)
+    })
+    .unwrap_or(false)
 }
 
 #[neon::export]
-fn get_body_rotations() -> Vec<f64> {
-    unsafe {
-        if let Some(ref world) = WORLD {
-            world.get_body_rotations()
-        } else {
-            Vec::new()
-        }
-    }
+fn set_collider_active_hooks(world_id: f64, handle: f64, active_hooks: f64) -> bool {
+    with_world_mut(world_id, |world| {
+        world.set_collider_active_hooks(handle, active_hooks as u32)
+    })
+    .unwrap_or(false)
 }
 
 #[neon::export]
-fn get_body_velocity(handle: f64) -> Vec<f64> {
-    unsafe {
-        if let Some(ref world) = WORLD {
-            if let Some((vx, vy, vz)) = world.get_body_velocity(handle) {
-                vec![vx, vy, vz]
-            } else {
-                vec![]
-            }
-        } else {
-            vec![]
-        }
-    }
+fn set_collider_pair_ignored(world_id: f64, handle1: f64, handle2: f64, ignored: bool) -> bool {
+    with_world_mut(world_id, |world| {
+        world.set_collider_pair_ignored(handle1, handle2, ignored);
+        true
+    })
+    .unwrap_or(false)
 }
 
 #[neon::export]
-fn get_body_angular_velocity(handle: f64) -> Vec<f64> {
-    unsafe {
-        if let Some(ref world) = WORLD {
-            if let Some((wx, wy, wz)) = world.get_body_angular_velocity(handle) {
-                vec![wx, wy, wz]
-            } else {
-                vec![]
-            }
-        } else {
-            vec![]
-        }
-    }
+fn set_collider_one_way_direction(
+    world_id: f64,
+    handle: f64,
+    x: f64,
+    y: f64,
+    z: f64,
+    allowed_angle: f64,
+) -> bool {
+    with_world_mut(world_id, |world| {
+        world.set_collider_one_way_direction(handle, x, y, z, allowed_angle)
+    })
+    .unwrap_or(false)
 }
 
 #[neon::export]
-fn is_body_enabled(handle: f64) -> bool {
-    unsafe {
-        if let Some(ref world) = WORLD {
-            world.is_body_enabled(handle)
-        } else {
-            false
-        }
-    }
+fn remove_collider_one_way_direction(world_id: f64, handle: f64) -> bool {
+    with_world_mut(world_id, |world| {
+        world.remove_collider_one_way_direction(handle)
+    })
+    .unwrap_or(false)
 }
 
 #[neon::export]
-fn set_body_enabled(handle: f64, enabled: bool) -> bool {
-    unsafe {
-        if let Some(ref mut world) = WORLD {
-            world.set_body_enabled(handle, enabled)
-        } else {
-            false
-        }
-    }
+fn set_collider_surface_velocity(world_id: f64, handle: f64, vx: f64, vy: f64, vz: f64) -> bool {
+    with_world_mut(world_id, |world| {
+        world.set_collider_surface_velocity(handle, vx, vy, vz)
+    })
+    .unwrap_or(false)
 }
 
 #[neon::export]
-fn set_body_enabled_translations(
+fn set_collider_surface_angular_velocity(
+    world_id: f64,
     handle: f64,
-    enabled_x: bool,
-    enabled_y: bool,
-    enabled_z: bool,
-    wake_up: bool,
+    wx: f64,
+    wy: f64,
+    wz: f64,
 ) -> bool {
-    unsafe {
-        if let Some(ref mut world) = WORLD {
-            world.set_body_enabled_translations(handle, enabled_x, enabled_y, enabled_z, wake_up)
-        } else {
-            false
-        }
-    }
+    with_world_mut(world_id, |world| {
+        world.set_collider_surface_angular_velocity(handle, wx, wy, wz)
+    })
+    .unwrap_or(false)
 }
 
 #[neon::export]
-fn set_body_enabled_rotations(
-    handle: f64,
-    enabled_x: bool,
-    enabled_y: bool,
-    enabled_z: bool,
-    wake_up: bool,
-) -> bool {
-    unsafe {
-        if let Some(ref mut world) = WORLD {
-            world.set_body_enabled_rotations(handle, enabled_x, enabled_y, enabled_z, wake_up)
-        } else {
-            false
-        }
-    }
+fn remove_collider_surface_velocity(world_id: f64, handle: f64) -> bool {
+    with_world_mut(world_id, |world| {
+        world.remove_collider_surface_velocity(handle)
+    })
+    .unwrap_or(false)
+}
+
+#[neon::export]
+fn set_collider_contact_skin(world_id: f64, handle: f64, contact_skin: f64) -> bool {
+    with_world_mut(world_id, |world| {
+        world.set_collider_contact_skin(handle, contact_skin)
+    })
+    .unwrap_or(false)
+}
+
+#[neon::export]
+fn set_collider_enabled(world_id: f64, handle: f64, enabled: bool) -> bool {
+    with_world_mut(world_id, |world| {
+        world.set_collider_enabled(handle, enabled)
+    })
+    .unwrap_or(false)
 }
 
 #[neon::export]
-fn body_sleep(handle: f64) -> bool {
-    unsafe {
-        if let Some(ref mut world) = WORLD {
-            world.body_sleep(handle)
-        } else {
-            false
-        }
-    }
+fn is_collider_enabled(world_id: f64, handle: f64) -> bool {
+    with_world(world_id, |world| world.is_collider_enabled(handle)).unwrap_or(false)
 }
 
 #[neon::export]
-fn is_body_sleeping(handle: f64) -> bool {
-    unsafe {
-        if let Some(ref world) = WORLD {
-            world.is_body_sleeping(handle)
+fn get_collider_shape_type(world_id: f64, handle: f64) -> f64 {
+    with_world(world_id, |world| {
+        if let Some(shape_type) = world.get_collider_shape_type(handle) {
+            shape_type as f64
         } else {
-            false
+            -1.0
         }
-    }
+    })
+    .unwrap_or(-1.0)
 }
 
 #[neon::export]
-fn set_body_soft_ccd_prediction(handle: f64, precision: f64) -> bool {
-    unsafe {
-        if let Some(ref mut world) = WORLD {
-            world.set_body_soft_ccd_prediction(handle, precision)
-        } else {
-            false
-        }
-    }
+fn get_collider_parent(world_id: f64, handle: f64) -> Option<f64> {
+    with_world(world_id, |world| world.get_collider_parent(handle)).flatten()
 }
 
 #[neon::export]
-fn set_body_ccd_enabled(handle: f64, enabled: bool) -> bool {
-    unsafe {
-        if let Some(ref mut world) = WORLD {
-            world.set_body_ccd_enabled(handle, enabled)
+fn get_collider_translation(world_id: f64, handle: f64) -> Vec<f64> {
+    with_world(world_id, |world| {
+        if let Some((x, y, z)) = world.get_collider_translation(handle) {
+            vec![x, y, z]
         } else {
-            false
+            vec![]
         }
-    }
+    })
+    .unwrap_or_default()
 }
 
 #[neon::export]
-fn set_body_additional_solver_iterations(handle: f64, iterations: f64) -> bool {
-    unsafe {
-        if let Some(ref mut world) = WORLD {
-            world.set_body_additional_solver_iterations(handle, iterations as usize)
+fn get_collider_rotation(world_id: f64, handle: f64) -> Vec<f64> {
+    with_world(world_id, |world| {
+        if let Some((x, y, z, w)) = world.get_collider_rotation(handle) {
+            vec![x, y, z, w]
         } else {
-            false
+            vec![]
         }
-    }
+    })
+    .unwrap_or_default()
 }
 
 #[neon::export]
-fn get_body_mass(handle: f64) -> f64 {
-    unsafe {
-        if let Some(ref world) = WORLD {
-            if let Some(mass) = world.get_body_mass(handle) {
-                mass
-            } else {
-                -1.0
-            }
+fn get_collider_vertices(world_id: f64, handle: f64) -> Vec<f64> {
+    with_world(world_id, |world| {
+        if let Some(vertices) = world.get_collider_vertices(handle) {
+            vertices.into_iter().collect()
         } else {
-            -1.0
+            vec![]
         }
-    }
+    })
+    .unwrap_or_default()
 }
 
 #[neon::export]
-fn set_body_angular_damping(handle: f64, damping: f64) -> bool {
-    unsafe {
-        if let Some(ref mut world) = WORLD {
-            world.set_body_angular_damping(handle, damping)
+fn get_collider_indices(world_id: f64, handle: f64) -> Vec<f64> {
+    with_world(world_id, |world| {
+        if let Some(indices) = world.get_collider_indices(handle) {
+            indices.into_iter().map(|i| i as f64).collect()
         } else {
-            false
+            vec![]
         }
-    }
+    })
+    .unwrap_or_default()
 }
 
 #[neon::export]
-fn set_body_linear_damping(handle: f64, damping: f64) -> bool {
-    unsafe {
-        if let Some(ref mut world) = WORLD {
-            world.set_body_linear_damping(handle, damping)
+fn get_collider_half_extents(world_id: f64, handle: f64) -> Vec<f64> {
+    with_world(world_id, |world| {
+        if let Some((half_x, half_y, half_z)) = world.get_collider_half_extents(handle) {
+            vec![half_x, half_y, half_z]
         } else {
-            false
+            vec![]
         }
-    }
+    })
+    .unwrap_or_default()
 }
 
 #[neon::export]
-fn get_body_num_colliders(handle: f64) -> f64 {
-    unsafe {
-        if let Some(ref world) = WORLD {
-            world.get_body_num_colliders(handle) as f64
+fn get_collider_half_height(world_id: f64, handle: f64) -> f64 {
+    with_world(world_id, |world| {
+        if let Some(half_height) = world.get_collider_half_height(handle) {
+            half_height
         } else {
             -1.0
         }
-    }
+    })
+    .unwrap_or(-1.0)
 }
 
 #[neon::export]
-fn get_body_collider(handle: f64, collider_index: f64) -> f64 {
-    unsafe {
-        if let Some(ref world) = WORLD {
-            if let Some(handle) = world.get_body_collider(handle, collider_index as usize) {
-                handle
-            } else {
-                -1.0
-            }
+fn get_collider_radius(world_id: f64, handle: f64) -> f64 {
+    with_world(world_id, |world| {
+        if let Some(radius) = world.get_collider_radius(handle) {
+            radius
         } else {
             -1.0
         }
-    }
+    })
+    .unwrap_or(-1.0)
 }
 
 #[neon::export]
-fn set_collider_density(handle: f64, density: f64) -> bool {
-    unsafe {
-        if let Some(ref mut world) = WORLD {
-            world.set_collider_density(handle, density)
+fn get_collider_border_radius(world_id: f64, handle: f64) -> f64 {
+    with_world(world_id, |world| {
+        if let Some(border_radius) = world.get_collider_border_radius(handle) {
+            border_radius
         } else {
-            false
+            -1.0
         }
-    }
+    })
+    .unwrap_or(-1.0)
 }
 
 #[neon::export]
-fn set_collider_friction(handle: f64, friction: f64) -> bool {
-    unsafe {
-        if let Some(ref mut world) = WORLD {
-            world.set_collider_friction(handle, friction)
+fn get_collider_heightfield(world_id: f64, handle: f64) -> Vec<f64> {
+    with_world(world_id, |world| {
+        if let Some(data) = world.get_collider_heightfield(handle) {
+            data
         } else {
-            false
+            vec![]
         }
-    }
+    })
+    .unwrap_or_default()
 }
 
 #[neon::export]
-fn set_collider_restitution(handle: f64, restitution: f64) -> bool {
-    unsafe {
-        if let Some(ref mut world) = WORLD {
-            world.set_collider_restitution(handle, restitution)
+fn get_collider_compound_shapes(world_id: f64, handle: f64) -> Vec<f64> {
+    with_world(world_id, |world| {
+        if let Some(data) = world.get_collider_compound_shapes(handle) {
+            data
         } else {
-            false
+            vec![]
         }
-    }
+    })
+    .unwrap_or_default()
 }
 
 #[neon::export]
-fn set_collider_collision_groups(handle: f64, groups: f64) -> bool {
-    unsafe {
-        if let Some(ref mut world) = WORLD {
-            world.set_collider_collision_groups(handle, groups as u32)
+fn get_collider_flags(world_id: f64, handle: f64) -> u32 {
+    with_world(world_id, |world| {
+        if let Some(flags) = world.get_collider_flags(handle) {
+            flags
         } else {
-            false
+            0
         }
-    }
+    })
+    .unwrap_or(0)
 }
 
 #[neon::export]
-fn set_collider_contact_skin(handle: f64, contact_skin: f64) -> bool {
-    unsafe {
-        if let Some(ref mut world) = WORLD {
-            world.set_collider_contact_skin(handle, contact_skin)
-        } else {
-            false
-        }
-    }
+fn step_simulation(world_id: f64, dt: f64) {
+    with_world_mut(world_id, |world| {
+        world.step(dt);
+    });
 }
 
-#[neon::export]
-fn set_collider_enabled(handle: f64, enabled: bool) -> bool {
-    unsafe {
-        if let Some(ref mut world) = WORLD {
-            world.set_collider_enabled(handle, enabled)
-        } else {
-            false
-        }
+fn interaction_groups(memberships: Option<f64>, filter: Option<f64>) -> Option<InteractionGroups> {
+    if memberships.is_none() && filter.is_none() {
+        return None;
     }
+    Some(InteractionGroups::new(
+        memberships.map_or(Group::ALL, |bits| Group::from_bits_retain(bits as u32)),
+        filter.map_or(Group::ALL, |bits| Group::from_bits_retain(bits as u32)),
+    ))
 }
 
-#[neon::export]
-fn is_collider_enabled(handle: f64) -> bool {
-    unsafe {
-        if let Some(ref world) = WORLD {
-            world.is_collider_enabled(handle)
-        } else {
-            false
-        }
+fn query_filter(
+    groups: Option<InteractionGroups>,
+    exclude_body: Option<f64>,
+) -> QueryFilter<'static> {
+    let mut filter = QueryFilter::default();
+    if let Some(groups) = groups {
+        filter = filter.groups(groups);
+    }
+    if let Some(exclude_body) = exclude_body {
+        let (index, generation) = decode_handle_from_js(exclude_body);
+        filter = filter.exclude_rigid_body(RigidBodyHandle::from_raw_parts(index, generation));
     }
+    filter
 }
 
-#[neon::export]
-fn get_collider_shape_type(handle: f64) -> u32 {
-    unsafe {
-        if let Some(ref world) = WORLD {
-            if let Some(shape_type) = world.get_collider_shape_type(handle) {
-                shape_type as u32
-            } else {
-                0
+fn rotation_from_json(rotation: [Real; 4]) -> UnitQuaternion<Real> {
+    let [qx, qy, qz, qw] = rotation;
+    UnitQuaternion::from_quaternion(Quaternion::new(qw, qx, qy, qz))
+}
+
+fn rotation_to_json(rotation: &UnitQuaternion<Real>) -> [Real; 4] {
+    [rotation.i, rotation.j, rotation.k, rotation.w]
+}
+
+fn isometry_from_json(translation: [Real; 3], rotation: [Real; 4]) -> Isometry<Real> {
+    let [x, y, z] = translation;
+    Isometry::from_parts(Translation3::new(x, y, z), rotation_from_json(rotation))
+}
+
+fn isometry_to_json(position: &Isometry<Real>) -> ([Real; 3], [Real; 4]) {
+    (
+        position.translation.vector.into(),
+        rotation_to_json(&position.rotation),
+    )
+}
+
+fn body_to_json(id: u64, body: &RigidBody) -> BodyJson {
+    let (translation, rotation) = isometry_to_json(body.position());
+    let (additional_mass, additional_mass_properties) =
+        match body.mass_properties().additional_local_mprops.as_deref() {
+            Some(RigidBodyAdditionalMassProps::Mass(mass)) => (Some(*mass), None),
+            Some(RigidBodyAdditionalMassProps::MassProps(mass_properties)) => {
+                (None, Some(MassPropertiesJson::of(mass_properties)))
             }
-        } else {
-            0
-        }
+            None => (None, None),
+        };
+    BodyJson {
+        id,
+        body_type: match body.body_type() {
+            RigidBodyType::Dynamic => BodyTypeJson::Dynamic,
+            RigidBodyType::Fixed => BodyTypeJson::Fixed,
+            RigidBodyType::KinematicPositionBased => BodyTypeJson::KinematicPosition,
+            RigidBodyType::KinematicVelocityBased => BodyTypeJson::KinematicVelocity,
+        },
+        translation,
+        rotation,
+        linvel: (*body.linvel()).into(),
+        angvel: (*body.angvel()).into(),
+        linear_damping: body.linear_damping(),
+        angular_damping: body.angular_damping(),
+        gravity_scale: body.gravity_scale(),
+        ccd: body.is_ccd_enabled(),
+        soft_ccd_prediction: body.soft_ccd_prediction(),
+        additional_solver_iterations: body.additional_solver_iterations(),
+        additional_mass,
+        additional_mass_properties,
+        sleeping: body.is_sleeping(),
+        can_sleep: body.activation().normalized_linear_threshold >= 0.0,
+        enabled: body.is_enabled(),
+        locked_axes: body.locked_axes().bits(),
+        dominance_group: body.dominance_group(),
+        user_data: body.user_data,
     }
 }
 
-#[neon::export]
-fn get_collider_parent(handle: f64) -> f64 {
-    unsafe {
-        if let Some(ref world) = WORLD {
-            if let Some(parent_handle) = world.get_collider_parent(handle) {
-                parent_handle
-            } else {
-                -1.0
-            }
-        } else {
-            -1.0
+fn body_from_json(body: &BodyJson) -> Result<RigidBody, String> {
+    if body.user_data > MAX_JS_USER_DATA {
+        return Err("user_data is above 2^53 - 1".into());
+    }
+    let body_type = match body.body_type {
+        BodyTypeJson::Dynamic => RigidBodyType::Dynamic,
+        BodyTypeJson::Fixed => RigidBodyType::Fixed,
+        BodyTypeJson::KinematicPosition => RigidBodyType::KinematicPositionBased,
+        BodyTypeJson::KinematicVelocity => RigidBodyType::KinematicVelocityBased,
+    };
+    let builder = RigidBodyBuilder::new(body_type)
+        .position(isometry_from_json(body.translation, body.rotation))
+        .linvel(body.linvel.into())
+        .angvel(body.angvel.into())
+        .linear_damping(body.linear_damping)
+        .angular_damping(body.angular_damping)
+        .gravity_scale(body.gravity_scale)
+        .ccd_enabled(body.ccd)
+        .soft_ccd_prediction(body.soft_ccd_prediction)
+        .additional_solver_iterations(body.additional_solver_iterations)
+        .sleeping(body.sleeping)
+        .can_sleep(body.can_sleep)
+        .enabled(body.enabled)
+        .locked_axes(LockedAxes::from_bits_truncate(body.locked_axes))
+        .dominance_group(body.dominance_group)
+        .user_data(body.user_data);
+    let builder = match (body.additional_mass, &body.additional_mass_properties) {
+        (None, None) => builder,
+        (Some(mass), None) => builder.additional_mass(mass),
+        (None, Some(mass_properties)) => {
+            builder.additional_mass_properties(mass_properties.mass_properties())
         }
-    }
+        (Some(_), Some(_)) => {
+            return Err(
+                "only one of additional_mass and additional_mass_properties may be set".into(),
+            );
+        }
+    };
+    Ok(builder.build())
 }
 
-#[neon::export]
-fn get_collider_translation(handle: f64) -> Vec<f64> {
-    unsafe {
-        if let Some(ref world) = WORLD {
-            if let Some((x, y, z)) = world.get_collider_translation(handle) {
-                vec![x, y, z]
-            } else {
-                vec![]
-            }
+fn collider_to_json(
+    id: u64,
+    body: Option<u64>,
+    collider: &Collider,
+) -> Result<ColliderJson, String> {
+    let position = if collider.parent().is_some() {
+        collider
+            .position_wrt_parent()
+            .copied()
+            .unwrap_or_else(Isometry::identity)
+    } else {
+        *collider.position()
+    };
+    let (translation, rotation) = isometry_to_json(&position);
+    // Rapier does not say whether a density, a mass or mass properties were given, so export the
+    // simplest of them that reproduces the collider's mass properties exactly.
+    let shape = collider.shape();
+    let (density, mass) = (collider.density(), collider.mass());
+    let mass_properties = collider.mass_properties();
+    let (density, mass, mass_properties) =
+        if ColliderMassProps::Density(density).mass_properties(shape) == mass_properties {
+            (Some(density), None, None)
+        } else if ColliderMassProps::Mass(mass).mass_properties(shape) == mass_properties {
+            (None, Some(mass), None)
         } else {
-            vec![]
+            (None, None, Some(MassPropertiesJson::of(&mass_properties)))
+        };
+    let collision_groups = collider.collision_groups();
+    let solver_groups = collider.solver_groups();
+    Ok(ColliderJson {
+        id,
+        body,
+        shape: shape_to_json(shape)?,
+        translation,
+        rotation,
+        sensor: collider.is_sensor(),
+        friction: collider.friction(),
+        friction_combine_rule: CombineRuleJson::of(collider.friction_combine_rule()),
+        restitution: collider.restitution(),
+        restitution_combine_rule: CombineRuleJson::of(collider.restitution_combine_rule()),
+        density,
+        mass,
+        mass_properties,
+        contact_skin: collider.contact_skin(),
+        collision_groups: [
+            collision_groups.memberships.bits(),
+            collision_groups.filter.bits(),
+        ],
+        solver_groups: [
+            solver_groups.memberships.bits(),
+            solver_groups.filter.bits(),
+        ],
+        active_collision_types: collider.active_collision_types().bits(),
+        active_events: collider.active_events().bits(),
+        contact_force_event_threshold: collider.contact_force_event_threshold(),
+        active_hooks: collider.active_hooks().bits(),
+        one_way: None,
+        surface_velocity: None,
+        enabled: collider.is_enabled(),
+        user_data: collider.user_data,
+    })
+}
+
+fn collider_from_json(collider: &ColliderJson) -> Result<Collider, String> {
+    if collider.user_data > MAX_JS_USER_DATA {
+        return Err("user_data is above 2^53 - 1".into());
+    }
+    let [collision_memberships, collision_filter] = collider.collision_groups;
+    let [solver_memberships, solver_filter] = collider.solver_groups;
+    let builder = ColliderBuilder::new(shape_from_json(&collider.shape)?)
+        .position(isometry_from_json(collider.translation, collider.rotation))
+        .sensor(collider.sensor)
+        .friction(collider.friction)
+        .friction_combine_rule(collider.friction_combine_rule.rule())
+        .restitution(collider.restitution)
+        .restitution_combine_rule(collider.restitution_combine_rule.rule())
+        .contact_skin(collider.contact_skin)
+        .collision_groups(InteractionGroups::new(
+            Group::from_bits_retain(collision_memberships),
+            Group::from_bits_retain(collision_filter),
+        ))
+        .solver_groups(InteractionGroups::new(
+            Group::from_bits_retain(solver_memberships),
+            Group::from_bits_retain(solver_filter),
+        ))
+        .active_collision_types(ActiveCollisionTypes::from_bits_truncate(
+            collider.active_collision_types,
+        ))
+        .active_events(ActiveEvents::from_bits_truncate(collider.active_events))
+        .contact_force_event_threshold(collider.contact_force_event_threshold)
+        .active_hooks(ActiveHooks::from_bits_truncate(collider.active_hooks))
+        .enabled(collider.enabled)
+        .user_data(collider.user_data);
+    let builder = match (collider.density, collider.mass, &collider.mass_properties) {
+        (density, None, None) => builder.density(density.unwrap_or(1.0)),
+        (None, Some(mass), None) => builder.mass(mass),
+        (None, None, Some(mass_properties)) => {
+            builder.mass_properties(mass_properties.mass_properties())
         }
-    }
+        _ => return Err("only one of density, mass and mass_properties may be set".into()),
+    };
+    Ok(builder.build())
 }
 
-#[neon::export]
-fn get_collider_rotation(handle: f64) -> Vec<f64> {
-    unsafe {
-        if let Some(ref world) = WORLD {
-            if let Some((x, y, z, w)) = world.get_collider_rotation(handle) {
-                vec![x, y, z, w]
-            } else {
-                vec![]
-            }
-        } else {
-            vec![]
+fn shape_to_json(shape: &dyn Shape) -> Result<ShapeJson, String> {
+    let shape = if let Some(ball) = shape.as_ball() {
+        ShapeJson::Ball {
+            radius: ball.radius,
         }
-    }
+    } else if let Some(cuboid) = shape.as_cuboid() {
+        ShapeJson::Cuboid {
+            half_extents: cuboid.half_extents.into(),
+        }
+    } else if let Some(capsule) = shape.as_capsule() {
+        ShapeJson::Capsule {
+            half_height: capsule.half_height(),
+            radius: capsule.radius,
+        }
+    } else if let Some(cylinder) = shape.as_cylinder() {
+        ShapeJson::Cylinder {
+            half_height: cylinder.half_height,
+            radius: cylinder.radius,
+        }
+    } else if let Some(cone) = shape.as_cone() {
+        ShapeJson::Cone {
+            half_height: cone.half_height,
+            radius: cone.radius,
+        }
+    } else if let Some(round_cylinder) = shape.as_round_cylinder() {
+        ShapeJson::RoundCylinder {
+            half_height: round_cylinder.inner_shape.half_height,
+            radius: round_cylinder.inner_shape.radius,
+            border_radius: round_cylinder.border_radius,
+        }
+    } else if let Some(trimesh) = shape.as_trimesh() {
+        ShapeJson::Trimesh {
+            vertices: trimesh
+                .vertices()
+                .iter()
+                .map(|point| point.coords.into())
+                .collect(),
+            indices: trimesh.indices().to_vec(),
+            flags: trimesh.flags().bits(),
+        }
+    } else if let Some(convex) = shape.as_convex_polyhedron() {
+        ShapeJson::ConvexHull {
+            points: convex
+                .points()
+                .iter()
+                .map(|point| point.coords.into())
+                .collect(),
+        }
+    } else if let Some(heightfield) = shape.as_heightfield() {
+        let heights = heightfield.heights();
+        ShapeJson::Heightfield {
+            heights: (0..heights.nrows())
+                .map(|row| heights.row(row).iter().copied().collect())
+                .collect(),
+            scale: (*heightfield.scale()).into(),
+        }
+    } else if let Some(compound) = shape.as_compound() {
+        let shapes = compound
+            .shapes()
+            .iter()
+            .map(|(position, shape)| {
+                let (translation, rotation) = isometry_to_json(position);
+                Ok(CompoundPartJson {
+                    translation,
+                    rotation,
+                    shape: shape_to_json(shape.as_ref())?,
+                })
+            })
+            .collect::<Result<Vec<_>, String>>()?;
+        ShapeJson::Compound { shapes }
+    } else {
+        return Err(format!("unsupported shape type {:?}", shape.shape_type()));
+    };
+    Ok(shape)
 }
 
-#[neon::export]
-fn get_collider_vertices(handle: f64) -> Vec<f64> {
-    unsafe {
-        if let Some(ref world) = WORLD {
-            if let Some(vertices) = world.get_collider_vertices(handle) {
-                vertices.into_iter().collect()
-            } else {
-                vec![]
+fn shape_from_json(shape: &ShapeJson) -> Result<SharedShape, String> {
+    let shape = match shape {
+        ShapeJson::Ball { radius } => SharedShape::ball(*radius),
+        ShapeJson::Cuboid { half_extents } => {
+            SharedShape::cuboid(half_extents[0], half_extents[1], half_extents[2])
+        }
+        ShapeJson::Capsule {
+            half_height,
+            radius,
+        } => SharedShape::capsule_y(*half_height, *radius),
+        ShapeJson::Cylinder {
+            half_height,
+            radius,
+        } => SharedShape::cylinder(*half_height, *radius),
+        ShapeJson::Cone {
+            half_height,
+            radius,
+        } => SharedShape::cone(*half_height, *radius),
+        ShapeJson::RoundCylinder {
+            half_height,
+            radius,
+            border_radius,
+        } => SharedShape::round_cylinder(*half_height, *radius, *border_radius),
+        ShapeJson::Trimesh {
+            vertices,
+            indices,
+            flags,
+        } => SharedShape::trimesh_with_flags(
+            vertices
+                .iter()
+                .map(|&[x, y, z]| Point::new(x, y, z))
+                .collect(),
+            indices.clone(),
+            TriMeshFlags::from_bits_truncate(*flags),
+        )
+        .map_err(|error| format!("invalid trimesh: {error:?}"))?,
+        ShapeJson::ConvexHull { points } => {
+            let points: Vec<Point<Real>> = points
+                .iter()
+                .map(|&[x, y, z]| Point::new(x, y, z))
+                .collect();
+            SharedShape::convex_hull(&points).ok_or("invalid convex hull")?
+        }
+        ShapeJson::Heightfield { heights, scale } => {
+            let nrows = heights.len();
+            let ncols = heights.first().map_or(0, Vec::len);
+            if nrows < 2 || ncols < 2 || heights.iter().any(|row| row.len() != ncols) {
+                return Err("heightfield needs at least 2x2 heights in equal rows".into());
             }
-        } else {
-            vec![]
+            SharedShape::heightfield(
+                DMatrix::from_fn(nrows, ncols, |row, col| heights[row][col]),
+                (*scale).into(),
+            )
         }
-    }
+        ShapeJson::Compound { shapes } => SharedShape::compound(
+            shapes
+                .iter()
+                .map(|part| {
+                    let position = isometry_from_json(part.translation, part.rotation);
+                    Ok((position, shape_from_json(&part.shape)?))
+                })
+                .collect::<Result<Vec<_>, String>>()?,
+        ),
+    };
+    Ok(shape)
 }
 
-#[neon::export]
-fn get_collider_indices(handle: f64) -> Vec<f64> {
-    unsafe {
-        if let Some(ref world) = WORLD {
-            if let Some(indices) = world.get_collider_indices(handle) {
-                indices.into_iter().map(|i| i as f64).collect()
-            } else {
-                vec![]
+fn joint_axes_to_json(mask: JointAxesMask) -> Vec<JointAxisJson> {
+    JOINT_AXES
+        .into_iter()
+        .filter(|axis| mask.contains(axis.mask()))
+        .collect()
+}
+
+fn joint_axes_from_json(axes: &[JointAxisJson]) -> JointAxesMask {
+    axes.iter()
+        .fold(JointAxesMask::empty(), |mask, axis| mask | axis.mask())
+}
+
+fn joint_to_json(id: u64, body1: u64, body2: u64, joint: &GenericJoint) -> JointJson {
+    let (translation1, rotation1) = isometry_to_json(&joint.local_frame1);
+    let (translation2, rotation2) = isometry_to_json(&joint.local_frame2);
+    let limits = joint_axes_to_json(joint.limit_axes)
+        .into_iter()
+        .map(|axis| {
+            let limits = &joint.limits[axis as usize];
+            JointLimitJson {
+                axis,
+                min: limits.min,
+                max: limits.max,
             }
-        } else {
-            vec![]
-        }
+        })
+        .collect();
+    let motors = joint_axes_to_json(joint.motor_axes)
+        .into_iter()
+        .map(|axis| {
+            let motor = &joint.motors[axis as usize];
+            JointMotorJson {
+                axis,
+                target_pos: motor.target_pos,
+                target_vel: motor.target_vel,
+                stiffness: motor.stiffness,
+                damping: motor.damping,
+                max_force: motor.max_force,
+                model: match motor.model {
+                    MotorModel::AccelerationBased => MotorModelJson::AccelerationBased,
+                    MotorModel::ForceBased => MotorModelJson::ForceBased,
+                },
+            }
+        })
+        .collect();
+    JointJson {
+        id,
+        body1,
+        body2,
+        frame1: FrameJson {
+            translation: translation1,
+            rotation: rotation1,
+        },
+        frame2: FrameJson {
+            translation: translation2,
+            rotation: rotation2,
+        },
+        locked_axes: joint_axes_to_json(joint.locked_axes),
+        coupled_axes: joint_axes_to_json(joint.coupled_axes),
+        limits,
+        motors,
+        contacts_enabled: joint.contacts_enabled,
+        break_threshold: None,
     }
 }
 
-#[neon::export]
-fn get_collider_half_extents(handle: f64) -> Vec<f64> {
-    unsafe {
-        if let Some(ref world) = WORLD {
-            if let Some((half_x, half_y, half_z)) = world.get_collider_half_extents(handle) {
-                vec![half_x, half_y, half_z]
-            } else {
-                vec![]
-            }
-        } else {
-            vec![]
-        }
+fn joint_from_json(joint: &JointJson) -> GenericJoint {
+    let mut builder = GenericJointBuilder::new(joint_axes_from_json(&joint.locked_axes))
+        .local_frame1(isometry_from_json(
+            joint.frame1.translation,
+            joint.frame1.rotation,
+        ))
+        .local_frame2(isometry_from_json(
+            joint.frame2.translation,
+            joint.frame2.rotation,
+        ))
+        .coupled_axes(joint_axes_from_json(&joint.coupled_axes))
+        .contacts_enabled(joint.contacts_enabled);
+    for limit in &joint.limits {
+        builder = builder.limits(limit.axis.axis(), [limit.min, limit.max]);
+    }
+    for motor in &joint.motors {
+        let axis = motor.axis.axis();
+        let model = match motor.model {
+            MotorModelJson::AccelerationBased => MotorModel::AccelerationBased,
+            MotorModelJson::ForceBased => MotorModel::ForceBased,
+        };
+        builder = builder
+            .motor(
+                axis,
+                motor.target_pos,
+                motor.target_vel,
+                motor.stiffness,
+                motor.damping,
+            )
+            .motor_max_force(axis, motor.max_force)
+            .motor_model(axis, model);
     }
+    builder.build()
 }
 
-#[neon::export]
-fn get_collider_half_height(handle: f64) -> f64 {
-    unsafe {
-        if let Some(ref world) = WORLD {
-            if let Some(half_height) = world.get_collider_half_height(handle) {
-                half_height
-            } else {
-                -1.0
-            }
-        } else {
-            -1.0
-        }
+fn build_shape(shape_type: u32, params: &[Real]) -> Option<SharedShape> {
+    match (shape_type, params) {
+        (0, [radius, ..]) => Some(SharedShape::ball(*radius)),
+        (1, [half_x, half_y, half_z, ..]) => Some(SharedShape::cuboid(*half_x, *half_y, *half_z)),
+        (2, [half_height, radius, ..]) => Some(SharedShape::capsule_y(*half_height, *radius)),
+        (10, [half_height, radius, ..]) => Some(SharedShape::cylinder(*half_height, *radius)),
+        (11, [half_height, radius, ..]) => Some(SharedShape::cone(*half_height, *radius)),
+        (14, [half_height, radius, border_radius, ..]) => Some(SharedShape::round_cylinder(
+            *half_height,
+            *radius,
+            *border_radius,
+        )),
+        _ => None,
     }
 }
 
-#[neon::export]
-fn get_collider_radius(handle: f64) -> f64 {
-    unsafe {
-        if let Some(ref world) = WORLD {
-            if let Some(radius) = world.get_collider_radius(handle) {
-                radius
-            } else {
-                -1.0
-            }
-        } else {
-            -1.0
-        }
+// Upper bound on the grid cells `find_free_spawn_pose` tests before giving up.
+const MAX_SPAWN_CANDIDATES: usize = 4096;
+fn primitive_shape_params(shape: &dyn Shape) -> Option<(u32, Vec<Real>)> {
+    if let Some(ball) = shape.as_ball() {
+        Some((0, vec![ball.radius]))
+    } else if let Some(cuboid) = shape.as_cuboid() {
+        let half_extents = cuboid.half_extents;
+        Some((1, vec![half_extents.x, half_extents.y, half_extents.z]))
+    } else if let Some(capsule) = shape.as_capsule() {
+        Some((2, vec![capsule.half_height(), capsule.radius]))
+    } else if let Some(cylinder) = shape.as_cylinder() {
+        Some((10, vec![cylinder.half_height, cylinder.radius]))
+    } else if let Some(cone) = shape.as_cone() {
+        Some((11, vec![cone.half_height, cone.radius]))
+    } else if let Some(round_cylinder) = shape.as_round_cylinder() {
+        Some((
+            14,
+            vec![
+                round_cylinder.inner_shape.half_height,
+                round_cylinder.inner_shape.radius,
+                round_cylinder.border_radius,
+            ],
+        ))
+    } else {
+        None
     }
 }
 
-#[neon::export]
-fn get_collider_flags(handle: f64) -> u32 {
-    unsafe {
-        if let Some(ref world) = WORLD {
-            if let Some(flags) = world.get_collider_flags(handle) {
-                flags
-            } else {
-                0
-            }
-        } else {
-            0
+// Each compound entry is [shape_type, param_count, x, y, z, qx, qy, qz, qw, params...].
+fn decode_compound_shapes(data: &[Real]) -> Option<Vec<(Isometry<Real>, SharedShape)>> {
+    let mut shapes = Vec::new();
+    let mut rest = data;
+    while let [shape_type, param_count, x, y, z, qx, qy, qz, qw, tail @ ..] = rest {
+        let param_count = *param_count as usize;
+        if tail.len() < param_count {
+            return None;
         }
+        let (params, tail) = tail.split_at(param_count);
+        let shape = build_shape(*shape_type as u32, params)?;
+        let position = Isometry::from_parts(
+            Translation3::new(*x, *y, *z),
+            UnitQuaternion::from_quaternion(Quaternion::new(*qw, *qx, *qy, *qz)),
+        );
+        shapes.push((position, shape));
+        rest = tail;
+    }
+    if rest.is_empty() && !shapes.is_empty() {
+        Some(shapes)
+    } else {
+        None
     }
 }
 
-#[neon::export]
-fn step_simulation(dt: f64) {
-    unsafe {
-        if let Some(ref mut world) = WORLD {
-            world.step(dt);
-        }
+fn encode_compound_shapes(shapes: &[(Isometry<Real>, SharedShape)]) -> Option<Vec<Real>> {
+    let mut data = Vec::new();
+    for (position, shape) in shapes {
+        let (shape_type, params) = primitive_shape_params(shape.as_ref())?;
+        data.extend([
+            shape_type as Real,
+            params.len() as Real,
+            position.translation.x,
+            position.translation.y,
+            position.translation.z,
+            position.rotation.i,
+            position.rotation.j,
+            position.rotation.k,
+            position.rotation.w,
+        ]);
+        data.extend(params);
+    }
+    Some(data)
+}
+
+fn encode_joint_data(
+    body1: RigidBodyHandle,
+    body2: RigidBodyHandle,
+    data: &GenericJoint,
+) -> Option<Vec<f64>> {
+    let (joint_type, params): (f64, Vec<Real>) = if data.as_revolute().is_some() {
+        let axis = data.local_axis1();
+        (JOINT_TYPE_REVOLUTE, vec![axis.x, axis.y, axis.z])
+    } else if let Some(fixed) = data.as_fixed() {
+        let frame1 = fixed.local_frame1().rotation;
+        let frame2 = fixed.local_frame2().rotation;
+        (
+            JOINT_TYPE_FIXED,
+            vec![
+                frame1.i, frame1.j, frame1.k, frame1.w, frame2.i, frame2.j, frame2.k, frame2.w,
+            ],
+        )
+    } else if data.as_prismatic().is_some() {
+        let axis = data.local_axis1();
+        (JOINT_TYPE_PRISMATIC, vec![axis.x, axis.y, axis.z])
+    } else if data.as_spherical().is_some() {
+        (JOINT_TYPE_SPHERICAL, vec![])
+    } else if is_rope_joint(data) {
+        let limits = data.limits(JointAxis::LinX)?;
+        (JOINT_TYPE_ROPE, vec![limits.max])
+    } else if is_spring_joint(data) {
+        let motor = data.motor(JointAxis::LinX)?;
+        (
+            JOINT_TYPE_SPRING,
+            vec![motor.target_pos, motor.stiffness, motor.damping],
+        )
+    } else {
+        return None;
+    };
+    let (index1, generation1) = body1.into_raw_parts();
+    let (index2, generation2) = body2.into_raw_parts();
+    let anchor1 = data.local_anchor1();
+    let anchor2 = data.local_anchor2();
+    let mut result = vec![
+        joint_type,
+        encode_handle_for_js(index1, generation1),
+        encode_handle_for_js(index2, generation2),
+        anchor1.x,
+        anchor1.y,
+        anchor1.z,
+        anchor2.x,
+        anchor2.y,
+        anchor2.z,
+    ];
+    result.extend(params);
+    Some(result)
+}
+
+fn angular_joint_axis(axis: u32) -> Option<JointAxis> {
+    match axis {
+        0 => Some(JointAxis::AngX),
+        1 => Some(JointAxis::AngY),
+        2 => Some(JointAxis::AngZ),
+        _ => None,
+    }
+}
+
+// Rope and spring joints leave every axis free, so they are told apart by how
+// the first linear axis is constrained: a rope limits it, a spring drives it.
+fn is_rope_joint(joint: &GenericJoint) -> bool {
+    joint.locked_axes.is_empty() && joint.limit_axes.contains(JointAxesMask::LIN_X)
+}
+
+fn is_spring_joint(joint: &GenericJoint) -> bool {
+    joint.locked_axes.is_empty()
+        && !joint.limit_axes.contains(JointAxesMask::LIN_X)
+        && joint.motor_axes.contains(JointAxesMask::LIN_X)
+}
+
+fn spawn_grid_coordinates(min: Real, max: Real, half_extent: Real) -> Vec<Real> {
+    let start = min + half_extent;
+    let end = max - half_extent;
+    if end <= start {
+        return vec![(min + max) / 2.0];
+    }
+    let step = 2.0 * half_extent;
+    let count = (((end - start) / step).floor() as usize + 1).min(MAX_SPAWN_CANDIDATES);
+    (0..count).map(|i| start + i as Real * step).collect()
+}
+
+// User data reaches JS as a plain number, so only integers it can represent exactly are
+// accepted.
+const MAX_JS_USER_DATA: u128 = (1 << 53) - 1;
+
+fn user_data_from_js(user_data: f64) -> Option<u128> {
+    (user_data >= 0.0 && user_data.fract() == 0.0 && user_data <= MAX_JS_USER_DATA as f64)
+        .then_some(user_data as u128)
+}
+
+fn ordered_collider_pair(
+    collider1: ColliderHandle,
+    collider2: ColliderHandle,
+) -> (ColliderHandle, ColliderHandle) {
+    if collider1.into_raw_parts() <= collider2.into_raw_parts() {
+        (collider1, collider2)
+    } else {
+        (collider2, collider1)
     }
 }
 
@@ -2251,3 +7651,669 @@ fn decode_handle_from_js(encoded: f64) -> (u32, u32) {
     let generation = (combined >> 32) as u32;
     (index, generation)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample_payload() -> Vec<u8> {
+        (0..4096u32).map(|i| (i * 7 % 251) as u8).collect()
+    }
+
+    // Rewrites the header of a snapshot and seals it again with a valid checksum.
+    fn resealed(snapshot: &[u8], edit: impl FnOnce(&mut SnapshotHeader)) -> Vec<u8> {
+        let mut header = SnapshotHeader::read(snapshot).unwrap();
+        let payload = &snapshot[SNAPSHOT_HEADER_LEN..];
+        edit(&mut header);
+        header.crc32 = header.checksum(payload);
+        let mut out = Vec::new();
+        header.write(&mut out);
+        out.extend_from_slice(payload);
+        out
+    }
+
+    fn rejection(snapshot: &[u8]) -> SnapshotError {
+        match unwrap_snapshot(snapshot) {
+            Ok(_) => panic!("snapshot was accepted"),
+            Err(error) => error,
+        }
+    }
+
+    fn sample_snapshot() -> Vec<u8> {
+        wrap_snapshot(SNAPSHOT_MAGIC, sample_payload(), SnapshotCompression::None).unwrap()
+    }
+
+    #[test]
+    fn rapier_version_matches_cargo_lock() {
+        // Cargo writes the lockfile before it builds the tests; there is nothing to compare
+        // against in a tree that was never resolved.
+        let Some(lock) = std::path::Path::new(env!("CARGO_MANIFEST_DIR"))
+            .ancestors()
+            .find_map(|dir| std::fs::read_to_string(dir.join("Cargo.lock")).ok())
+        else {
+            return;
+        };
+        let entry = lock
+            .split("[[package]]")
+            .find(|entry| {
+                entry
+                    .lines()
+                    .any(|line| line.trim() == "name = \"rapier3d-f64\"")
+            })
+            .expect("rapier3d-f64 is not in Cargo.lock");
+        let version = entry
+            .lines()
+            .find_map(|line| line.trim().strip_prefix("version = \""))
+            .expect("rapier3d-f64 has no version in Cargo.lock")
+            .trim_end_matches('"');
+        let locked: Vec<u16> = version
+            .split(['.', '-', '+'])
+            .take(3)
+            .map(|part| part.parse().unwrap())
+            .collect();
+        assert_eq!(locked, RAPIER_VERSION);
+    }
+
+    #[test]
+    fn header_round_trip() {
+        let snapshot = sample_snapshot();
+        let header = SnapshotHeader::read(&snapshot).unwrap();
+        assert_eq!(header.format_version, SNAPSHOT_FORMAT_VERSION);
+        assert_eq!(header.precision, SNAPSHOT_PRECISION);
+        assert_eq!(header.rapier_version, RAPIER_VERSION);
+        assert_eq!(header.payload_len, 4096);
+        assert_eq!(header.raw_len, 4096);
+        assert_eq!(snapshot.len(), SNAPSHOT_HEADER_LEN + 4096);
+        assert_eq!(unwrap_snapshot(&snapshot).unwrap(), sample_payload());
+    }
+
+    #[test]
+    fn rejects_truncated_snapshots() {
+        let snapshot = sample_snapshot();
+        assert!(matches!(
+            rejection(&snapshot[..SNAPSHOT_HEADER_LEN - 1]),
+            SnapshotError::Truncated
+        ));
+    }
+
+    #[test]
+    fn rejects_bad_magic() {
+        let mut snapshot = sample_snapshot();
+        snapshot[0] = b'X';
+        assert!(matches!(rejection(&snapshot), SnapshotError::BadMagic));
+    }
+
+    #[test]
+    fn rejects_unsupported_format_version() {
+        let snapshot = resealed(&sample_snapshot(), |header| header.format_version = 2);
+        assert!(matches!(
+            rejection(&snapshot),
+            SnapshotError::UnsupportedFormatVersion(2)
+        ));
+    }
+
+    #[test]
+    fn rejects_length_mismatch() {
+        let mut snapshot = sample_snapshot();
+        snapshot.push(0);
+        assert!(matches!(
+            rejection(&snapshot),
+            SnapshotError::LengthMismatch {
+                expected: 4096,
+                found: 4097
+            }
+        ));
+    }
+
+    #[test]
+    fn checksum_covers_payload_and_header() {
+        let mut snapshot = sample_snapshot();
+        snapshot[SNAPSHOT_HEADER_LEN + 10] ^= 1;
+        assert!(matches!(
+            rejection(&snapshot),
+            SnapshotError::ChecksumMismatch { .. }
+        ));
+
+        let mut snapshot = sample_snapshot();
+        // The patch part of the rapier version is not otherwise validated.
+        snapshot[12] ^= 1;
+        assert!(matches!(
+            rejection(&snapshot),
+            SnapshotError::ChecksumMismatch { .. }
+        ));
+    }
+
+    #[test]
+    fn rejects_precision_mismatch() {
+        let snapshot = resealed(&sample_snapshot(), |header| header.precision = 16);
+        assert!(matches!(
+            rejection(&snapshot),
+            SnapshotError::PrecisionMismatch(16)
+        ));
+    }
+
+    #[test]
+    fn checks_rapier_major_and_minor_version() {
+        let [major, minor, patch] = RAPIER_VERSION;
+        let snapshot = resealed(&sample_snapshot(), |header| {
+            header.rapier_version = [major, minor + 1, patch]
+        });
+        assert!(matches!(
+            rejection(&snapshot),
+            SnapshotError::RapierVersionMismatch(_)
+        ));
+
+        let snapshot = resealed(&sample_snapshot(), |header| {
+            header.rapier_version = [major, minor, patch + 1]
+        });
+        assert!(unwrap_snapshot(&snapshot).is_ok());
+    }
+
+    #[test]
+    fn rejects_unknown_compression() {
+        let snapshot = resealed(&sample_snapshot(), |header| header.compression = 7);
+        assert!(matches!(
+            rejection(&snapshot),
+            SnapshotError::UnknownCompression(7)
+        ));
+    }
+
+    #[test]
+    fn rejects_raw_length_above_limit() {
+        let snapshot = resealed(&sample_snapshot(), |header| {
+            header.raw_len = MAX_SNAPSHOT_RAW_LEN + 1
+        });
+        assert!(matches!(
+            rejection(&snapshot),
+            SnapshotError::RawLengthTooLarge(raw_len) if raw_len == MAX_SNAPSHOT_RAW_LEN + 1
+        ));
+    }
+
+    #[test]
+    fn compression_round_trip() {
+        for compression in [
+            SnapshotCompression::None,
+            SnapshotCompression::Lz4,
+            SnapshotCompression::Zstd,
+        ] {
+            let snapshot = wrap_snapshot(SNAPSHOT_MAGIC, sample_payload(), compression).unwrap();
+            let header = SnapshotHeader::read(&snapshot).unwrap();
+            assert_eq!(header.compression, compression.code());
+            assert_eq!(header.raw_len, 4096);
+            assert_eq!(unwrap_snapshot(&snapshot).unwrap(), sample_payload());
+        }
+    }
+
+    #[test]
+    fn rejects_payload_shorter_than_raw_length() {
+        let snapshot =
+            wrap_snapshot(SNAPSHOT_MAGIC, sample_payload(), SnapshotCompression::Lz4).unwrap();
+        let snapshot = resealed(&snapshot, |header| header.raw_len += 1);
+        assert!(matches!(rejection(&snapshot), SnapshotError::Decompression));
+    }
+
+    #[test]
+    fn queues_collision_events() {
+        let mut world = World::new(0.0, -9.81, 0.0);
+        let ground = world.create_fixed_body();
+        let ground_collider = world
+            .add_box_collider(
+                ground, 10.0, 0.5, 10.0, false, None, None, None, None, None, None,
+            )
+            .unwrap();
+        let body = world.create_dynamic_body();
+        world.set_body_translation(body, 0.0, 1.5, 0.0, true);
+        let collider = world
+            .add_box_collider(
+                body, 0.5, 0.5, 0.5, false, None, None, None, None, None, None,
+            )
+            .unwrap();
+        world.set_collider_active_events(collider, ActiveEvents::COLLISION_EVENTS.bits());
+
+        let mut events = Vec::new();
+        for _ in 0..120 {
+            world.step(1.0 / 60.0);
+            events.extend(world.drain_collision_events());
+        }
+        let started = events
+            .chunks(5)
+            .find(|event| event[2] as u32 & COLLISION_EVENT_STARTED != 0)
+            .expect("no collision started");
+        let mut pair = [started[0].to_bits(), started[1].to_bits()];
+        pair.sort();
+        let mut expected = [ground_collider.to_bits(), collider.to_bits()];
+        expected.sort();
+        assert_eq!(pair, expected);
+        assert!(world.drain_collision_events().is_empty());
+    }
+
+    #[test]
+    fn spawn_pose_sees_colliders_added_this_frame() {
+        let mut world = World::new(0.0, -9.81, 0.0);
+        let min = Point::new(-4.0, 0.0, -1.0);
+        let max = Point::new(4.0, 2.0, 1.0);
+        let first = world
+            .find_free_spawn_pose(&min, &max, 1.0, 1.0, None)
+            .expect("no free pose");
+        let body = world.create_dynamic_body();
+        world.set_body_translation(body, first.x, first.y, first.z, true);
+        world.add_cylinder_collider(body, 1.0, 1.0, false, None, None, None, None, None, None);
+        let second = world
+            .find_free_spawn_pose(&min, &max, 1.0, 1.0, None)
+            .expect("no free pose");
+        assert_ne!(first, second);
+    }
+
+    #[test]
+    fn spawn_pose_search_is_bounded() {
+        let mut world = World::new(0.0, -9.81, 0.0);
+        let ground = world.create_fixed_body();
+        world.add_box_collider(
+            ground, 1.0e4, 1.0e4, 1.0e4, false, None, None, None, None, None, None,
+        );
+        let min = Point::new(-1.0e3, -1.0e3, -1.0e3);
+        let max = Point::new(1.0e3, 1.0e3, 1.0e3);
+        assert!(spawn_grid_coordinates(min.x, max.x, 0.01).len() <= MAX_SPAWN_CANDIDATES);
+        assert!(
+            world
+                .find_free_spawn_pose(&min, &max, 0.01, 0.01, None)
+                .is_none()
+        );
+    }
+
+    #[test]
+    fn removal_drops_hook_state_only_when_it_succeeds() {
+        let mut world = World::new(0.0, -9.81, 0.0);
+        let body = world.create_dynamic_body();
+        let collider = world
+            .add_box_collider(
+                body, 0.5, 0.5, 0.5, false, None, None, None, None, None, None,
+            )
+            .unwrap();
+        world.set_collider_one_way_direction(collider, 0.0, 1.0, 0.0, 0.5);
+        assert!(!world.remove_collider(encode_handle_for_js(99, 0), true));
+        assert_eq!(world.physics_hooks.one_way_surfaces.len(), 1);
+        assert!(world.remove_rigid_body(body, true));
+        assert!(world.physics_hooks.one_way_surfaces.is_empty());
+        assert!(!world.remove_rigid_body(body, true));
+    }
+
+    #[test]
+    fn create_body_rejects_invalid_descriptors() {
+        let mut world = World::new(0.0, -9.81, 0.0);
+        assert!(world.create_body(&[0.0]).is_some());
+        assert!(world.create_body(&[1.0]).is_some());
+        assert!(world.create_body(&[4.0]).is_none());
+        let mut desc = vec![0.0; 22];
+        desc[21] = MAX_JS_USER_DATA as f64 + 1.0;
+        assert!(world.create_body(&desc).is_none());
+        desc[21] = 0.5;
+        assert!(world.create_body(&desc).is_none());
+    }
+
+    #[test]
+    fn colliders_attach_only_to_existing_bodies() {
+        let mut world = World::new(0.0, -9.81, 0.0);
+        let body = world.create_dynamic_body();
+        let ball = world
+            .add_ball_collider(body, 0.5, false, None, None, None, None, None, None)
+            .unwrap();
+        assert_eq!(world.get_collider_shape_type(ball), Some(0));
+        assert!(world.remove_rigid_body(body, true));
+        assert!(
+            world
+                .add_box_collider(
+                    body, 0.5, 0.5, 0.5, false, None, None, None, None, None, None
+                )
+                .is_none()
+        );
+        assert!(
+            world
+                .add_heightfield_collider(
+                    body,
+                    2,
+                    2,
+                    vec![0.0; 4],
+                    1.0,
+                    1.0,
+                    1.0,
+                    false,
+                    None,
+                    None,
+                    None,
+                    None,
+                    None,
+                    None,
+                )
+                .is_none()
+        );
+    }
+
+    #[test]
+    fn joint_breaks_on_angular_impulse() {
+        let mut world = World::new(0.0, -9.81, 0.0);
+        let anchor = world.create_fixed_body();
+        let body = world.create_dynamic_body();
+        world.set_body_translation(body, 1.0, 0.0, 0.0, true);
+        world
+            .add_box_collider(
+                body, 0.5, 0.5, 0.5, false, None, None, None, None, None, None,
+            )
+            .unwrap();
+        let joint = world
+            .create_fixed_joint(
+                anchor, body, 0.0, 0.0, 0.0, -1.0, 0.0, 0.0, 0.0, 0.0, 0.0, 1.0, 0.0, 0.0, 0.0,
+                1.0, true,
+            )
+            .unwrap();
+        assert!(world.set_joint_break_threshold(joint, None, Some(0.01)));
+        world.step(1.0 / 60.0);
+        let broken = world.drain_broken_joints();
+        assert_eq!(broken.len(), 5);
+        assert_eq!(broken[0].to_bits(), joint.to_bits());
+        assert!(broken[4] > 0.01);
+        assert!(world.get_joint_impulses(joint).is_none());
+    }
+
+    #[test]
+    fn joint_still_breaks_after_restore() {
+        let mut world = World::new(0.0, -9.81, 0.0);
+        let anchor = world.create_fixed_body();
+        let body = world.create_dynamic_body();
+        world
+            .add_box_collider(
+                body, 0.5, 0.5, 0.5, false, None, None, None, None, None, None,
+            )
+            .unwrap();
+        let joint = world
+            .create_spherical_joint(anchor, body, 0.0, 0.0, 0.0, 0.0, 1.0, 0.0, true)
+            .unwrap();
+        assert!(world.set_joint_break_threshold(joint, Some(0.01), None));
+        let snapshot = world.take_snapshot(SnapshotCompression::None);
+
+        let mut restored = World::new(0.0, -9.81, 0.0);
+        restored.restore_snapshot(&snapshot, None).unwrap();
+        restored.step(1.0 / 60.0);
+        let broken = restored.drain_broken_joints();
+        assert_eq!(broken.len(), 5);
+        assert_eq!(broken[0].to_bits(), joint.to_bits());
+        assert!(restored.get_joint_impulses(joint).is_none());
+    }
+
+    fn sample_world() -> (World, f64, Vec<f64>) {
+        let mut world = World::new(0.0, -9.81, 0.0);
+        let ground = world.create_fixed_body();
+        let ground_collider = world
+            .add_box_collider(
+                ground, 10.0, 0.5, 10.0, false, None, None, None, None, None, None,
+            )
+            .unwrap();
+        let balls: Vec<f64> = (0..3)
+            .map(|i| {
+                let body = world.create_dynamic_body();
+                world.set_body_translation(body, i as Real * 2.0, 1.5, 0.0, true);
+                world
+                    .add_ball_collider(body, 0.5, false, None, None, None, None, None, None)
+                    .unwrap()
+            })
+            .collect();
+        (world, ground_collider, balls)
+    }
+
+    #[test]
+    fn sets_round_trip_through_their_layouts() {
+        let (mut world, _, balls) = sample_world();
+        // Leaves a free slot in both arenas.
+        let ball = world.get_collider_parent(balls[0]).unwrap();
+        world.remove_rigid_body(ball, true);
+        world.step(1.0 / 60.0);
+
+        let bodies: RigidBodySetLayout<RigidBody> = reencode(&world.rigid_body_set).unwrap();
+        let bodies: RigidBodySet = reencode(&bodies).unwrap();
+        assert_eq!(
+            serialize(&bodies).unwrap(),
+            serialize(&world.rigid_body_set).unwrap()
+        );
+        let colliders: ColliderSetLayout<Collider> = reencode(&world.collider_set).unwrap();
+        let colliders: ColliderSet = reencode(&colliders).unwrap();
+        assert_eq!(
+            serialize(&colliders).unwrap(),
+            serialize(&world.collider_set).unwrap()
+        );
+    }
+
+    #[test]
+    fn restores_dynamic_snapshot_against_its_scene() {
+        let (mut world, ground_collider, _) = sample_world();
+        let scene = world.take_scene_snapshot(SnapshotCompression::Zstd);
+        for _ in 0..10 {
+            world.step(1.0 / 60.0);
+        }
+        let dynamic = world.take_dynamic_snapshot(SnapshotCompression::Lz4);
+        assert!(dynamic.len() < world.take_snapshot(SnapshotCompression::Lz4).len());
+
+        let mut restored = World::new(0.0, 0.0, 0.0);
+        restored
+            .restore_snapshot(&dynamic, Some(scene.as_slice()))
+            .unwrap();
+        assert_eq!(
+            restored.get_collider_half_extents(ground_collider),
+            Some((10.0, 0.5, 10.0))
+        );
+        assert_eq!(
+            restored.get_body_translations(),
+            world.get_body_translations()
+        );
+        // The rebuilt broad phase may report pairs in another order, so the worlds only have to
+        // stay close.
+        for _ in 0..10 {
+            world.step(1.0 / 60.0);
+            restored.step(1.0 / 60.0);
+        }
+        for (a, b) in restored
+            .get_body_translations()
+            .iter()
+            .zip(world.get_body_translations())
+        {
+            assert!((a - b).abs() < 1e-4);
+        }
+
+        let error = restored.restore_snapshot(&dynamic, None).unwrap_err();
+        assert!(matches!(error.error, SnapshotError::SceneRequired));
+
+        let (mut other, _, _) = sample_world();
+        let ground = other.get_body_handles()[0];
+        other.add_ball_collider(ground, 2.0, false, None, None, None, None, None, None);
+        let other_scene = other.take_scene_snapshot(SnapshotCompression::None);
+        let error = restored
+            .restore_snapshot(&dynamic, Some(other_scene.as_slice()))
+            .unwrap_err();
+        assert!(error.reference);
+        assert!(matches!(error.error, SnapshotError::SceneMismatch { .. }));
+
+        let error = restored.restore_snapshot(&scene, None).unwrap_err();
+        assert!(matches!(
+            error.error,
+            SnapshotError::UnexpectedKind(SCENE_SNAPSHOT_MAGIC)
+        ));
+    }
+
+    #[test]
+    fn delta_snapshot_restores_bodies_bit_for_bit() {
+        let (mut world, _, balls) = sample_world();
+        let base = world.take_base_snapshot(SnapshotCompression::None);
+        let base_id = SnapshotHeader::read(&base).unwrap().crc32;
+        for _ in 0..10 {
+            world.step(1.0 / 60.0);
+        }
+        // The added body takes the slot of the removed one, with the next generation.
+        let removed = world.get_collider_parent(balls[0]).unwrap();
+        world.remove_rigid_body(removed, true);
+        let added = world.create_dynamic_body();
+        world.set_body_translation(added, 0.0, 4.0, 0.0, true);
+        world
+            .add_ball_collider(added, 0.25, false, None, None, None, None, None, None)
+            .unwrap();
+        world.step(1.0 / 60.0);
+        let delta = world
+            .take_delta_snapshot(base_id, SnapshotCompression::None)
+            .unwrap();
+        assert!(delta.len() < base.len());
+
+        let mut restored = World::new(0.0, 0.0, 0.0);
+        restored.restore_snapshot(&delta, Some(&base[..])).unwrap();
+        let states = |world: &World| {
+            world
+                .rigid_body_set
+                .iter()
+                .map(|(handle, body)| (handle, BodyState::of(body)))
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(states(&restored), states(&world));
+        let colliders = |world: &World| {
+            world
+                .collider_set
+                .iter()
+                .map(|(handle, collider)| (handle, collider.parent()))
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(colliders(&restored), colliders(&world));
+
+        let error = restored.restore_snapshot(&delta, None).unwrap_err();
+        assert!(matches!(error.error, SnapshotError::BaseRequired));
+        let other_base = world.take_base_snapshot(SnapshotCompression::None);
+        let error = restored
+            .apply_delta_snapshot(&delta, &other_base)
+            .unwrap_err();
+        assert!(error.reference);
+        assert!(matches!(error.error, SnapshotError::BaseMismatch { .. }));
+
+        assert!(world.release_base_snapshot(base_id));
+        assert!(
+            world
+                .take_delta_snapshot(base_id, SnapshotCompression::None)
+                .is_none()
+        );
+    }
+
+    #[test]
+    fn json_export_import_is_stable() {
+        let (mut world, ground_collider, balls) = sample_world();
+        let ball = world.get_collider_parent(balls[0]).unwrap();
+        let other_ball = world.get_collider_parent(balls[1]).unwrap();
+        let joint = world
+            .create_spherical_joint(ball, other_ball, 0.0, 0.0, 0.0, -2.0, 0.0, 0.0, true)
+            .unwrap();
+        assert!(world.set_joint_break_threshold(joint, Some(5.0), None));
+        assert!(world.set_collider_mass(balls[0], 2.0));
+        assert!(world.set_collider_contact_skin(balls[1], 0.01));
+        assert!(world.set_body_additional_mass(ball, 1.0, true));
+        assert!(world.set_body_soft_ccd_prediction(other_ball, 0.5));
+        assert!(world.set_collider_one_way_direction(ground_collider, 0.0, 1.0, 0.0, 0.5));
+        world.set_collider_pair_ignored(balls[2], balls[0], true);
+        let json = world.export_world_json().unwrap();
+
+        let mut imported = World::new(0.0, 0.0, 0.0);
+        imported.import_world_json(&json).unwrap();
+        assert_eq!(imported.get_body_count(), 4);
+        assert_eq!(imported.collider_set.len(), 4);
+        assert_eq!(imported.impulse_joint_set.len(), 1);
+        assert_eq!(imported.joint_break_thresholds.len(), 1);
+        assert_eq!(imported.physics_hooks.ignored_pairs.len(), 1);
+        assert_eq!(imported.export_world_json().unwrap(), json);
+    }
+
+    #[test]
+    fn json_import_rejects_invalid_worlds() {
+        let (world, _, _) = sample_world();
+        let json: serde_json::Value =
+            serde_json::from_str(&world.export_world_json().unwrap()).unwrap();
+        let edited = |edit: fn(&mut serde_json::Value)| {
+            let mut json = json.clone();
+            edit(&mut json);
+            json.to_string()
+        };
+        let mut imported = World::new(0.0, 0.0, 0.0);
+        imported.create_dynamic_body();
+
+        let invalid = [
+            edited(|json| json["version"] = 2.into()),
+            edited(|json| json["bodies"][1]["id"] = 0.into()),
+            edited(|json| json["colliders"][1]["body"] = 99.into()),
+            edited(|json| json["colliders"][1]["mass"] = 2.0.into()),
+            edited(|json| json["ignored_pairs"] = serde_json::json!([[0, 99]])),
+            edited(|json| json["bodies"][0]["user_data"] = (MAX_JS_USER_DATA as u64 + 1).into()),
+            edited(|json| json["colliders"][0]["user_data"] = (MAX_JS_USER_DATA as u64 + 1).into()),
+        ];
+        for json in invalid {
+            assert!(imported.import_world_json(&json).is_err(), "{json}");
+            assert_eq!(imported.get_body_count(), 1);
+        }
+    }
+
+    #[test]
+    fn multibody_links_cannot_close_a_loop() {
+        let mut world = World::new(0.0, -9.81, 0.0);
+        let root = world.create_fixed_body();
+        let link = world.create_dynamic_body();
+        let joint = world.create_multibody_prismatic_joint(
+            root, link, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0, 1.0, 0.0, true,
+        );
+        assert_eq!(joint.map(f64::to_bits), Some(link.to_bits()));
+        assert!(
+            world
+                .create_multibody_prismatic_joint(
+                    link, root, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0, 1.0, 0.0, true,
+                )
+                .is_none()
+        );
+    }
+
+    #[test]
+    fn user_data_must_fit_a_js_number() {
+        assert_eq!(user_data_from_js(42.0), Some(42));
+        assert_eq!(
+            user_data_from_js(MAX_JS_USER_DATA as f64),
+            Some(MAX_JS_USER_DATA)
+        );
+        assert_eq!(user_data_from_js(MAX_JS_USER_DATA as f64 + 1.0), None);
+        assert_eq!(user_data_from_js(-1.0), None);
+        assert_eq!(user_data_from_js(0.5), None);
+        assert_eq!(user_data_from_js(f64::NAN), None);
+    }
+
+    #[test]
+    fn writes_body_poses_in_place() {
+        let mut world = World::new(0.0, -9.81, 0.0);
+        world.create_fixed_body();
+        let body = world.create_dynamic_body();
+        world.set_body_translation(body, 1.0, 2.0, 3.0, true);
+
+        assert_eq!(world.write_body_poses(&mut [0.0; 13]), None);
+        let mut poses = [0.0; 14];
+        assert_eq!(world.write_body_poses(&mut poses), Some(2));
+        assert_eq!(poses[7..], [1.0, 2.0, 3.0, 0.0, 0.0, 0.0, 1.0]);
+    }
+
+    #[test]
+    fn active_body_poses_report_bodies_that_fell_asleep() {
+        let mut world = World::new(0.0, 0.0, 0.0);
+        let body = world.create_dynamic_body();
+        world.create_fixed_body();
+        world.step(1.0 / 60.0);
+
+        let poses = world.get_active_body_poses();
+        assert_eq!(poses.len(), 9);
+        assert_eq!(poses[0], 1.0);
+        assert_eq!(poses[1].to_bits(), body.to_bits());
+        assert!(world.get_active_body_poses()[9..].is_empty());
+
+        world.body_sleep(body);
+        world.step(1.0 / 60.0);
+        let poses = world.get_active_body_poses();
+        assert_eq!(poses.len(), 2);
+        assert_eq!(poses[0], 0.0);
+        assert_eq!(poses[1].to_bits(), body.to_bits());
+        assert_eq!(world.get_active_body_poses(), [0.0]);
+    }
+}